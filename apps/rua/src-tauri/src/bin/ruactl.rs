@@ -1,5 +1,5 @@
 #[warn(dead_code)]
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::{
   fs::{self, File},
   io::{BufRead, BufReader, Read, Write},
@@ -8,10 +8,26 @@ use std::{
 };
 
 use regex::Regex;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
 
-const SERVER_URL: &str = "http://127.0.0.1:7777";
+#[path = "../control_paths.rs"]
+mod control_paths;
+#[path = "../diagnostic_checks.rs"]
+mod diagnostic_checks;
+#[allow(dead_code)]
+#[path = "../linux/display_server.rs"]
+mod display_server;
+
+use control_paths::{
+  control_discovery_path, control_runtime_dir, control_socket_path, control_token_path,
+};
+use diagnostic_checks::{DEPENDENCIES, ENVIRONMENT_VARS, TERMINAL_EMULATORS};
+use display_server::detect_display_server;
+
+const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:7777";
+/// Env var overriding the control server address (`host:port` or just a port)
+const CONTROL_ADDR_ENV: &str = "RUA_CONTROL_ADDR";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Default ignore patterns when no .ruaignore file exists
@@ -35,6 +51,8 @@ const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
 struct Response {
   success: bool,
   message: String,
+  #[serde(default)]
+  data: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -74,52 +92,479 @@ struct FileInfo {
   size: u64,
 }
 
-fn send_request(endpoint: &str) -> Result<Response, Box<dyn std::error::Error>> {
-  let url = format!("{}{}", SERVER_URL, endpoint);
+/// Read the bearer token the running app wrote at startup
+fn read_control_token() -> Result<String, Box<dyn std::error::Error>> {
+  let token_path = control_token_path();
+  let token = fs::read_to_string(&token_path).map_err(|e| {
+    format!(
+      "Failed to read control token {}: {}",
+      token_path.display(),
+      e
+    )
+  })?;
+  Ok(token.trim().to_string())
+}
+
+/// Endpoints written by the running app to `control.json`
+#[derive(Debug, Deserialize)]
+struct ControlDiscovery {
+  pid: u32,
+  url: Option<String>,
+  socket: Option<PathBuf>,
+  #[serde(default)]
+  errors: Vec<String>,
+}
+
+/// Where to reach the control server
+struct ControlEndpoints {
+  url: String,
+  #[cfg(unix)]
+  socket: Option<PathBuf>,
+}
+
+#[cfg(unix)]
+fn is_process_alive(pid: u32) -> bool {
+  // SAFETY: signal 0 only checks whether the process exists
+  unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_process_alive(_pid: u32) -> bool {
+  true
+}
+
+/// Read the discovery file, ignoring one left behind by an exited process
+fn read_control_discovery() -> Option<ControlDiscovery> {
+  let content = fs::read_to_string(control_discovery_path()).ok()?;
+  serde_json::from_str::<ControlDiscovery>(&content)
+    .ok()
+    .filter(|discovery| is_process_alive(discovery.pid))
+}
+
+/// Resolve the control server endpoints
+///
+/// `RUA_CONTROL_ADDR` overrides the TCP address; otherwise the endpoints
+/// from the discovery file are used, falling back to the defaults.
+fn resolve_endpoints() -> Result<ControlEndpoints, Box<dyn std::error::Error>> {
+  let discovery = read_control_discovery();
+
+  if let Some(discovery) = &discovery {
+    if discovery.url.is_none() && discovery.socket.is_none() {
+      return Err(
+        format!(
+          "rua is running but its control server failed to start:\n  {}",
+          discovery.errors.join("\n  ")
+        )
+        .into(),
+      );
+    }
+  }
+
+  let url = match std::env::var(CONTROL_ADDR_ENV)
+    .ok()
+    .and_then(|addr| control_addr_url(&addr))
+  {
+    Some(url) => url,
+    None => discovery
+      .as_ref()
+      .and_then(|d| d.url.clone())
+      .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string()),
+  };
+
+  Ok(ControlEndpoints {
+    url,
+    #[cfg(unix)]
+    socket: discovery
+      .and_then(|d| d.socket)
+      .or_else(control_socket_path)
+      .filter(|p| p.exists()),
+  })
+}
+
+/// The URL for a `RUA_CONTROL_ADDR` value
+///
+/// Returns `None` for port 0, where the app picked a free port that only the discovery file knows.
+fn control_addr_url(addr: &str) -> Option<String> {
+  let addr = addr.trim();
+  let port = addr.rsplit(':').next().unwrap_or(addr);
+  if port.parse::<u16>() == Ok(0) {
+    return None;
+  }
+  if addr.parse::<u16>().is_ok() {
+    Some(format!("http://127.0.0.1:{}", addr))
+  } else {
+    Some(format!("http://{}", addr))
+  }
+}
+
+/// Report a failure to reach the control server and exit
+fn exit_connection_error(e: Box<dyn std::error::Error>) -> ! {
+  eprintln!("Failed to connect to rua: {}", e);
+  // Only suggest starting rua when no running instance wrote a discovery file
+  if read_control_discovery().is_none() {
+    eprintln!("Make sure rua is running.");
+  }
+  process::exit(1);
+}
+
+/// Send a request to the control server, preferring the Unix socket and falling back to TCP
+fn send_request<T: DeserializeOwned>(
+  endpoint: &str,
+  body: Option<&serde_json::Value>,
+) -> Result<T, Box<dyn std::error::Error>> {
+  let endpoints = resolve_endpoints()?;
+  let token = read_control_token()?;
+
+  #[cfg(unix)]
+  if let Some(socket_path) = &endpoints.socket {
+    match std::os::unix::net::UnixStream::connect(socket_path) {
+      Ok(stream) => return send_unix_request(stream, endpoint, body, &token),
+      // A refused connection means a stale socket left behind by an exited app
+      Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+      Err(e) => eprintln!("Control socket unavailable ({}), falling back to TCP", e),
+    }
+  }
+
+  send_tcp_request(&endpoints.url, endpoint, body, &token)
+}
+
+fn send_tcp_request<T: DeserializeOwned>(
+  server_url: &str,
+  endpoint: &str,
+  body: Option<&serde_json::Value>,
+  token: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+  let url = format!("{}{}", server_url, endpoint);
   let client = reqwest::blocking::Client::new();
-  let response = client.post(&url).send()?;
-  let response_data: Response = response.json()?;
+  let mut request = client.post(&url).bearer_auth(token);
+  if let Some(body) = body {
+    request = request.json(body);
+  }
+  let response = request.send()?;
+  let status = response.status();
+  let response_data: T = response
+    .json()
+    .map_err(|e| format!("Unexpected response ({}): {}", status, e))?;
   Ok(response_data)
 }
 
+/// Blocking reader over a response body received on the control socket
+///
+/// Owns the runtime driving the connection, which only makes progress while the
+/// body is being read.
+#[cfg(unix)]
+struct UnixBody {
+  runtime: tokio::runtime::Runtime,
+  body: hyper::body::Incoming,
+  chunk: hyper::body::Bytes,
+}
+
+#[cfg(unix)]
+impl Read for UnixBody {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    use http_body_util::BodyExt;
+
+    while self.chunk.is_empty() {
+      match self.runtime.block_on(self.body.frame()) {
+        Some(Ok(frame)) => {
+          if let Ok(data) = frame.into_data() {
+            self.chunk = data;
+          }
+        }
+        Some(Err(e)) => return Err(std::io::Error::other(e)),
+        None => return Ok(0),
+      }
+    }
+
+    let n = buf.len().min(self.chunk.len());
+    buf[..n].copy_from_slice(&self.chunk[..n]);
+    self.chunk = self.chunk.slice(n..);
+    Ok(n)
+  }
+}
+
+/// Send an HTTP/1.1 request over the control socket
+///
+/// Returns the response status and a reader over the body.
+#[cfg(unix)]
+fn unix_http_request(
+  stream: std::os::unix::net::UnixStream,
+  method: hyper::Method,
+  endpoint: &str,
+  body: Option<&serde_json::Value>,
+  token: &str,
+) -> Result<(hyper::StatusCode, UnixBody), Box<dyn std::error::Error>> {
+  use http_body_util::Full;
+  use hyper::{body::Bytes, header, Request};
+  use hyper_util::rt::TokioIo;
+
+  let body = match body {
+    Some(body) => serde_json::to_vec(body)?,
+    None => Vec::new(),
+  };
+  let request = Request::builder()
+    .method(method)
+    .uri(endpoint)
+    .header(header::HOST, "localhost")
+    .header(header::AUTHORIZATION, format!("Bearer {}", token))
+    .header(header::CONTENT_TYPE, "application/json")
+    .body(Full::new(Bytes::from(body)))?;
+
+  let runtime = tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()?;
+  let response = runtime.block_on(async {
+    stream.set_nonblocking(true)?;
+    let stream = tokio::net::UnixStream::from_std(stream)?;
+    let (mut sender, connection) =
+      hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(async move {
+      if let Err(e) = connection.await {
+        eprintln!("Control socket connection error: {}", e);
+      }
+    });
+    Ok::<_, Box<dyn std::error::Error>>(sender.send_request(request).await?)
+  })?;
+
+  let status = response.status();
+  let body = UnixBody {
+    runtime,
+    body: response.into_body(),
+    chunk: Bytes::new(),
+  };
+  Ok((status, body))
+}
+
+#[cfg(unix)]
+fn send_unix_request<T: DeserializeOwned>(
+  stream: std::os::unix::net::UnixStream,
+  endpoint: &str,
+  body: Option<&serde_json::Value>,
+  token: &str,
+) -> Result<T, Box<dyn std::error::Error>> {
+  let (status, mut reader) = unix_http_request(stream, hyper::Method::POST, endpoint, body, token)?;
+
+  let mut body = Vec::new();
+  reader.read_to_end(&mut body)?;
+
+  serde_json::from_slice(&body)
+    .map_err(|e| format!("Unexpected response ({}): {}", status, e).into())
+}
+
+/// Open the control server event stream, preferring the Unix socket
+fn open_event_stream() -> Result<Box<dyn BufRead>, Box<dyn std::error::Error>> {
+  let endpoints = resolve_endpoints()?;
+  let token = read_control_token()?;
+
+  #[cfg(unix)]
+  if let Some(socket_path) = &endpoints.socket {
+    match std::os::unix::net::UnixStream::connect(socket_path) {
+      Ok(stream) => {
+        let (status, mut reader) =
+          unix_http_request(stream, hyper::Method::GET, "/events", None, &token)?;
+        if !status.is_success() {
+          let mut body = Vec::new();
+          reader.read_to_end(&mut body)?;
+          let message = serde_json::from_slice::<Response>(&body)
+            .map(|r| r.message)
+            .unwrap_or_else(|_| status.to_string());
+          return Err(message.into());
+        }
+        return Ok(Box::new(BufReader::new(reader)));
+      }
+      // A refused connection means a stale socket left behind by an exited app
+      Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+      Err(e) => eprintln!("Control socket unavailable ({}), falling back to TCP", e),
+    }
+  }
+
+  // The default blocking client times out after 30s, which would cut the stream
+  let client = reqwest::blocking::Client::builder().timeout(None).build()?;
+  let response = client
+    .get(format!("{}/events", endpoints.url))
+    .bearer_auth(&token)
+    .send()?;
+
+  if !response.status().is_success() {
+    let status = response.status();
+    let message = response
+      .json::<Response>()
+      .map(|r| r.message)
+      .unwrap_or_else(|_| status.to_string());
+    return Err(message.into());
+  }
+
+  Ok(Box::new(BufReader::new(response)))
+}
+
+/// Call `on_event` with the data of each Server-Sent Event until the stream
+/// closes, then exit with an error
+fn read_event_stream(reader: Box<dyn BufRead>, mut on_event: impl FnMut(&str)) -> ! {
+  let mut data = String::new();
+
+  for line in reader.lines() {
+    let line = match line {
+      Ok(l) => l,
+      Err(e) => {
+        eprintln!("✗ Event stream error: {}", e);
+        process::exit(1);
+      }
+    };
+
+    if let Some(value) = line.strip_prefix("data:") {
+      data.push_str(value.trim_start());
+    } else if line.is_empty() && !data.is_empty() {
+      on_event(&data);
+      data.clear();
+    }
+  }
+
+  eprintln!("Event stream closed");
+  process::exit(1);
+}
+
+/// Print control server events as JSON lines
+///
+/// Without `follow`, only the current state is printed.
+fn events(follow: bool) {
+  let reader = match open_event_stream() {
+    Ok(r) => r,
+    Err(e) => exit_connection_error(e),
+  };
+
+  let stdout = std::io::stdout();
+  read_event_stream(reader, |data| {
+    let mut out = stdout.lock();
+    let _ = writeln!(out, "{}", data);
+    let _ = out.flush();
+
+    if !follow {
+      process::exit(0);
+    }
+  });
+}
+
 fn toggle() {
-  match send_request("/toggle") {
+  exit_with_response(send_request("/toggle", None));
+}
+
+fn health() {
+  match send_request::<Response>("/health", None) {
     Ok(resp) => {
       if resp.success {
-        println!("{}", resp.message);
+        println!("✓ {}", resp.message);
         process::exit(0);
       } else {
         eprintln!("Error: {}", resp.message);
         process::exit(1);
       }
     }
-    Err(e) => {
-      eprintln!("Failed to connect to rua: {}", e);
-      eprintln!("Make sure rua is running.");
-      process::exit(1);
-    }
+    Err(e) => exit_connection_error(e),
   }
 }
 
-fn health() {
-  match send_request("/health") {
+/// Print a control server response and exit with a matching status code
+fn exit_with_response(result: Result<Response, Box<dyn std::error::Error>>) -> ! {
+  match result {
     Ok(resp) => {
       if resp.success {
-        println!("✓ {}", resp.message);
+        println!("{}", resp.message);
         process::exit(0);
       } else {
         eprintln!("Error: {}", resp.message);
         process::exit(1);
       }
     }
-    Err(e) => {
-      eprintln!("Failed to connect to rua: {}", e);
-      eprintln!("Make sure rua is running.");
-      process::exit(1);
+    Err(e) => exit_connection_error(e),
+  }
+}
+
+/// Show the window, optionally with a pre-filled query and a target view
+fn show(query: Option<&str>, view: Option<&str>) {
+  let body = serde_json::json!({
+    "query": query,
+    "view": view,
+  });
+  exit_with_response(send_request("/show", Some(&body)));
+}
+
+fn hide() {
+  exit_with_response(send_request("/hide", None));
+}
+
+/// Run an extension action and print its result as JSON
+fn run(action_id: &str, args: &[(String, String)]) {
+  let args: serde_json::Map<String, serde_json::Value> = args
+    .iter()
+    .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+    .collect();
+  let body = serde_json::json!({
+    "actionId": action_id,
+    "args": args,
+  });
+
+  match send_request::<Response>("/actions/run", Some(&body)) {
+    Ok(resp) => {
+      let output = serde_json::json!({
+        "success": resp.success,
+        "message": resp.message,
+        "result": resp.data,
+      });
+      let output = serde_json::to_string_pretty(&output).unwrap_or_default();
+      if resp.success {
+        println!("{}", output);
+        process::exit(0);
+      } else {
+        eprintln!("{}", output);
+        process::exit(1);
+      }
     }
+    Err(e) => exit_connection_error(e),
   }
 }
 
+/// Call a backend command through the JSON-RPC bridge and print its result
+fn rpc(method: &str, params: Option<&str>) {
+  let params: serde_json::Value = match params {
+    Some(params) => match serde_json::from_str(params) {
+      Ok(value @ serde_json::Value::Object(_)) => value,
+      _ => {
+        eprintln!("✗ Params must be a JSON object, e.g. '{{\"path\": \"/tmp\"}}'");
+        process::exit(1);
+      }
+    },
+    None => serde_json::json!({}),
+  };
+  let body = serde_json::json!({
+    "jsonrpc": "2.0",
+    "id": 1,
+    "method": method,
+    "params": params,
+  });
+
+  let response: serde_json::Value = match send_request("/rpc", Some(&body)) {
+    Ok(resp) => resp,
+    Err(e) => exit_connection_error(e),
+  };
+
+  if let Some(result) = response.get("result") {
+    println!(
+      "{}",
+      serde_json::to_string_pretty(result).unwrap_or_default()
+    );
+    process::exit(0);
+  }
+
+  // Either a JSON-RPC error or a control server error (e.g. invalid token)
+  let message = response
+    .pointer("/error/message")
+    .or_else(|| response.get("message"))
+    .and_then(|m| m.as_str())
+    .unwrap_or("Unexpected response");
+  eprintln!("✗ {}", message);
+  process::exit(1);
+}
+
 fn print_usage() {
   println!("ruactl - Control utility for Rua");
   println!();
@@ -128,19 +573,46 @@ fn print_usage() {
   println!();
   println!("COMMANDS:");
   println!("    toggle              Toggle window visibility");
+  println!("    show                Show window (see --query, --view)");
+  println!("    hide                Hide window");
+  println!("    run <action-id>     Run an extension action (see --arg)");
+  println!("    events              Print app events as JSON lines (see --follow)");
+  println!(
+    "    rpc <method> [json] Call a backend command, e.g. rpc fs_exists '{{\"path\":\"/tmp\"}}'"
+  );
   println!("    health              Check if Rua is running");
+  println!("    doctor              Check the environment and external dependencies");
+  println!("    bugreport           Zip diagnostics, redacted preferences and logs (see --output)");
   println!("    pack [path]         Package extension into .rua format");
   println!("    validate [path]     Validate extension manifest");
-  println!("    install <source>    Install extension from GitHub or local .rua file");
+  println!("    dev [path]          Develop an extension with hot reload (see --stop)");
+  println!("    install <source>    Install extension from GitHub, a .rua file or a directory");
+  println!("    ext list            List installed extensions");
+  println!("    ext info <id>       Show extension details");
+  println!("    ext enable <id>     Enable an extension");
+  println!("    ext disable <id>    Disable an extension");
+  println!("    ext uninstall <id>  Uninstall an extension");
+  println!("    ext update <id>     Reinstall an extension from its original source");
   println!("    help                Print this help message");
   println!();
   println!("INSTALL SOURCES:");
   println!("    github:owner/repo   Install latest release from GitHub");
   println!("    github:owner/repo@v1.0.0  Install specific version from GitHub");
   println!("    /path/to/ext.rua    Install from local .rua file");
+  println!("    /path/to/ext/       Install from an extension directory");
   println!();
   println!("OPTIONS:");
+  println!("    --query <text>      (show) Pre-fill the search box");
+  println!("    --view <action-id>  (show) Open an action view, e.g. ext.id.action");
+  println!("    --arg <key=value>   (run) Pass an argument to the action, repeatable");
+  println!("    --json              (ext list/info) Print JSON");
+  println!("    --offline           (install, ext) Edit the extensions directory without rua");
+  println!("    -f, --follow        (events) Keep streaming instead of printing the current state");
   println!("    --dry-run           (pack) List files without creating archive");
+  println!("    --stop              (dev) Unregister the dev extension");
+  println!(
+    "    -o, --output <file> (bugreport) Archive path, defaults to rua-bugreport-<time>.zip"
+  );
   println!("    -h, --help          Print help information");
   println!("    -v, --version       Print version information");
 }
@@ -396,6 +868,573 @@ fn validate(path: Option<&str>) {
   }
 }
 
+/// An event received from `/events`
+#[derive(Deserialize)]
+struct StreamEvent {
+  event: String,
+  #[serde(default)]
+  payload: serde_json::Value,
+}
+
+/// Dev command: register a directory as the dev extension, then print its
+/// reloads and errors until interrupted
+fn dev(path: Option<&str>) {
+  let dir = PathBuf::from(path.unwrap_or("."));
+  let abs_dir = fs::canonicalize(&dir).unwrap_or(dir.clone());
+
+  let manifest = match parse_manifest(&abs_dir) {
+    Ok(m) => m,
+    Err(errors) => {
+      eprintln!("✗ Manifest validation failed:");
+      for error in errors {
+        eprintln!("  - {}", error);
+      }
+      process::exit(1);
+    }
+  };
+
+  // Subscribe first so events caused by the registration aren't missed
+  let reader = match open_event_stream() {
+    Ok(r) => r,
+    Err(e) => exit_connection_error(e),
+  };
+
+  let body = serde_json::json!({ "path": abs_dir });
+  match send_request::<Response>("/dev", Some(&body)) {
+    Ok(resp) if resp.success => println!("✓ {}", resp.message),
+    Ok(resp) => {
+      eprintln!("✗ {}", resp.message);
+      process::exit(1);
+    }
+    Err(e) => exit_connection_error(e),
+  }
+  println!(
+    "ℹ Watching {} for changes, press Ctrl-C to stop",
+    abs_dir.display()
+  );
+  println!("  The extension stays registered until `ruactl dev --stop`");
+
+  let dev_path = abs_dir.to_string_lossy().to_string();
+  read_event_stream(reader, |data| {
+    let Ok(event) = serde_json::from_str::<StreamEvent>(data) else {
+      return;
+    };
+    let payload = &event.payload;
+    let extension_id = payload.get("extensionId").and_then(|v| v.as_str());
+    let time = chrono::Local::now().format("%H:%M:%S");
+
+    match event.event.as_str() {
+      "rua://dev-extension-reload" if extension_id == Some(manifest.id.as_str()) => {
+        let changed = payload
+          .get("paths")
+          .and_then(|v| v.as_array())
+          .map_or(0, |paths| paths.len());
+        println!(
+          "[{}] ↻ Reloaded {} ({} files changed)",
+          time, manifest.id, changed
+        );
+      }
+      "rua://dev-extension-error" if extension_id.is_none_or(|id| id == manifest.id) => {
+        let source = payload.get("source").and_then(|v| v.as_str());
+        let message = payload.get("message").and_then(|v| v.as_str());
+        eprintln!(
+          "[{}] ✗ {} error: {}",
+          time,
+          source.unwrap_or("extension"),
+          message.unwrap_or("unknown error")
+        );
+      }
+      "rua://dev-extension-changed"
+        if payload.get("path").and_then(|v| v.as_str()) != Some(dev_path.as_str()) =>
+      {
+        println!("ℹ Dev extension was unregistered or replaced, stopping");
+        process::exit(0);
+      }
+      _ => {}
+    }
+  });
+}
+
+/// Unregister the dev extension
+fn dev_stop() {
+  exit_with_response(send_request("/dev", Some(&serde_json::json!({}))));
+}
+
+/// Preference keys whose values are left out of bug reports
+///
+/// Deliberately broad: `key` and `pass` also cover `openaiKey`, `privateKey`,
+/// `passphrase` and the like, at the cost of some harmless settings.
+const SENSITIVE_KEY_PATTERN: &str = r"(?i)token|secret|key|pass|auth|cookie|credential";
+
+/// An external command and where it was found
+#[derive(Debug, Serialize, Deserialize)]
+struct Dependency {
+  name: String,
+  purpose: String,
+  optional: bool,
+  path: Option<String>,
+}
+
+/// A directory the app reads or writes
+#[derive(Debug, Serialize, Deserialize)]
+struct DirectoryStatus {
+  name: String,
+  path: String,
+  exists: bool,
+  writable: bool,
+}
+
+/// Environment diagnostics, as reported by `/diagnostics`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Diagnostics {
+  version: String,
+  pid: u32,
+  os: String,
+  display_server: Option<String>,
+  terminal_emulator: Option<String>,
+  environment: Vec<(String, Option<String>)>,
+  dependencies: Vec<Dependency>,
+  directories: Vec<DirectoryStatus>,
+  /// Features unavailable in rua's environment, only reported by the app
+  #[serde(default)]
+  warnings: Vec<String>,
+}
+
+/// Resolve an XDG base directory, e.g. `XDG_CONFIG_HOME` or `~/.config`
+fn xdg_base_dir(var: &str, fallback: &str) -> PathBuf {
+  match std::env::var(var).ok().filter(|d| !d.is_empty()) {
+    Some(dir) => PathBuf::from(dir),
+    None => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(fallback),
+  }
+}
+
+/// Check whether a directory accepts new files
+fn is_writable(dir: &Path) -> bool {
+  let probe = dir.join(format!(".rua-write-test-{}", process::id()));
+  let writable = fs::write(&probe, b"").is_ok();
+  let _ = fs::remove_file(&probe);
+  writable
+}
+
+fn directory_status(name: &str, path: &Path) -> DirectoryStatus {
+  DirectoryStatus {
+    name: name.to_string(),
+    path: path.to_string_lossy().to_string(),
+    exists: path.is_dir(),
+    writable: path.is_dir() && is_writable(path),
+  }
+}
+
+/// Find a command on PATH, or check it directly if it is a path
+fn find_command(name: &str) -> Option<String> {
+  let path = Path::new(name);
+  if path.components().count() > 1 {
+    return path.is_file().then(|| name.to_string());
+  }
+  std::env::split_paths(&std::env::var_os("PATH")?)
+    .map(|dir| dir.join(name))
+    .find(|candidate| candidate.is_file())
+    .map(|p| p.to_string_lossy().to_string())
+}
+
+/// Collect diagnostics in ruactl's own process, for when rua isn't running
+fn local_diagnostics() -> Diagnostics {
+  let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+  let mut dependencies: Vec<Dependency> = DEPENDENCIES
+    .iter()
+    .map(|(name, purpose, optional)| Dependency {
+      name: name.to_string(),
+      purpose: purpose.to_string(),
+      optional: *optional,
+      path: find_command(name),
+    })
+    .collect();
+  dependencies.push(Dependency {
+    path: find_command(&shell),
+    name: shell,
+    purpose: "Shell commands".to_string(),
+    optional: false,
+  });
+
+  let linux = cfg!(target_os = "linux");
+  Diagnostics {
+    version: VERSION.to_string(),
+    pid: process::id(),
+    os: std::env::consts::OS.to_string(),
+    display_server: linux.then(|| detect_display_server().as_str().to_string()),
+    terminal_emulator: TERMINAL_EMULATORS
+      .iter()
+      .find(|terminal| linux && find_command(terminal).is_some())
+      .map(|terminal| terminal.to_string()),
+    environment: ENVIRONMENT_VARS
+      .iter()
+      .map(|var| (var.to_string(), std::env::var(var).ok()))
+      .collect(),
+    dependencies,
+    directories: vec![
+      directory_status(
+        "data",
+        &xdg_base_dir("XDG_DATA_HOME", ".local/share").join("like.rua.ai"),
+      ),
+      directory_status(
+        "config",
+        &xdg_base_dir("XDG_CONFIG_HOME", ".config").join("like.rua.ai"),
+      ),
+      directory_status(
+        "cache",
+        &xdg_base_dir("XDG_CACHE_HOME", ".cache").join("rua"),
+      ),
+      directory_status("runtime", &control_runtime_dir()),
+    ],
+    warnings: Vec::new(),
+  }
+}
+
+/// Parse a JSON file from a diagnosed directory for the report
+fn check_json_file(path: &Path) -> Result<Option<serde_json::Value>, String> {
+  match fs::read_to_string(path) {
+    Ok(content) => serde_json::from_str(&content)
+      .map(Some)
+      .map_err(|e| format!("Failed to parse: {}", e)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(format!("Failed to read: {}", e)),
+  }
+}
+
+/// Run all checks and render the report
+///
+/// Returns the report, the diagnostics it was built from and the number of
+/// problems found.
+fn diagnose() -> (String, Diagnostics, usize) {
+  use std::fmt::Write as _;
+
+  let mut report = String::new();
+  let mut problems = 0;
+  let mut mark = |ok: bool, required: bool| match (ok, required) {
+    (true, _) => "✓",
+    (false, true) => {
+      problems += 1;
+      "✗"
+    }
+    (false, false) => "⚠",
+  };
+
+  let _ = writeln!(report, "rua");
+  let _ = writeln!(report, "  {} ruactl {}", mark(true, true), VERSION);
+
+  let discovery = read_control_discovery();
+  let app_diagnostics = match &discovery {
+    Some(discovery) => {
+      let _ = writeln!(
+        report,
+        "  {} rua is running (pid {})",
+        mark(true, true),
+        discovery.pid
+      );
+      for error in &discovery.errors {
+        let _ = writeln!(report, "  {} {}", mark(false, true), error);
+      }
+      match send_request::<Response>("/diagnostics", None) {
+        Ok(resp) if resp.success => resp
+          .data
+          .and_then(|data| serde_json::from_value::<Diagnostics>(data).ok()),
+        Ok(resp) => {
+          let _ = writeln!(
+            report,
+            "  {} Diagnostics: {}",
+            mark(false, true),
+            resp.message
+          );
+          None
+        }
+        Err(e) => {
+          let _ = writeln!(report, "  {} Control server: {}", mark(false, true), e);
+          None
+        }
+      }
+    }
+    None => {
+      let _ = writeln!(report, "  {} rua is not running", mark(false, false));
+      None
+    }
+  };
+
+  let from_app = app_diagnostics.is_some();
+  let diagnostics = app_diagnostics.unwrap_or_else(local_diagnostics);
+  if from_app {
+    let _ = writeln!(report, "  {} rua {}", mark(true, true), diagnostics.version);
+  }
+  for warning in &diagnostics.warnings {
+    let _ = writeln!(report, "  {} {}", mark(false, false), warning);
+  }
+
+  let _ = writeln!(report);
+  let _ = writeln!(
+    report,
+    "Environment ({})",
+    if from_app {
+      "as seen by rua"
+    } else {
+      "as seen by ruactl, may differ from rua's"
+    }
+  );
+  let display_server = diagnostics.display_server.as_deref();
+  if let Some(display_server) = display_server {
+    let _ = writeln!(
+      report,
+      "  {} Display server: {}",
+      mark(display_server != "Unknown", false),
+      display_server
+    );
+  }
+  if diagnostics.os == "linux" {
+    match &diagnostics.terminal_emulator {
+      Some(terminal) => {
+        let _ = writeln!(
+          report,
+          "  {} Terminal emulator: {}",
+          mark(true, true),
+          terminal
+        );
+      }
+      None => {
+        let _ = writeln!(
+          report,
+          "  {} Terminal emulator: none found, terminal applications can't be launched",
+          mark(false, false)
+        );
+      }
+    }
+  }
+  for (var, value) in &diagnostics.environment {
+    let _ = writeln!(
+      report,
+      "    {}={}",
+      var,
+      value.as_deref().unwrap_or("(unset)")
+    );
+  }
+
+  let _ = writeln!(report);
+  let _ = writeln!(report, "Dependencies");
+  for dep in &diagnostics.dependencies {
+    // hyprctl is only needed on Hyprland, where it's used for window control
+    let required = !dep.optional || (dep.name == "hyprctl" && display_server == Some("Hyprland"));
+    let _ = writeln!(
+      report,
+      "  {} {:<14} {:<28} {}",
+      mark(dep.path.is_some(), required),
+      dep.name,
+      dep.path.as_deref().unwrap_or("not found"),
+      dep.purpose
+    );
+  }
+
+  let _ = writeln!(report);
+  let _ = writeln!(report, "Directories");
+  for dir in &diagnostics.directories {
+    let status = match (dir.exists, dir.writable) {
+      (false, _) => " (does not exist yet)",
+      (true, false) => " (not writable)",
+      (true, true) => "",
+    };
+    let _ = writeln!(
+      report,
+      "  {} {:<8} {}{}",
+      mark(dir.writable, dir.exists),
+      dir.name,
+      dir.path,
+      status
+    );
+  }
+
+  let dir_path = |name: &str| {
+    diagnostics
+      .directories
+      .iter()
+      .find(|dir| dir.name == name)
+      .map(|dir| PathBuf::from(&dir.path))
+  };
+  let files = [
+    (
+      "registry.json",
+      dir_path("data").map(|dir| dir.join("extensions/registry.json")),
+    ),
+    (
+      "preferences.json",
+      dir_path("config").map(|dir| dir.join("preferences.json")),
+    ),
+  ];
+  for (name, path) in files {
+    let Some(path) = path else { continue };
+    let (ok, status) = match check_json_file(&path) {
+      Ok(Some(_)) => (true, "valid".to_string()),
+      Ok(None) => (true, "not created yet".to_string()),
+      Err(e) => (false, e),
+    };
+    let _ = writeln!(report, "  {} {}: {}", mark(ok, true), name, status);
+  }
+
+  let _ = writeln!(report);
+  if problems == 0 {
+    let _ = writeln!(report, "No problems found");
+  } else {
+    let _ = writeln!(report, "{} problem(s) found", problems);
+  }
+
+  (report, diagnostics, problems)
+}
+
+/// Doctor command: check the environment and external dependencies
+fn doctor() {
+  let (report, _, problems) = diagnose();
+  print!("{}", report);
+  process::exit(if problems == 0 { 0 } else { 1 });
+}
+
+/// Replace the values of sensitive preference keys, at any depth
+///
+/// The whole value under a sensitive key is replaced, including objects and
+/// arrays, so nested secrets can't slip through under an innocuous name.
+fn redact_preferences(value: &mut serde_json::Value, sensitive: &Regex) {
+  match value {
+    serde_json::Value::Object(map) => {
+      for (key, value) in map.iter_mut() {
+        if sensitive.is_match(key) {
+          if !value.is_null() {
+            *value = serde_json::Value::String("<redacted>".to_string());
+          }
+        } else {
+          redact_preferences(value, sensitive);
+        }
+      }
+    }
+    serde_json::Value::Array(items) => {
+      for item in items {
+        redact_preferences(item, sensitive);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Recent app logs from the user journal
+fn recent_logs() -> String {
+  let output = process::Command::new("journalctl")
+    .args([
+      "--user",
+      "--no-pager",
+      "--output",
+      "short-iso",
+      "--since",
+      "-2h",
+      "--lines",
+      "2000",
+      "_COMM=rua",
+    ])
+    .output();
+
+  match output {
+    Ok(output) if output.status.success() && !output.stdout.is_empty() => {
+      String::from_utf8_lossy(&output.stdout).to_string()
+    }
+    Ok(output) => format!(
+      "No journal entries from rua in the last 2 hours.\n{}",
+      String::from_utf8_lossy(&output.stderr)
+    ),
+    Err(e) => format!("journalctl is not available: {}\n", e),
+  }
+}
+
+/// Bugreport command: zip the diagnosis with redacted preferences, the
+/// registry and recent logs
+fn bugreport(output: Option<&str>) {
+  let (report, diagnostics, _) = diagnose();
+  print!("{}", report);
+  println!();
+
+  let dir_path = |name: &str| {
+    diagnostics
+      .directories
+      .iter()
+      .find(|dir| dir.name == name)
+      .map(|dir| PathBuf::from(&dir.path))
+  };
+  let mut files: Vec<(&str, String)> = vec![("doctor.txt", report.clone())];
+  if let Ok(json) = serde_json::to_string_pretty(&diagnostics) {
+    files.push(("diagnostics.json", json));
+  }
+
+  if let Some(path) = dir_path("config").map(|dir| dir.join("preferences.json")) {
+    if let Ok(Some(mut preferences)) = check_json_file(&path) {
+      let sensitive = Regex::new(SENSITIVE_KEY_PATTERN).unwrap();
+      redact_preferences(&mut preferences, &sensitive);
+      if let Ok(json) = serde_json::to_string_pretty(&preferences) {
+        files.push(("preferences.json", json));
+      }
+    }
+  }
+
+  let copied = [
+    (
+      "registry.json",
+      dir_path("data").map(|dir| dir.join("extensions/registry.json")),
+    ),
+    ("control.json", Some(control_discovery_path())),
+  ];
+  for (name, path) in copied {
+    if let Some(content) = path.and_then(|p| fs::read_to_string(p).ok()) {
+      files.push((name, content));
+    }
+  }
+
+  files.push(("logs.txt", recent_logs()));
+
+  let output = output.map(PathBuf::from).unwrap_or_else(|| {
+    PathBuf::from(format!(
+      "rua-bugreport-{}.zip",
+      chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ))
+  });
+  let file = match File::create(&output) {
+    Ok(f) => f,
+    Err(e) => {
+      eprintln!("✗ Cannot write to output file: {}", e);
+      process::exit(1);
+    }
+  };
+
+  // Don't leak the user name through home directory paths
+  let home = std::env::var("HOME").ok().filter(|h| h.len() > 1);
+  let mut zip = ZipWriter::new(file);
+  let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+  for (name, content) in &files {
+    let content = match &home {
+      Some(home) => content.replace(home.as_str(), "~"),
+      None => content.clone(),
+    };
+    if let Err(e) = zip
+      .start_file(*name, options)
+      .map_err(|e| e.to_string())
+      .and_then(|_| zip.write_all(content.as_bytes()).map_err(|e| e.to_string()))
+    {
+      eprintln!("✗ Failed to add {} to archive: {}", name, e);
+      process::exit(1);
+    }
+  }
+  if let Err(e) = zip.finish() {
+    eprintln!("✗ Failed to finalize archive: {}", e);
+    process::exit(1);
+  }
+
+  println!("✓ Bug report written to {}", output.display());
+  for (name, _) in &files {
+    println!("  {}", name);
+  }
+  println!("ℹ Secrets in preferences are redacted, but please review the files before sharing");
+}
+
 /// Pack command
 fn pack(path: Option<&str>, dry_run: bool) {
   let dir = PathBuf::from(path.unwrap_or("."));
@@ -523,10 +1562,19 @@ fn pack(path: Option<&str>, dry_run: bool) {
   process::exit(0);
 }
 
-/// Get extensions directory
+/// Get the app's extensions directory (`$XDG_DATA_HOME/like.rua.ai/extensions`)
 fn get_extensions_dir() -> Result<PathBuf, String> {
-  let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
-  let extensions_dir = PathBuf::from(home).join(".local/share/like.rua.ai/extensions");
+  let data_dir = match std::env::var("XDG_DATA_HOME")
+    .ok()
+    .filter(|d| !d.is_empty())
+  {
+    Some(dir) => PathBuf::from(dir),
+    None => {
+      let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
+      PathBuf::from(home).join(".local/share")
+    }
+  };
+  let extensions_dir = data_dir.join("like.rua.ai/extensions");
 
   if !extensions_dir.exists() {
     fs::create_dir_all(&extensions_dir)
@@ -695,107 +1743,596 @@ fn extract_rua_archive(archive_data: &[u8], extensions_dir: &Path) -> Result<Str
   Ok(ext_id.to_string())
 }
 
-/// Install command
-fn install(source: &str) {
-  println!("ℹ Installing extension from {}", source);
+/// Registry state shared with the app (`registry.json` in the extensions directory)
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryState {
+  version: u32,
+  extensions: HashMap<String, ExtensionState>,
+}
 
-  let extensions_dir = match get_extensions_dir() {
-    Ok(d) => d,
-    Err(e) => {
-      eprintln!("✗ {}", e);
-      process::exit(1);
-    }
-  };
+/// Individual extension state, as written by the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExtensionState {
+  id: String,
+  enabled: bool,
+  #[serde(rename = "installedAt")]
+  installed_at: String,
+  #[serde(rename = "updatedAt")]
+  updated_at: String,
+  version: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  source: Option<String>,
+}
 
-  let archive_data: Vec<u8>;
-  let source_desc: String;
+fn load_registry(extensions_dir: &Path) -> Result<RegistryState, String> {
+  let registry_path = extensions_dir.join("registry.json");
 
-  if source.starts_with("github:") {
-    // GitHub source
-    let (owner, repo, version) = match parse_github_source(source) {
-      Some(v) => v,
-      None => {
+  if !registry_path.exists() {
+    return Ok(RegistryState::default());
+  }
+
+  let content =
+    fs::read_to_string(&registry_path).map_err(|e| format!("Failed to read registry: {}", e))?;
+
+  serde_json::from_str(&content).map_err(|e| format!("Failed to parse registry: {}", e))
+}
+
+fn save_registry(extensions_dir: &Path, state: &RegistryState) -> Result<(), String> {
+  let content = serde_json::to_string_pretty(state)
+    .map_err(|e| format!("Failed to serialize registry: {}", e))?;
+
+  fs::write(extensions_dir.join("registry.json"), content)
+    .map_err(|e| format!("Failed to write registry: {}", e))
+}
+
+/// Send an extension request to the running app
+///
+/// Returns `None` when the caller should use the extensions directory directly:
+/// with `--offline`, or when rua isn't running. Changes are refused when rua is
+/// running but unreachable, since they would happen behind its back.
+fn try_app_request(
+  endpoint: &str,
+  body: &serde_json::Value,
+  offline: bool,
+  read_only: bool,
+) -> Option<Response> {
+  if offline {
+    return None;
+  }
+
+  match send_request::<Response>(endpoint, Some(body)) {
+    Ok(resp) => Some(resp),
+    Err(e) if read_control_discovery().is_some() => {
+      if !read_only {
+        eprintln!("✗ rua is running but unreachable: {}", e);
         eprintln!(
-          "✗ Invalid GitHub source format. Use: github:owner/repo or github:owner/repo@version"
+          "  Fix the connection, or pass --offline to edit the extensions directory anyway."
         );
         process::exit(1);
       }
-    };
+      eprintln!("⚠ rua is running but unreachable: {}", e);
+      eprintln!("  Reading the extensions directory directly.");
+      None
+    }
+    Err(_) => None,
+  }
+}
 
-    println!("  Fetching release info from {}/{}...", owner, repo);
+/// Read the installed extensions the same way the app does
+///
+/// Returns objects shaped like the app's `ExtensionInfo`.
+fn local_extensions(extensions_dir: &Path) -> Result<Vec<serde_json::Value>, String> {
+  let registry = load_registry(extensions_dir)?;
+  let entries =
+    fs::read_dir(extensions_dir).map_err(|e| format!("Failed to read extensions dir: {}", e))?;
 
-    let release = match fetch_github_release(&owner, &repo, version.as_deref()) {
-      Ok(r) => r,
+  let mut extensions = Vec::new();
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if !path.is_dir() {
+      continue;
+    }
+
+    let manifest = fs::read_to_string(path.join("manifest.json"))
+      .map_err(|e| format!("Failed to read manifest: {}", e))
+      .and_then(|content| {
+        serde_json::from_str::<serde_json::Value>(&content)
+          .map_err(|e| format!("Failed to parse manifest: {}", e))
+      });
+
+    let extension = match manifest {
+      Ok(manifest) => {
+        let id = manifest["id"].as_str().unwrap_or_default().to_string();
+        let enabled = registry
+          .extensions
+          .get(&id)
+          .map(|s| s.enabled)
+          .unwrap_or(true);
+        let actions: Vec<String> = manifest["rua"]["actions"]
+          .as_array()
+          .map(|actions| {
+            actions
+              .iter()
+              .filter_map(|a| a["name"].as_str())
+              .map(|name| format!("{}.{}", id, name))
+              .collect()
+          })
+          .unwrap_or_default();
+
+        serde_json::json!({
+          "manifest": manifest,
+          "enabled": enabled,
+          "loaded": enabled,
+          "path": path.to_string_lossy(),
+          "actions": actions,
+          "error": null,
+        })
+      }
       Err(e) => {
-        eprintln!("✗ {}", e);
-        process::exit(1);
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        serde_json::json!({
+          "manifest": { "id": dir_name, "name": dir_name, "version": "0.0.0" },
+          "enabled": false,
+          "loaded": false,
+          "path": path.to_string_lossy(),
+          "actions": [],
+          "error": e,
+        })
       }
     };
 
-    // Find .rua asset
-    let rua_asset = release.assets.iter().find(|a| a.name.ends_with(".rua"));
+    extensions.push(extension);
+  }
 
-    let asset = match rua_asset {
-      Some(a) => a,
-      None => {
-        eprintln!("✗ No .rua file found in release {}", release.tag_name);
-        process::exit(1);
-      }
-    };
+  Ok(extensions)
+}
+
+fn find_local_extension(extensions_dir: &Path, id: &str) -> Result<serde_json::Value, String> {
+  local_extensions(extensions_dir)?
+    .into_iter()
+    .find(|ext| ext["manifest"]["id"] == id)
+    .ok_or_else(|| format!("Extension not installed: {}", id))
+}
+
+/// Set the enabled flag of an installed extension in the registry
+fn set_local_enabled(extensions_dir: &Path, id: &str, enabled: bool) -> Result<(), String> {
+  let extension = find_local_extension(extensions_dir, id)?;
+  let mut registry = load_registry(extensions_dir)?;
+  let now = chrono::Utc::now().to_rfc3339();
+
+  let state = registry
+    .extensions
+    .entry(id.to_string())
+    .or_insert_with(|| ExtensionState {
+      id: id.to_string(),
+      enabled,
+      installed_at: now.clone(),
+      updated_at: now.clone(),
+      version: extension["manifest"]["version"]
+        .as_str()
+        .unwrap_or("0.0.0")
+        .to_string(),
+      source: None,
+    });
+  state.enabled = enabled;
+  state.updated_at = now;
+
+  save_registry(extensions_dir, &registry)
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
+  fs::create_dir_all(dst).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+  for entry in fs::read_dir(src).map_err(|e| format!("Failed to read directory: {}", e))? {
+    let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+    let src_path = entry.path();
+    let dst_path = dst.join(entry.file_name());
+
+    if src_path.is_dir() {
+      copy_dir_recursive(&src_path, &dst_path)?;
+    } else {
+      fs::copy(&src_path, &dst_path).map_err(|e| format!("Failed to copy file: {}", e))?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Result of installing an extension without the app
+struct LocalInstall {
+  id: String,
+  version: String,
+  description: String,
+}
+
+/// Install an extension into the extensions directory and record it in the registry
+fn install_locally(source: &str, extensions_dir: &Path) -> Result<LocalInstall, String> {
+  let (ext_id, recorded_source, description) = if source.starts_with("github:") {
+    let (owner, repo, version) = parse_github_source(source)
+      .ok_or("Invalid GitHub source format. Use: github:owner/repo or github:owner/repo@version")?;
+
+    println!("  Fetching release info from {}/{}...", owner, repo);
+    let release = fetch_github_release(&owner, &repo, version.as_deref())?;
+
+    let asset = release
+      .assets
+      .iter()
+      .find(|a| a.name.ends_with(".rua"))
+      .ok_or_else(|| format!("No .rua file found in release {}", release.tag_name))?;
 
     println!("  Downloading {}...", asset.name);
+    let archive_data = download_file(&asset.browser_download_url)?;
 
-    archive_data = match download_file(&asset.browser_download_url) {
-      Ok(d) => d,
-      Err(e) => {
-        eprintln!("✗ {}", e);
-        process::exit(1);
-      }
-    };
+    println!("  Extracting...");
+    let ext_id = extract_rua_archive(&archive_data, extensions_dir)?;
 
-    source_desc = format!("{}/{} {}", owner, repo, release.tag_name);
-  } else if source.ends_with(".rua") {
-    // Local .rua file
+    // Updates follow the latest release, even if a version was pinned
+    (
+      ext_id,
+      format!("github:{}/{}", owner, repo),
+      format!("{}/{} {}", owner, repo, release.tag_name),
+    )
+  } else {
     let path = PathBuf::from(source);
     if !path.exists() {
-      eprintln!("✗ File not found: {}", source);
-      process::exit(1);
+      return Err(format!("File not found: {}", source));
     }
+    let absolute = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+    let description = path
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_else(|| source.to_string());
 
-    archive_data = match fs::read(&path) {
-      Ok(d) => d,
-      Err(e) => {
-        eprintln!("✗ Failed to read file: {}", e);
-        process::exit(1);
+    let ext_id = if path.is_dir() {
+      let manifest = parse_manifest(&path).map_err(|errors| errors.join("; "))?;
+      let target = extensions_dir.join(&manifest.id);
+      if target.exists() {
+        fs::remove_dir_all(&target)
+          .map_err(|e| format!("Failed to remove existing extension: {}", e))?;
       }
+      println!("  Copying...");
+      copy_dir_recursive(&path, &target)?;
+      manifest.id
+    } else if source.ends_with(".rua") {
+      let archive_data = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+      println!("  Extracting...");
+      extract_rua_archive(&archive_data, extensions_dir)?
+    } else {
+      return Err(
+        "Unknown source format. Use github:owner/repo, path/to/extension.rua or an extension directory"
+          .to_string(),
+      );
     };
 
-    source_desc = path
-      .file_name()
-      .map(|n| n.to_string_lossy().to_string())
-      .unwrap_or_else(|| source.to_string());
+    (ext_id, absolute.to_string_lossy().to_string(), description)
+  };
+
+  let manifest =
+    parse_manifest(&extensions_dir.join(&ext_id)).map_err(|errors| errors.join("; "))?;
+
+  let mut registry = load_registry(extensions_dir)?;
+  let now = chrono::Utc::now().to_rfc3339();
+  let installed_at = registry
+    .extensions
+    .get(&ext_id)
+    .map(|state| state.installed_at.clone())
+    .unwrap_or_else(|| now.clone());
+  registry.extensions.insert(
+    ext_id.clone(),
+    ExtensionState {
+      id: ext_id.clone(),
+      enabled: true,
+      installed_at,
+      updated_at: now,
+      version: manifest.version.clone(),
+      source: Some(recorded_source),
+    },
+  );
+  save_registry(extensions_dir, &registry)?;
+
+  Ok(LocalInstall {
+    id: ext_id,
+    version: manifest.version,
+    description,
+  })
+}
+
+/// Install command
+fn install(source: &str, offline: bool) {
+  println!("ℹ Installing extension from {}", source);
+
+  // The app resolves paths relative to its own working directory
+  let app_source = if source.starts_with("github:") {
+    source.to_string()
   } else {
-    eprintln!("✗ Unknown source format. Use github:owner/repo or path/to/extension.rua");
-    process::exit(1);
+    fs::canonicalize(source)
+      .map(|p| p.to_string_lossy().to_string())
+      .unwrap_or_else(|_| source.to_string())
+  };
+
+  if let Some(resp) = try_app_request(
+    "/extensions/install",
+    &serde_json::json!({ "source": app_source }),
+    offline,
+    false,
+  ) {
+    if !resp.success {
+      eprintln!("✗ {}", resp.message);
+      process::exit(1);
+    }
+    let data = resp.data.unwrap_or_default();
+    println!("✓ Extension installed successfully");
+    println!(
+      "  ID: {}",
+      data["manifest"]["id"].as_str().unwrap_or_default()
+    );
+    println!(
+      "  Version: {}",
+      data["manifest"]["version"].as_str().unwrap_or_default()
+    );
+    println!("  Location: {}", data["path"].as_str().unwrap_or_default());
+    process::exit(0);
   }
 
-  println!("  Extracting...");
+  let extensions_dir = match get_extensions_dir() {
+    Ok(d) => d,
+    Err(e) => {
+      eprintln!("✗ {}", e);
+      process::exit(1);
+    }
+  };
 
-  let ext_id = match extract_rua_archive(&archive_data, &extensions_dir) {
-    Ok(id) => id,
+  match install_locally(source, &extensions_dir) {
+    Ok(installed) => {
+      println!("✓ Extension installed successfully");
+      println!("  ID: {}", installed.id);
+      println!("  Version: {}", installed.version);
+      println!("  Source: {}", installed.description);
+      println!(
+        "  Location: {}",
+        extensions_dir.join(&installed.id).display()
+      );
+      process::exit(0);
+    }
     Err(e) => {
       eprintln!("✗ {}", e);
       process::exit(1);
     }
+  }
+}
+
+fn print_extension_list(extensions: &[serde_json::Value]) {
+  if extensions.is_empty() {
+    println!("No extensions installed");
+    return;
+  }
+
+  let rows: Vec<[String; 4]> = extensions
+    .iter()
+    .map(|ext| {
+      let status = if ext["error"].is_string() {
+        "error"
+      } else if ext["enabled"].as_bool().unwrap_or(false) {
+        "enabled"
+      } else {
+        "disabled"
+      };
+      [
+        ext["manifest"]["id"]
+          .as_str()
+          .unwrap_or_default()
+          .to_string(),
+        ext["manifest"]["version"]
+          .as_str()
+          .unwrap_or_default()
+          .to_string(),
+        status.to_string(),
+        ext["manifest"]["name"]
+          .as_str()
+          .unwrap_or_default()
+          .to_string(),
+      ]
+    })
+    .collect();
+
+  let id_width = rows.iter().map(|r| r[0].len()).max().unwrap_or(0).max(2);
+  let version_width = rows.iter().map(|r| r[1].len()).max().unwrap_or(0).max(7);
+
+  println!(
+    "{:<id_width$}  {:<version_width$}  {:<8}  NAME",
+    "ID", "VERSION", "STATUS"
+  );
+  for [id, version, status, name] in rows {
+    println!(
+      "{:<id_width$}  {:<version_width$}  {:<8}  {}",
+      id, version, status, name
+    );
+  }
+}
+
+fn print_extension_info(extension: &serde_json::Value, state: &serde_json::Value) {
+  let manifest = &extension["manifest"];
+  let text = |value: &serde_json::Value| value.as_str().unwrap_or("-").to_string();
+
+  println!(
+    "{} ({}) v{}",
+    text(&manifest["name"]),
+    text(&manifest["id"]),
+    text(&manifest["version"])
+  );
+  if let Some(error) = extension["error"].as_str() {
+    println!("  Error:       {}", error);
+  }
+  let status = if extension["enabled"].as_bool().unwrap_or(false) {
+    "enabled"
+  } else {
+    "disabled"
   };
+  println!("  Status:      {}", status);
+  println!("  Description: {}", text(&manifest["description"]));
+  println!("  Author:      {}", text(&manifest["author"]));
+  println!("  Path:        {}", text(&extension["path"]));
+  println!("  Source:      {}", text(&state["source"]));
+  println!("  Installed:   {}", text(&state["installedAt"]));
+  println!("  Updated:     {}", text(&state["updatedAt"]));
+
+  if let Some(actions) = manifest["rua"]["actions"].as_array() {
+    println!("  Actions:");
+    for action in actions {
+      println!(
+        "    {}.{} ({}) {}",
+        text(&manifest["id"]),
+        text(&action["name"]),
+        text(&action["mode"]),
+        text(&action["title"])
+      );
+    }
+  }
+}
 
-  println!("✓ Extension installed successfully");
-  println!("  ID: {}", ext_id);
-  println!("  Source: {}", source_desc);
-  println!("  Location: {}", extensions_dir.join(&ext_id).display());
+/// Run a local extension operation, exiting with its outcome
+fn exit_with_local_result(result: Result<String, String>) -> ! {
+  match result {
+    Ok(message) => {
+      println!("✓ {}", message);
+      process::exit(0);
+    }
+    Err(e) => {
+      eprintln!("✗ {}", e);
+      process::exit(1);
+    }
+  }
+}
+
+/// `ruactl ext <subcommand>`: manage installed extensions
+///
+/// Goes through the running app when it's reachable, so its state stays in
+/// sync, and edits the extensions directory and registry directly when rua isn't
+/// running or with `--offline`.
+fn ext(subcommand: &str, id: Option<&str>, json: bool, offline: bool) {
+  let body = serde_json::json!({ "id": id });
+  let needs_id = subcommand != "list";
+  if needs_id && id.is_none() {
+    eprintln!("✗ Missing extension ID");
+    eprintln!("Usage: ruactl ext {} <extension-id>", subcommand);
+    process::exit(1);
+  }
+  if !matches!(
+    subcommand,
+    "list" | "info" | "enable" | "disable" | "uninstall" | "update"
+  ) {
+    eprintln!("✗ Unknown ext command: {}", subcommand);
+    eprintln!("Usage: ruactl ext list|info|enable|disable|uninstall|update [extension-id]");
+    process::exit(1);
+  }
+
+  let app_response = try_app_request(
+    &format!("/extensions/{}", subcommand),
+    &body,
+    offline,
+    matches!(subcommand, "list" | "info"),
+  );
+
+  // Normalize list/info data so both paths print the same way
+  let data = match app_response {
+    Some(resp) if !resp.success => {
+      eprintln!("✗ {}", resp.message);
+      process::exit(1);
+    }
+    Some(resp) if matches!(subcommand, "list" | "info") => resp.data.unwrap_or_default(),
+    Some(resp) => {
+      println!("✓ {}", resp.message);
+      process::exit(0);
+    }
+    None => {
+      let extensions_dir = match get_extensions_dir() {
+        Ok(d) => d,
+        Err(e) => {
+          eprintln!("✗ {}", e);
+          process::exit(1);
+        }
+      };
+      let id = id.unwrap_or_default();
+
+      match subcommand {
+        "list" => match local_extensions(&extensions_dir) {
+          Ok(extensions) => serde_json::Value::Array(extensions),
+          Err(e) => exit_with_local_result(Err(e)),
+        },
+        "info" => {
+          let result = find_local_extension(&extensions_dir, id).and_then(|extension| {
+            let state = load_registry(&extensions_dir)?.extensions.remove(id);
+            Ok(serde_json::json!({ "extension": extension, "state": state }))
+          });
+          match result {
+            Ok(data) => data,
+            Err(e) => exit_with_local_result(Err(e)),
+          }
+        }
+        "enable" => exit_with_local_result(
+          set_local_enabled(&extensions_dir, id, true).map(|_| format!("{} enabled", id)),
+        ),
+        "disable" => exit_with_local_result(
+          set_local_enabled(&extensions_dir, id, false).map(|_| format!("{} disabled", id)),
+        ),
+        "uninstall" => exit_with_local_result(find_local_extension(&extensions_dir, id).and_then(
+          |extension| {
+            if let Some(path) = extension["path"].as_str() {
+              fs::remove_dir_all(path).map_err(|e| format!("Failed to remove extension: {}", e))?;
+            }
+            let mut registry = load_registry(&extensions_dir)?;
+            registry.extensions.remove(id);
+            save_registry(&extensions_dir, &registry)?;
+            Ok(format!("{} uninstalled", id))
+          },
+        )),
+        _ => exit_with_local_result(local_update(&extensions_dir, id)),
+      }
+    }
+  };
+
+  if json {
+    println!(
+      "{}",
+      serde_json::to_string_pretty(&data).unwrap_or_default()
+    );
+  } else if subcommand == "list" {
+    print_extension_list(data.as_array().map(|a| a.as_slice()).unwrap_or_default());
+  } else {
+    print_extension_info(&data["extension"], &data["state"]);
+  }
   process::exit(0);
 }
 
+/// Reinstall an extension from its recorded source without the app
+fn local_update(extensions_dir: &Path, id: &str) -> Result<String, String> {
+  let state = load_registry(extensions_dir)?
+    .extensions
+    .remove(id)
+    .ok_or_else(|| format!("Extension not installed: {}", id))?;
+  let source = state.source.ok_or_else(|| {
+    format!(
+      "No install source recorded for {}, reinstall it once to enable updates",
+      id
+    )
+  })?;
+
+  println!("ℹ Updating {} from {}", id, source);
+  let installed = install_locally(&source, extensions_dir)?;
+  if installed.id != id {
+    return Err(format!(
+      "Source of {} now provides a different extension: {}",
+      id, installed.id
+    ));
+  }
+
+  if !state.enabled {
+    set_local_enabled(extensions_dir, id, false)?;
+  }
+
+  Ok(format!("Updated {} to {}", id, installed.version))
+}
+
 fn main() {
   let args: Vec<String> = std::env::args().collect();
 
@@ -807,10 +2344,107 @@ fn main() {
   match args[1].as_str() {
     "toggle" => toggle(),
     "health" => health(),
+    "doctor" => doctor(),
+    "bugreport" => {
+      let mut output: Option<&str> = None;
+
+      let mut iter = args.iter().skip(2);
+      while let Some(arg) = iter.next() {
+        match arg.as_str() {
+          "--output" | "-o" => output = iter.next().map(|s| s.as_str()),
+          other => {
+            eprintln!("✗ Unknown option for bugreport: {}", other);
+            eprintln!("Usage: ruactl bugreport [--output <file>]");
+            process::exit(1);
+          }
+        }
+      }
+
+      bugreport(output);
+    }
+    "show" => {
+      let mut query: Option<&str> = None;
+      let mut view: Option<&str> = None;
+
+      let mut iter = args.iter().skip(2);
+      while let Some(arg) = iter.next() {
+        match arg.as_str() {
+          "--query" | "-q" => query = iter.next().map(|s| s.as_str()),
+          "--view" => view = iter.next().map(|s| s.as_str()),
+          other => {
+            eprintln!("✗ Unknown option for show: {}", other);
+            eprintln!("Usage: ruactl show [--query <text>] [--view <action-id>]");
+            process::exit(1);
+          }
+        }
+      }
+
+      show(query, view);
+    }
+    "hide" => hide(),
+    "events" => {
+      let follow = args.iter().skip(2).any(|a| a == "--follow" || a == "-f");
+      events(follow);
+    }
+    "rpc" => {
+      let method = match args.get(2) {
+        Some(method) if !method.starts_with('-') => method,
+        _ => {
+          eprintln!("✗ Missing method name");
+          eprintln!("Usage: ruactl rpc <method> [params-json]");
+          process::exit(1);
+        }
+      };
+      rpc(method, args.get(3).map(|s| s.as_str()));
+    }
+    "run" => {
+      let action_id = match args.get(2) {
+        Some(id) if !id.starts_with('-') => id,
+        _ => {
+          eprintln!("✗ Missing action ID");
+          eprintln!("Usage: ruactl run <ext.id.action> [--arg key=value]...");
+          process::exit(1);
+        }
+      };
+
+      let mut action_args: Vec<(String, String)> = Vec::new();
+      let mut iter = args.iter().skip(3);
+      while let Some(arg) = iter.next() {
+        match arg.as_str() {
+          "--arg" | "-a" => {
+            let pair = iter.next().and_then(|p| p.split_once('='));
+            match pair {
+              Some((key, value)) => action_args.push((key.to_string(), value.to_string())),
+              None => {
+                eprintln!("✗ --arg expects key=value");
+                process::exit(1);
+              }
+            }
+          }
+          other => {
+            eprintln!("✗ Unknown option for run: {}", other);
+            process::exit(1);
+          }
+        }
+      }
+
+      run(action_id, &action_args);
+    }
     "validate" => {
       let path = args.get(2).map(|s| s.as_str());
       validate(path);
     }
+    "dev" => {
+      if args.iter().skip(2).any(|a| a == "--stop") {
+        dev_stop();
+      }
+      let path = args
+        .iter()
+        .skip(2)
+        .find(|a| !a.starts_with('-'))
+        .map(|s| s.as_str());
+      dev(path);
+    }
     "pack" => {
       let mut path: Option<&str> = None;
       let mut dry_run = false;
@@ -825,10 +2459,29 @@ fn main() {
 
       pack(path, dry_run);
     }
+    "ext" => {
+      let subcommand = match args.get(2) {
+        Some(sub) => sub.as_str(),
+        None => {
+          eprintln!("✗ Missing ext command");
+          eprintln!("Usage: ruactl ext list|info|enable|disable|uninstall|update [extension-id]");
+          process::exit(1);
+        }
+      };
+      let id = args
+        .iter()
+        .skip(3)
+        .find(|a| !a.starts_with('-'))
+        .map(|s| s.as_str());
+      let json = args.iter().skip(3).any(|a| a == "--json");
+      let offline = args.iter().skip(3).any(|a| a == "--offline");
+      ext(subcommand, id, json, offline);
+    }
     "install" => {
-      let source = args.get(2);
+      let source = args.iter().skip(2).find(|a| !a.starts_with('-'));
+      let offline = args.iter().skip(2).any(|a| a == "--offline");
       match source {
-        Some(s) => install(s),
+        Some(s) => install(s, offline),
         None => {
           eprintln!("✗ Missing source argument");
           eprintln!("Usage: ruactl install github:owner/repo");
@@ -853,3 +2506,41 @@ fn main() {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_redact_preferences() {
+    let sensitive = Regex::new(SENSITIVE_KEY_PATTERN).unwrap();
+    let mut preferences = serde_json::json!({
+      "ext.ai": {
+        "openaiKey": "sk-123",
+        "accessKey": "abc",
+        "passphrase": "hunter2",
+        "credentials": { "user": "me", "secret": "s3" },
+        "tokens": ["t1", "t2"],
+        "unset": null,
+        "model": "gpt",
+        "servers": [{ "name": "a", "privateKey": "pk" }],
+      },
+      "system.advanced": { "controlServerPort": 7777 },
+    });
+
+    redact_preferences(&mut preferences, &sensitive);
+
+    let redacted = serde_json::json!("<redacted>");
+    let ai = &preferences["ext.ai"];
+    assert_eq!(ai["openaiKey"], redacted);
+    assert_eq!(ai["accessKey"], redacted);
+    assert_eq!(ai["passphrase"], redacted);
+    assert_eq!(ai["credentials"], redacted);
+    assert_eq!(ai["tokens"], redacted);
+    assert_eq!(ai["unset"], serde_json::Value::Null);
+    assert_eq!(ai["model"], "gpt");
+    assert_eq!(ai["servers"][0]["name"], "a");
+    assert_eq!(ai["servers"][0]["privateKey"], redacted);
+    assert_eq!(preferences["system.advanced"]["controlServerPort"], 7777);
+  }
+}
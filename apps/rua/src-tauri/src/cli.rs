@@ -0,0 +1,181 @@
+//! CLI Module
+//!
+//! Command-line interface of the `rua` binary. The same arguments work on
+//! first launch and when rua is already running: a second invocation exits
+//! right away and the single-instance plugin forwards its arguments to the
+//! running instance, so `rua --query foo` behaves the same either way.
+
+use tauri::{AppHandle, Manager};
+
+#[cfg(target_os = "linux")]
+use crate::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::*;
+use crate::{
+  deep_link::{dispatch, dispatch_deep_link, is_deep_link, DeepLink},
+  types::WindowShownPayload,
+};
+
+pub const USAGE: &str = "\
+rua - Command palette launcher
+
+USAGE:
+    rua [OPTIONS] [rua://...]
+
+OPTIONS:
+    -q, --query <text>      Show the window with a pre-filled search
+    --view <action-id>      Show the window on an action view, e.g. ext.id.action
+    --hidden                Start without showing the window (for autostart)
+    -h, --help              Print help information
+    -V, --version           Print version information
+
+If rua is already running, the arguments are passed to the running instance.";
+
+/// Arguments that affect the window and what it shows
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LaunchArgs {
+  pub query: Option<String>,
+  pub view: Option<String>,
+  pub hidden: bool,
+  /// `rua://` links to open
+  pub links: Vec<String>,
+}
+
+/// What the command line asks for
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliCommand {
+  Launch(LaunchArgs),
+  Help,
+  Version,
+}
+
+/// Parse the command line, without the program name
+pub fn parse_args<I, S>(args: I) -> Result<CliCommand, String>
+where
+  I: IntoIterator<Item = S>,
+  S: AsRef<str>,
+{
+  let mut launch = LaunchArgs::default();
+  let mut args = args.into_iter();
+
+  while let Some(arg) = args.next() {
+    let arg = arg.as_ref();
+    let (name, inline_value) = match arg.split_once('=') {
+      Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+      _ => (arg, None),
+    };
+    let mut value = |name: &str| {
+      inline_value
+        .clone()
+        .or_else(|| args.next().map(|v| v.as_ref().to_string()))
+        .ok_or_else(|| format!("Missing value for {}", name))
+    };
+
+    match name {
+      "-q" | "--query" => launch.query = Some(value(name)?),
+      "--view" => launch.view = Some(value(name)?),
+      "--hidden" => launch.hidden = true,
+      "-h" | "--help" => return Ok(CliCommand::Help),
+      "-V" | "--version" => return Ok(CliCommand::Version),
+      link if is_deep_link(link) => launch.links.push(link.to_string()),
+      other => return Err(format!("Unknown argument: {}", other)),
+    }
+  }
+
+  Ok(CliCommand::Launch(launch))
+}
+
+impl LaunchArgs {
+  /// Whether the arguments ask for something besides showing the window
+  fn has_requests(&self) -> bool {
+    self.query.is_some() || self.view.is_some() || !self.links.is_empty()
+  }
+
+  /// Open the links and apply the query and view
+  fn dispatch(&self, app: &AppHandle) {
+    for link in &self.links {
+      dispatch_deep_link(app, link);
+    }
+
+    if self.query.is_some() || self.view.is_some() {
+      dispatch(
+        app,
+        DeepLink::Show(WindowShownPayload {
+          query: self.query.clone(),
+          view: self.view.clone(),
+          ..Default::default()
+        }),
+      );
+    }
+  }
+}
+
+fn focus_main_window(app: &AppHandle) {
+  if let Some(window) = app.get_webview_window("main") {
+    let _ = window.show();
+    let _ = window.set_focus();
+  }
+}
+
+/// Apply the arguments rua was started with
+pub fn handle_launch_args(app: &AppHandle, args: &LaunchArgs) {
+  if !args.hidden {
+    focus_main_window(app);
+  }
+
+  args.dispatch(app);
+}
+
+/// Apply the arguments of a second invocation, forwarded to this instance
+pub fn handle_forwarded_args(app: &AppHandle, argv: &[String]) {
+  let args = match parse_args(argv.iter().skip(1)) {
+    Ok(CliCommand::Launch(args)) => args,
+    Ok(_) => return,
+    Err(e) => {
+      eprintln!("[CLI] Ignoring forwarded arguments: {}", e);
+      notify("Rua", &e);
+      return;
+    }
+  };
+
+  if args.has_requests() {
+    args.dispatch(app);
+  } else if !args.hidden {
+    // A plain `rua` shows and focuses the existing window
+    focus_main_window(app);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_args() {
+    assert_eq!(
+      parse_args(Vec::<String>::new()).unwrap(),
+      CliCommand::Launch(LaunchArgs::default())
+    );
+    assert_eq!(
+      parse_args(["--query", "foo bar", "--view=fzdwx.clip.list", "--hidden"]).unwrap(),
+      CliCommand::Launch(LaunchArgs {
+        query: Some("foo bar".to_string()),
+        view: Some("fzdwx.clip.list".to_string()),
+        hidden: true,
+        links: vec![],
+      })
+    );
+    assert_eq!(
+      parse_args(["rua://show?query=x"]).unwrap(),
+      CliCommand::Launch(LaunchArgs {
+        links: vec!["rua://show?query=x".to_string()],
+        ..Default::default()
+      })
+    );
+    assert_eq!(parse_args(["-q", "a", "--help"]).unwrap(), CliCommand::Help);
+    assert_eq!(parse_args(["-V"]).unwrap(), CliCommand::Version);
+
+    assert!(parse_args(["--query"]).is_err());
+    assert!(parse_args(["--unknown"]).is_err());
+  }
+}
@@ -0,0 +1,464 @@
+//! Clipboard History Module
+//!
+//! Records text and images copied anywhere on the desktop so they can be
+//! searched and copied again. Entries are deduplicated by content, the newest
+//! first, and pinned entries are never evicted. Content marked as sensitive by
+//! password managers is not recorded.
+//!
+//! The history is stored in the app data dir: `clipboard-history/history.json`
+//! holds the entries and `clipboard-history/images/<id>.png` the images.
+//! It is configured by the `system.clipboard` preferences.
+
+use std::{fs, path::PathBuf, sync::Mutex, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+  preferences::{get_preferences_path, load_preferences},
+  read_clipboard, read_clipboard_format, read_clipboard_image, watch_clipboard, write_clipboard,
+  write_clipboard_image,
+};
+
+/// Preference namespace of the clipboard settings
+const CLIPBOARD_NAMESPACE: &str = "system.clipboard";
+const DEFAULT_HISTORY_LIMIT: usize = 200;
+
+/// Larger content is not recorded
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Format password managers (KeePassXC, KDE) set on copied secrets
+const PASSWORD_HINT_MIME_TYPE: &str = "x-kde-passwordManagerHint";
+
+/// Event emitted when entries are added, changed or removed
+pub const HISTORY_CHANGED_EVENT: &str = "rua://clipboard-history-changed";
+
+lazy_static::lazy_static! {
+    /// History entries, newest first, `None` until loaded
+    static ref HISTORY: Mutex<Option<Vec<ClipboardEntry>>> = Mutex::new(None);
+    /// Recording settings and the preferences mtime they were read at
+    static ref SETTINGS: Mutex<Option<(Option<SystemTime>, (bool, usize))>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardEntryKind {
+  Text,
+  Image,
+}
+
+/// A recorded clipboard content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+  /// Hash of the content, the same content always gets the same ID
+  pub id: String,
+  pub kind: ClipboardEntryKind,
+  /// Copied text, `None` for images
+  pub text: Option<String>,
+  /// Path of the stored PNG for images
+  pub image_path: Option<String>,
+  /// Size of the content in bytes
+  pub size: usize,
+  pub pinned: bool,
+  /// When the content was last copied (RFC 3339)
+  pub copied_at: String,
+}
+
+/// 64-bit FNV-1a hash of the content as hex, stable across versions
+fn content_id(data: &[u8]) -> String {
+  let hash = data.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+  });
+  format!("{:016x}", hash)
+}
+
+/// Add an entry to the front, replacing an entry with the same content and
+/// keeping its pin. Returns the entries evicted to stay within `limit`.
+fn insert_entry(
+  entries: &mut Vec<ClipboardEntry>,
+  mut entry: ClipboardEntry,
+  limit: usize,
+) -> Vec<ClipboardEntry> {
+  if let Some(index) = entries.iter().position(|e| e.id == entry.id) {
+    entry.pinned = entries.remove(index).pinned;
+  }
+  entries.insert(0, entry);
+
+  let mut evicted = Vec::new();
+  while entries.len() > limit {
+    match entries.iter().rposition(|e| !e.pinned) {
+      Some(index) => evicted.push(entries.remove(index)),
+      None => break,
+    }
+  }
+  evicted
+}
+
+/// Find text entries containing all words of the query, ignoring case. An
+/// empty query matches every entry.
+fn search_entries<'a>(
+  entries: &'a [ClipboardEntry],
+  query: &str,
+) -> impl Iterator<Item = &'a ClipboardEntry> {
+  let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+  entries.iter().filter(move |entry| {
+    if words.is_empty() {
+      return true;
+    }
+    let text = entry.text.as_deref().unwrap_or_default().to_lowercase();
+    words.iter().all(|word| text.contains(word))
+  })
+}
+
+fn history_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  let app_data_dir = app
+    .path()
+    .app_data_dir()
+    .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+  Ok(app_data_dir.join("clipboard-history"))
+}
+
+fn load_history(app: &AppHandle) -> Result<Vec<ClipboardEntry>, String> {
+  let path = history_dir(app)?.join("history.json");
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+
+  let content =
+    fs::read_to_string(&path).map_err(|e| format!("Failed to read clipboard history: {}", e))?;
+  serde_json::from_str(&content).map_err(|e| format!("Failed to parse clipboard history: {}", e))
+}
+
+fn save_history(app: &AppHandle, entries: &[ClipboardEntry]) -> Result<(), String> {
+  let dir = history_dir(app)?;
+  fs::create_dir_all(&dir).map_err(|e| format!("Failed to create clipboard history dir: {}", e))?;
+
+  let content = serde_json::to_string_pretty(entries)
+    .map_err(|e| format!("Failed to serialize clipboard history: {}", e))?;
+  fs::write(dir.join("history.json"), content)
+    .map_err(|e| format!("Failed to write clipboard history: {}", e))
+}
+
+/// Delete the stored images of removed entries
+fn remove_images(entries: &[ClipboardEntry]) {
+  for path in entries.iter().filter_map(|e| e.image_path.as_ref()) {
+    if let Err(e) = fs::remove_file(path) {
+      eprintln!("[ClipboardHistory] Failed to remove {}: {}", path, e);
+    }
+  }
+}
+
+/// Run `f` on the loaded history, save it if `f` changed it and notify
+/// listeners
+fn update_history<T>(
+  app: &AppHandle,
+  f: impl FnOnce(&mut Vec<ClipboardEntry>) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+  let mut history = HISTORY.lock().unwrap();
+  if history.is_none() {
+    *history = Some(load_history(app)?);
+  }
+  let entries = history.as_mut().unwrap();
+
+  let (result, changed) = f(entries)?;
+  if changed {
+    save_history(app, entries)?;
+    if let Err(e) = app.emit(HISTORY_CHANGED_EVENT, ()) {
+      eprintln!("Failed to emit {}: {}", HISTORY_CHANGED_EVENT, e);
+    }
+  }
+  Ok(result)
+}
+
+/// Whether recording is enabled and how many entries to keep
+///
+/// Runs on every clipboard change, so the settings are only read again when
+/// preferences.json was modified.
+fn history_settings(app: &AppHandle) -> (bool, usize) {
+  let modified = get_preferences_path(app)
+    .ok()
+    .and_then(|path| fs::metadata(path).ok()?.modified().ok());
+
+  let mut cached = SETTINGS.lock().unwrap();
+  if let Some((cached_modified, settings)) = *cached {
+    if cached_modified == modified {
+      return settings;
+    }
+  }
+
+  let settings = read_history_settings(app);
+  *cached = Some((modified, settings));
+  settings
+}
+
+fn read_history_settings(app: &AppHandle) -> (bool, usize) {
+  let preferences = load_preferences(app).unwrap_or_default();
+  let clipboard = preferences.get(CLIPBOARD_NAMESPACE);
+
+  let enabled = clipboard
+    .and_then(|prefs| prefs.get("historyEnabled"))
+    .and_then(|value| value.as_bool())
+    .unwrap_or(true);
+  let limit = clipboard
+    .and_then(|prefs| prefs.get("historyLimit"))
+    .and_then(|value| value.as_u64())
+    .map(|limit| limit as usize)
+    .unwrap_or(DEFAULT_HISTORY_LIMIT);
+  (enabled, limit)
+}
+
+/// Check whether a password manager marked the clipboard content as secret
+fn is_sensitive() -> Result<bool, String> {
+  let hint = read_clipboard_format(PASSWORD_HINT_MIME_TYPE.to_string())?;
+  Ok(hint.as_deref() == Some(b"secret".as_slice()))
+}
+
+/// Read the current clipboard content, `None` if there is nothing to record
+fn read_clipboard_content() -> Result<Option<(ClipboardEntryKind, Vec<u8>)>, String> {
+  let formats = crate::get_clipboard_formats()?;
+
+  if formats.iter().any(|f| f.starts_with("text/plain")) {
+    let text = read_clipboard()?;
+    if text.trim().is_empty() || text.len() > MAX_TEXT_BYTES {
+      return Ok(None);
+    }
+    return Ok(Some((ClipboardEntryKind::Text, text.into_bytes())));
+  }
+
+  if formats.iter().any(|f| f == "image/png") {
+    return Ok(
+      read_clipboard_image()?
+        .filter(|data| !data.is_empty() && data.len() <= MAX_IMAGE_BYTES)
+        .map(|data| (ClipboardEntryKind::Image, data)),
+    );
+  }
+
+  Ok(None)
+}
+
+/// Record the current clipboard content
+fn record_clipboard(app: &AppHandle) -> Result<(), String> {
+  let (enabled, limit) = history_settings(app);
+  if !enabled {
+    return Ok(());
+  }
+  let Some((kind, data)) = read_clipboard_content()? else {
+    return Ok(());
+  };
+  let id = content_id(&data);
+
+  // Watchers also report changes that keep the content, e.g. re-copies
+  let is_latest = update_history(app, |entries| {
+    Ok((entries.first().is_some_and(|e| e.id == id), false))
+  })?;
+  if is_latest || is_sensitive()? {
+    return Ok(());
+  }
+
+  let images_dir = history_dir(app)?.join("images");
+  update_history(app, |entries| {
+    let mut entry = ClipboardEntry {
+      id: id.clone(),
+      kind,
+      text: None,
+      image_path: None,
+      size: data.len(),
+      pinned: false,
+      copied_at: chrono::Utc::now().to_rfc3339(),
+    };
+    match kind {
+      ClipboardEntryKind::Text => entry.text = Some(String::from_utf8_lossy(&data).to_string()),
+      ClipboardEntryKind::Image => {
+        let path = images_dir.join(format!("{}.png", id));
+        fs::create_dir_all(&images_dir)
+          .map_err(|e| format!("Failed to create clipboard images dir: {}", e))?;
+        fs::write(&path, &data).map_err(|e| format!("Failed to save clipboard image: {}", e))?;
+        entry.image_path = Some(path.to_string_lossy().to_string());
+      }
+    }
+
+    remove_images(&insert_entry(entries, entry, limit));
+    Ok(((), true))
+  })
+}
+
+/// Start recording clipboard changes in the background
+pub fn start_clipboard_history(app: &AppHandle) {
+  let app = app.clone();
+  watch_clipboard(move || {
+    if let Err(e) = record_clipboard(&app) {
+      eprintln!("[ClipboardHistory] Failed to record clipboard: {}", e);
+    }
+  });
+}
+
+/// Get the newest history entries
+#[tauri::command]
+pub fn get_clipboard_history(
+  app: AppHandle,
+  limit: Option<usize>,
+) -> Result<Vec<ClipboardEntry>, String> {
+  search_clipboard_history(app, String::new(), limit)
+}
+
+/// Search the text entries of the history
+#[tauri::command]
+pub fn search_clipboard_history(
+  app: AppHandle,
+  query: String,
+  limit: Option<usize>,
+) -> Result<Vec<ClipboardEntry>, String> {
+  update_history(&app, |entries| {
+    let matches = search_entries(entries, &query)
+      .take(limit.unwrap_or(usize::MAX))
+      .cloned()
+      .collect();
+    Ok((matches, false))
+  })
+}
+
+/// Pin or unpin an entry, pinned entries are kept when the history is full
+/// or cleared
+#[tauri::command]
+pub fn pin_clipboard_entry(app: AppHandle, id: String, pinned: bool) -> Result<(), String> {
+  update_history(&app, |entries| {
+    let entry = entries
+      .iter_mut()
+      .find(|e| e.id == id)
+      .ok_or_else(|| format!("Clipboard entry not found: {}", id))?;
+    let changed = entry.pinned != pinned;
+    entry.pinned = pinned;
+    Ok(((), changed))
+  })
+}
+
+/// Delete an entry from the history
+#[tauri::command]
+pub fn delete_clipboard_entry(app: AppHandle, id: String) -> Result<(), String> {
+  update_history(&app, |entries| {
+    let index = entries
+      .iter()
+      .position(|e| e.id == id)
+      .ok_or_else(|| format!("Clipboard entry not found: {}", id))?;
+    remove_images(&[entries.remove(index)]);
+    Ok(((), true))
+  })
+}
+
+/// Delete all entries except pinned ones
+#[tauri::command]
+pub fn clear_clipboard_history(app: AppHandle) -> Result<(), String> {
+  update_history(&app, |entries| {
+    let (pinned, removed): (Vec<_>, Vec<_>) = entries.drain(..).partition(|e| e.pinned);
+    *entries = pinned;
+    remove_images(&removed);
+    Ok(((), !removed.is_empty()))
+  })
+}
+
+/// Copy an entry to the clipboard again, moving it to the top of the history
+#[tauri::command]
+pub fn copy_clipboard_entry(app: AppHandle, id: String) -> Result<(), String> {
+  let entry = update_history(&app, |entries| {
+    let entry = entries
+      .iter()
+      .find(|e| e.id == id)
+      .cloned()
+      .ok_or_else(|| format!("Clipboard entry not found: {}", id))?;
+    Ok((entry, false))
+  })?;
+
+  match (entry.text, entry.image_path) {
+    (Some(text), _) => write_clipboard(text),
+    (None, Some(path)) => {
+      let data = fs::read(&path).map_err(|e| format!("Failed to read clipboard image: {}", e))?;
+      write_clipboard_image(data)
+    }
+    (None, None) => Err(format!("Clipboard entry has no content: {}", id)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn text_entry(text: &str, pinned: bool) -> ClipboardEntry {
+    ClipboardEntry {
+      id: content_id(text.as_bytes()),
+      kind: ClipboardEntryKind::Text,
+      text: Some(text.to_string()),
+      image_path: None,
+      size: text.len(),
+      pinned,
+      copied_at: String::new(),
+    }
+  }
+
+  fn texts(entries: &[ClipboardEntry]) -> Vec<&str> {
+    entries.iter().filter_map(|e| e.text.as_deref()).collect()
+  }
+
+  #[test]
+  fn test_content_id() {
+    assert_eq!(content_id(b""), "cbf29ce484222325");
+    assert_eq!(content_id(b"a"), "af63dc4c8601ec8c");
+    assert_ne!(content_id(b"ab"), content_id(b"ba"));
+  }
+
+  #[test]
+  fn test_insert_entry() {
+    let mut entries = Vec::new();
+    insert_entry(&mut entries, text_entry("one", true), 3);
+    insert_entry(&mut entries, text_entry("two", false), 3);
+    insert_entry(&mut entries, text_entry("three", false), 3);
+    assert_eq!(texts(&entries), vec!["three", "two", "one"]);
+
+    // Copying the same content again moves it to the front and keeps the pin
+    insert_entry(&mut entries, text_entry("one", false), 3);
+    assert_eq!(texts(&entries), vec!["one", "three", "two"]);
+    assert!(entries[0].pinned);
+
+    // The oldest unpinned entries are evicted
+    let evicted = insert_entry(&mut entries, text_entry("four", false), 3);
+    assert_eq!(texts(&evicted), vec!["two"]);
+    assert_eq!(texts(&entries), vec!["four", "one", "three"]);
+
+    // Pinned entries may exceed the limit
+    let mut entries = vec![text_entry("a", true), text_entry("b", true)];
+    let evicted = insert_entry(&mut entries, text_entry("c", false), 1);
+    assert_eq!(texts(&evicted), vec!["c"]);
+    assert_eq!(texts(&entries), vec!["a", "b"]);
+  }
+
+  #[test]
+  fn test_search_entries() {
+    let mut image = text_entry("", false);
+    image.kind = ClipboardEntryKind::Image;
+    image.text = None;
+    let entries = vec![
+      text_entry("Hello World", false),
+      image,
+      text_entry("world peace", false),
+    ];
+
+    assert_eq!(search_entries(&entries, "").count(), 3);
+    assert_eq!(
+      texts(
+        &search_entries(&entries, "WORLD")
+          .cloned()
+          .collect::<Vec<_>>()
+      ),
+      vec!["Hello World", "world peace"]
+    );
+    assert_eq!(
+      texts(
+        &search_entries(&entries, "world hello")
+          .cloned()
+          .collect::<Vec<_>>()
+      ),
+      vec!["Hello World"]
+    );
+  }
+}
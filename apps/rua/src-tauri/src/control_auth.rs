@@ -0,0 +1,164 @@
+//! Control Auth Module
+//!
+//! Protects the control server with a per-run bearer token. The token is
+//! generated at startup and written to a file only the current user can read,
+//! where `ruactl` picks it up. Requests carrying an `Origin` header come from a
+//! browser and are always rejected, so web pages can't drive the launcher.
+
+use std::{fs, path::PathBuf, sync::Arc};
+
+use axum::{
+  extract::{Request, State},
+  http::{header, StatusCode},
+  middleware::Next,
+  response::{IntoResponse, Response},
+  Json,
+};
+use base64::Engine;
+use rand::RngCore;
+
+use crate::control_paths::control_token_path;
+
+/// Generate a new random token and write it to the token file (mode 0600)
+pub fn create_control_token() -> anyhow::Result<String> {
+  let mut bytes = [0u8; 32];
+  rand::thread_rng().fill_bytes(&mut bytes);
+  let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+
+  let token_path = control_token_path();
+  if let Some(parent) = token_path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  write_private_file(&token_path, token.as_bytes())?;
+
+  Ok(token)
+}
+
+#[cfg(unix)]
+fn write_private_file(path: &PathBuf, contents: &[u8]) -> std::io::Result<()> {
+  use std::{
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+  };
+
+  let mut file = fs::OpenOptions::new()
+    .write(true)
+    .create(true)
+    .truncate(true)
+    .mode(0o600)
+    .open(path)?;
+  // The mode only applies on creation, so tighten an existing file as well
+  file.set_permissions(fs::Permissions::from_mode(0o600))?;
+  file.write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private_file(path: &PathBuf, contents: &[u8]) -> std::io::Result<()> {
+  fs::write(path, contents)
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  if a.len() != b.len() {
+    return false;
+  }
+  a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn reject(status: StatusCode, message: &str) -> Response {
+  (
+    status,
+    Json(serde_json::json!({
+      "success": false,
+      "message": message,
+    })),
+  )
+    .into_response()
+}
+
+/// Middleware: reject browser requests and requests without a valid bearer token
+pub async fn require_auth(
+  State(token): State<Arc<String>>,
+  request: Request,
+  next: Next,
+) -> Response {
+  if request.headers().contains_key(header::ORIGIN) {
+    return reject(
+      StatusCode::FORBIDDEN,
+      "Cross-origin requests are not allowed",
+    );
+  }
+
+  let provided = request
+    .headers()
+    .get(header::AUTHORIZATION)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.strip_prefix("Bearer "));
+
+  match provided {
+    Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => {
+      next.run(request).await
+    }
+    _ => reject(StatusCode::UNAUTHORIZED, "Missing or invalid control token"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::{body::Body, http::Request, middleware, routing::post, Router};
+  use tower::ServiceExt;
+
+  use super::*;
+
+  fn router() -> Router {
+    Router::new()
+      .route("/health", post(|| async { "ok" }))
+      .layer(middleware::from_fn_with_state(
+        Arc::new("secret".to_string()),
+        require_auth,
+      ))
+  }
+
+  async fn status(headers: &[(header::HeaderName, &str)]) -> StatusCode {
+    let mut request = Request::post("/health");
+    for (name, value) in headers {
+      request = request.header(name, *value);
+    }
+    router()
+      .oneshot(request.body(Body::empty()).unwrap())
+      .await
+      .unwrap()
+      .status()
+  }
+
+  #[tokio::test]
+  async fn test_require_auth() {
+    assert_eq!(status(&[]).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+      status(&[(header::AUTHORIZATION, "Bearer wrong")]).await,
+      StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+      status(&[(header::AUTHORIZATION, "secret")]).await,
+      StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+      status(&[(header::AUTHORIZATION, "Bearer secret")]).await,
+      StatusCode::OK
+    );
+
+    // A valid token doesn't let browser requests through
+    assert_eq!(
+      status(&[
+        (header::AUTHORIZATION, "Bearer secret"),
+        (header::ORIGIN, "http://localhost:1420"),
+      ])
+      .await,
+      StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+      status(&[(header::ORIGIN, "null")]).await,
+      StatusCode::FORBIDDEN
+    );
+  }
+}
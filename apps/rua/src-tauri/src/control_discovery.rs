@@ -0,0 +1,36 @@
+//! Control Discovery Module
+//!
+//! Writes the endpoints the control server actually listens on to
+//! `control.json` next to the control token, so `ruactl` can find the server
+//! when the port is configured or picked by the OS. Bind failures are recorded
+//! in the same file so `ruactl` can tell "not running" from "failed to start".
+
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::control_paths::control_discovery_path;
+
+/// Contents of the discovery file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlDiscovery {
+  /// PID of the rua process that wrote the file
+  pub pid: u32,
+  /// Base URL of the TCP listener, e.g. `http://127.0.0.1:7777`
+  pub url: Option<String>,
+  /// Path of the Unix control socket
+  pub socket: Option<PathBuf>,
+  /// Errors from listeners that failed to start, and warnings about exposed ones
+  #[serde(default)]
+  pub errors: Vec<String>,
+}
+
+/// Write the discovery file
+pub fn write_control_discovery(discovery: &ControlDiscovery) -> anyhow::Result<()> {
+  let path = control_discovery_path();
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::write(&path, serde_json::to_string_pretty(discovery)?)?;
+  Ok(())
+}
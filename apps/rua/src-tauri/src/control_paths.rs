@@ -0,0 +1,43 @@
+//! Control Paths Module
+//!
+//! Where the control server keeps its runtime files. Shared by the app and
+//! ruactl, which includes this file with `#[path]`, so it must only depend on std.
+
+use std::path::PathBuf;
+
+/// Get the directory holding the control server runtime files
+///
+/// Uses `$XDG_RUNTIME_DIR/rua`, falling back to `$XDG_CACHE_HOME/rua`.
+pub fn control_runtime_dir() -> PathBuf {
+  if let Some(runtime_dir) = std::env::var("XDG_RUNTIME_DIR")
+    .ok()
+    .filter(|dir| !dir.is_empty())
+  {
+    return PathBuf::from(runtime_dir).join("rua");
+  }
+
+  let cache_dir = std::env::var("XDG_CACHE_HOME")
+    .unwrap_or_else(|_| format!("{}/.cache", std::env::var("HOME").unwrap_or_default()));
+  PathBuf::from(cache_dir).join("rua")
+}
+
+/// Get the control token file path
+pub fn control_token_path() -> PathBuf {
+  control_runtime_dir().join("control.token")
+}
+
+/// Get the discovery file path
+pub fn control_discovery_path() -> PathBuf {
+  control_runtime_dir().join("control.json")
+}
+
+/// Get the control socket path (`$XDG_RUNTIME_DIR/rua/control.sock`)
+///
+/// There is no fallback: the socket is only created in the user's runtime dir.
+#[cfg_attr(not(unix), allow(dead_code))]
+pub fn control_socket_path() -> Option<PathBuf> {
+  let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+    .ok()
+    .filter(|dir| !dir.is_empty())?;
+  Some(PathBuf::from(runtime_dir).join("rua").join("control.sock"))
+}
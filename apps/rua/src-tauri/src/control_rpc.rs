@@ -0,0 +1,555 @@
+//! Control RPC Module
+//!
+//! JSON-RPC 2.0 bridge to the backend commands, served at `POST /rpc` on the
+//! control server. Method names and parameter names match the Tauri commands
+//! invoked by the frontend (e.g. `fs_read_text_file` with `{ "path": ... }`).
+//!
+//! Every method is denied unless it is listed in the `rpcAllowedMethods`
+//! preference of the `system.advanced` namespace. Entries are method names,
+//! prefixes ending in `*` (e.g. `fs_*`), or `*` for everything.
+//!
+//! The allowlist is a convenience filter, not a security boundary: methods like
+//! `execute_shell_command` can do anything the user can, so only allow them for
+//! clients you trust. RPC can't edit the allowlist directly, neither through the
+//! preference methods nor by writing files under the app config directory.
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+#[cfg(target_os = "linux")]
+use crate::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::*;
+use crate::{
+  clipboard_history, extension::*, file_search, fs_api, preferences, preferences::load_preferences,
+  webpage_info,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The command itself returned an error
+const COMMAND_ERROR: i64 = -32000;
+/// The method exists but is not in the allowlist
+const METHOD_NOT_ALLOWED: i64 = -32001;
+
+const ALLOWLIST_NAMESPACE: &str = "system.advanced";
+const ALLOWLIST_KEY: &str = "rpcAllowedMethods";
+
+/// Methods exposed over RPC
+const RPC_METHODS: &[&str] = &[
+  "get_applications",
+  "refresh_applications_cache",
+  "launch_application",
+  "launch_application_with",
+  "get_file_handlers",
+  "set_default_application",
+  "read_clipboard",
+  "write_clipboard",
+  "get_clipboard_formats",
+  "read_clipboard_format",
+  "write_clipboard_format",
+  "read_clipboard_image",
+  "write_clipboard_image",
+  "read_clipboard_files",
+  "write_clipboard_files",
+  "get_selected_text",
+  "get_clipboard_history",
+  "search_clipboard_history",
+  "pin_clipboard_entry",
+  "delete_clipboard_entry",
+  "clear_clipboard_history",
+  "copy_clipboard_entry",
+  "execute_shell_command",
+  "execute_shell_command_async",
+  "fetch_page_info",
+  "get_extensions",
+  "install_extension",
+  "uninstall_extension",
+  "enable_extension",
+  "disable_extension",
+  "get_extensions_path",
+  "show_notification",
+  "close_notification",
+  "extension_storage_get",
+  "extension_storage_set",
+  "extension_storage_remove",
+  "search_files",
+  "open_file",
+  "validate_search_paths",
+  "fs_read_text_file",
+  "fs_read_binary_file",
+  "fs_write_text_file",
+  "fs_write_binary_file",
+  "fs_read_dir",
+  "fs_exists",
+  "fs_stat",
+  "get_preference",
+  "get_all_preferences",
+  "set_preference",
+  "set_all_preferences",
+  "remove_preference",
+  "remove_all_preferences",
+];
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+  jsonrpc: String,
+  method: String,
+  #[serde(default)]
+  params: Value,
+  id: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+  code: i64,
+  message: String,
+}
+
+impl RpcError {
+  fn new(code: i64, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+  jsonrpc: &'static str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  result: Option<Value>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<RpcError>,
+  id: Value,
+}
+
+impl RpcResponse {
+  fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+    let (result, error) = match outcome {
+      Ok(result) => (Some(result), None),
+      Err(error) => (None, Some(error)),
+    };
+    Self {
+      jsonrpc: "2.0",
+      result,
+      error,
+      id,
+    }
+  }
+}
+
+/// Handle a JSON-RPC request body (single call or batch)
+///
+/// Returns `None` when nothing needs to be sent back, i.e. when the body only
+/// contained notifications.
+pub async fn handle_rpc(app: &AppHandle, body: &[u8]) -> Option<Value> {
+  let body: Value = match serde_json::from_slice(body) {
+    Ok(body) => body,
+    Err(e) => {
+      let response = RpcResponse::new(
+        Value::Null,
+        Err(RpcError::new(PARSE_ERROR, format!("Parse error: {}", e))),
+      );
+      return serde_json::to_value(response).ok();
+    }
+  };
+
+  match body {
+    Value::Array(calls) if calls.is_empty() => {
+      let response = RpcResponse::new(
+        Value::Null,
+        Err(RpcError::new(INVALID_REQUEST, "Empty batch")),
+      );
+      serde_json::to_value(response).ok()
+    }
+    Value::Array(calls) => {
+      let mut responses = Vec::new();
+      for call in calls {
+        if let Some(response) = handle_call(app, call).await {
+          responses.push(response);
+        }
+      }
+      if responses.is_empty() {
+        None
+      } else {
+        serde_json::to_value(responses).ok()
+      }
+    }
+    call => handle_call(app, call)
+      .await
+      .and_then(|response| serde_json::to_value(response).ok()),
+  }
+}
+
+async fn handle_call(app: &AppHandle, call: Value) -> Option<RpcResponse> {
+  let request: RpcRequest = match serde_json::from_value(call) {
+    Ok(request) => request,
+    Err(e) => {
+      return Some(RpcResponse::new(
+        Value::Null,
+        Err(RpcError::new(
+          INVALID_REQUEST,
+          format!("Invalid request: {}", e),
+        )),
+      ))
+    }
+  };
+
+  let outcome = if request.jsonrpc != "2.0" {
+    Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+  } else {
+    call_method(app, &request.method, request.params).await
+  };
+
+  // Requests without an id are notifications and get no response
+  request.id.map(|id| RpcResponse::new(id, outcome))
+}
+
+/// Load the method allowlist from preferences
+fn load_allowlist(app: &AppHandle) -> Result<Vec<String>, String> {
+  let preferences = load_preferences(app)?;
+  let value = preferences
+    .get(ALLOWLIST_NAMESPACE)
+    .and_then(|prefs| prefs.get(ALLOWLIST_KEY));
+
+  // Accept a JSON array, or a comma/whitespace separated string as saved by the settings UI
+  let entries = match value {
+    Some(Value::Array(items)) => items
+      .iter()
+      .filter_map(|item| item.as_str())
+      .map(|item| item.trim().to_string())
+      .collect(),
+    Some(Value::String(items)) => items
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .map(|item| item.to_string())
+      .collect(),
+    _ => Vec::new(),
+  };
+
+  Ok(
+    entries
+      .into_iter()
+      .filter(|entry| !entry.is_empty())
+      .collect(),
+  )
+}
+
+/// Check whether a method matches an allowlist
+fn is_method_allowed(allowlist: &[String], method: &str) -> bool {
+  allowlist.iter().any(|entry| match entry.strip_suffix('*') {
+    Some(prefix) => method.starts_with(prefix),
+    None => entry == method,
+  })
+}
+
+/// Refuse preference writes that would change the allowlist over RPC
+fn guard_allowlist(namespace: &str, key: Option<&str>) -> Result<(), RpcError> {
+  if namespace == ALLOWLIST_NAMESPACE && (key.is_none() || key == Some(ALLOWLIST_KEY)) {
+    return Err(RpcError::new(
+      METHOD_NOT_ALLOWED,
+      format!(
+        "{}.{} can't be changed over RPC",
+        ALLOWLIST_NAMESPACE, ALLOWLIST_KEY
+      ),
+    ));
+  }
+  Ok(())
+}
+
+/// Resolve `..` and symlinks in a path whose last components may not exist yet
+fn resolve_path(path: &Path) -> PathBuf {
+  let path = std::env::current_dir().unwrap_or_default().join(path);
+  let mut resolved = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        resolved.pop();
+      }
+      component => {
+        resolved.push(component);
+        if let Ok(canonical) = resolved.canonicalize() {
+          resolved = canonical;
+        }
+      }
+    }
+  }
+  resolved
+}
+
+/// Refuse file writes under the app config directory, where preferences.json
+/// holds the allowlist
+fn guard_config_write(app: &AppHandle, path: &str) -> Result<(), RpcError> {
+  let config_dir = app
+    .path()
+    .app_config_dir()
+    .map_err(|e| RpcError::new(COMMAND_ERROR, format!("Failed to get config dir: {}", e)))?;
+
+  if resolve_path(Path::new(&fs_api::expand_path(path))).starts_with(resolve_path(&config_dir)) {
+    return Err(RpcError::new(
+      METHOD_NOT_ALLOWED,
+      format!("{} can't be written over RPC", config_dir.display()),
+    ));
+  }
+  Ok(())
+}
+
+/// Read a named parameter, treating a missing one as `null`
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, RpcError> {
+  let value = params.get(name).cloned().unwrap_or(Value::Null);
+  serde_json::from_value(value)
+    .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid param \"{}\": {}", name, e)))
+}
+
+/// Convert a command result into an RPC outcome
+fn to_outcome<T: Serialize>(result: Result<T, String>) -> Result<Value, RpcError> {
+  let value = result.map_err(|e| RpcError::new(COMMAND_ERROR, e))?;
+  serde_json::to_value(value)
+    .map_err(|e| RpcError::new(COMMAND_ERROR, format!("Failed to serialize result: {}", e)))
+}
+
+async fn call_method(app: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+  if !RPC_METHODS.contains(&method) {
+    return Err(RpcError::new(
+      METHOD_NOT_FOUND,
+      format!("Method not found: {}", method),
+    ));
+  }
+
+  let allowlist = load_allowlist(app).map_err(|e| RpcError::new(COMMAND_ERROR, e))?;
+  if !is_method_allowed(&allowlist, method) {
+    return Err(RpcError::new(
+      METHOD_NOT_ALLOWED,
+      format!(
+        "Method not allowed: {} (add it to {}.{})",
+        method, ALLOWLIST_NAMESPACE, ALLOWLIST_KEY
+      ),
+    ));
+  }
+
+  if !params.is_null() && !params.is_object() {
+    return Err(RpcError::new(
+      INVALID_PARAMS,
+      "params must be an object of named parameters",
+    ));
+  }
+  let p = &params;
+  let app = app.clone();
+
+  match method {
+    "get_applications" => to_outcome(Ok(get_applications().await)),
+    "refresh_applications_cache" => to_outcome(refresh_applications_cache(app).await),
+    "launch_application" => to_outcome(launch_application(
+      app,
+      param(p, "exec")?,
+      param::<Option<bool>>(p, "terminal")?.unwrap_or(false),
+      param(p, "workingDir")?,
+    )),
+    "launch_application_with" => to_outcome(launch_application_with(
+      app,
+      param(p, "appId")?,
+      param::<Option<Vec<String>>>(p, "uris")?.unwrap_or_default(),
+    )),
+    "get_file_handlers" => to_outcome(get_file_handlers(param(p, "path")?)),
+    "set_default_application" => to_outcome(set_default_application(
+      param(p, "mimeType")?,
+      param(p, "appId")?,
+    )),
+    "read_clipboard" => to_outcome(read_clipboard()),
+    "write_clipboard" => to_outcome(write_clipboard(param(p, "text")?)),
+    "get_clipboard_formats" => to_outcome(get_clipboard_formats()),
+    "read_clipboard_format" => to_outcome(read_clipboard_format(param(p, "mimeType")?)),
+    "write_clipboard_format" => to_outcome(write_clipboard_format(
+      param(p, "mimeType")?,
+      param(p, "data")?,
+    )),
+    "read_clipboard_image" => to_outcome(read_clipboard_image()),
+    "write_clipboard_image" => to_outcome(write_clipboard_image(param(p, "data")?)),
+    "read_clipboard_files" => to_outcome(read_clipboard_files()),
+    "write_clipboard_files" => to_outcome(write_clipboard_files(param(p, "paths")?)),
+    "get_selected_text" => to_outcome(Ok(get_selected_text())),
+    "get_clipboard_history" => to_outcome(clipboard_history::get_clipboard_history(
+      app,
+      param(p, "limit")?,
+    )),
+    "search_clipboard_history" => to_outcome(clipboard_history::search_clipboard_history(
+      app,
+      param(p, "query")?,
+      param(p, "limit")?,
+    )),
+    "pin_clipboard_entry" => to_outcome(clipboard_history::pin_clipboard_entry(
+      app,
+      param(p, "id")?,
+      param(p, "pinned")?,
+    )),
+    "delete_clipboard_entry" => to_outcome(clipboard_history::delete_clipboard_entry(
+      app,
+      param(p, "id")?,
+    )),
+    "clear_clipboard_history" => to_outcome(clipboard_history::clear_clipboard_history(app)),
+    "copy_clipboard_entry" => to_outcome(clipboard_history::copy_clipboard_entry(
+      app,
+      param(p, "id")?,
+    )),
+    "execute_shell_command" => to_outcome(execute_shell_command(param(p, "command")?).await),
+    "execute_shell_command_async" => {
+      to_outcome(execute_shell_command_async(param(p, "command")?).await)
+    }
+    "fetch_page_info" => to_outcome(webpage_info::fetch_page_info(param(p, "url")?).await),
+    "get_extensions" => to_outcome(get_extensions(app).await),
+    "install_extension" => to_outcome(install_extension(app, param(p, "sourcePath")?).await),
+    "uninstall_extension" => to_outcome(
+      uninstall_extension(app, param(p, "extensionId")?, param(p, "extensionPath")?).await,
+    ),
+    "enable_extension" => to_outcome(enable_extension(app, param(p, "extensionId")?).await),
+    "disable_extension" => to_outcome(disable_extension(app, param(p, "extensionId")?).await),
+    "get_extensions_path" => to_outcome(get_extensions_path(app).await),
+    "show_notification" => to_outcome(
+      show_notification(
+        app,
+        param(p, "title")?,
+        param(p, "body")?,
+        param(p, "options")?,
+        None,
+      )
+      .await,
+    ),
+    "close_notification" => to_outcome(close_notification(param(p, "id")?).await),
+    "extension_storage_get" => {
+      to_outcome(extension_storage_get(app, param(p, "extensionId")?, param(p, "key")?).await)
+    }
+    "extension_storage_set" => to_outcome(
+      extension_storage_set(
+        app,
+        param(p, "extensionId")?,
+        param(p, "key")?,
+        param(p, "value")?,
+      )
+      .await,
+    ),
+    "extension_storage_remove" => {
+      to_outcome(extension_storage_remove(app, param(p, "extensionId")?, param(p, "key")?).await)
+    }
+    "search_files" => to_outcome(
+      file_search::search_files(
+        param(p, "query")?,
+        param(p, "maxResults")?,
+        param(p, "searchPaths")?,
+      )
+      .await,
+    ),
+    "open_file" => {
+      to_outcome(file_search::open_file(app, param(p, "path")?, param(p, "method")?).await)
+    }
+    "validate_search_paths" => to_outcome(file_search::validate_search_paths(param(p, "paths")?)),
+    "fs_read_text_file" => to_outcome(fs_api::fs_read_text_file(param(p, "path")?).await),
+    "fs_read_binary_file" => to_outcome(fs_api::fs_read_binary_file(param(p, "path")?).await),
+    "fs_write_text_file" => {
+      let path: String = param(p, "path")?;
+      guard_config_write(&app, &path)?;
+      to_outcome(fs_api::fs_write_text_file(path, param(p, "contents")?).await)
+    }
+    "fs_write_binary_file" => {
+      let path: String = param(p, "path")?;
+      guard_config_write(&app, &path)?;
+      to_outcome(fs_api::fs_write_binary_file(path, param(p, "contents")?).await)
+    }
+    "fs_read_dir" => to_outcome(fs_api::fs_read_dir(param(p, "path")?).await),
+    "fs_exists" => to_outcome(fs_api::fs_exists(param(p, "path")?).await),
+    "fs_stat" => to_outcome(fs_api::fs_stat(param(p, "path")?).await),
+    "get_preference" => {
+      to_outcome(preferences::get_preference(app, param(p, "namespace")?, param(p, "key")?).await)
+    }
+    "get_all_preferences" => {
+      to_outcome(preferences::get_all_preferences(app, param(p, "namespace")?).await)
+    }
+    "set_preference" => {
+      let (namespace, key): (String, String) = (param(p, "namespace")?, param(p, "key")?);
+      guard_allowlist(&namespace, Some(&key))?;
+      to_outcome(preferences::set_preference(app, namespace, key, param(p, "value")?).await)
+    }
+    "set_all_preferences" => {
+      let namespace: String = param(p, "namespace")?;
+      guard_allowlist(&namespace, None)?;
+      to_outcome(preferences::set_all_preferences(app, namespace, param(p, "values")?).await)
+    }
+    "remove_preference" => {
+      let (namespace, key): (String, String) = (param(p, "namespace")?, param(p, "key")?);
+      guard_allowlist(&namespace, Some(&key))?;
+      to_outcome(preferences::remove_preference(app, namespace, key).await)
+    }
+    "remove_all_preferences" => {
+      let namespace: String = param(p, "namespace")?;
+      guard_allowlist(&namespace, None)?;
+      to_outcome(preferences::remove_all_preferences(app, namespace).await)
+    }
+    _ => Err(RpcError::new(
+      METHOD_NOT_FOUND,
+      format!("Method not found: {}", method),
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_method_allowed() {
+    let allowlist = vec!["fs_*".to_string(), "get_applications".to_string()];
+    assert!(is_method_allowed(&allowlist, "fs_read_text_file"));
+    assert!(is_method_allowed(&allowlist, "get_applications"));
+    assert!(!is_method_allowed(&allowlist, "get_applications_cache"));
+    assert!(!is_method_allowed(&allowlist, "execute_shell_command"));
+    assert!(is_method_allowed(
+      &["*".to_string()],
+      "execute_shell_command"
+    ));
+    assert!(!is_method_allowed(&[], "fs_exists"));
+  }
+
+  #[test]
+  fn test_resolve_path() {
+    assert_eq!(
+      resolve_path(Path::new("/nonexistent-rua/a/../b/./c")),
+      PathBuf::from("/nonexistent-rua/b/c")
+    );
+    assert_eq!(
+      resolve_path(Path::new("nonexistent-rua")),
+      std::env::current_dir()
+        .unwrap()
+        .canonicalize()
+        .unwrap()
+        .join("nonexistent-rua")
+    );
+  }
+
+  #[test]
+  fn test_rpc_methods_dispatched() {
+    // The dispatch arms are the `"method" =>` lines of call_method
+    let source = include_str!("control_rpc.rs");
+    let arms: Vec<&str> = source
+      .lines()
+      .filter_map(|line| line.trim().strip_prefix('"')?.split_once("\" =>"))
+      .map(|(method, _)| method)
+      .collect();
+
+    for method in RPC_METHODS {
+      assert!(arms.contains(method), "{} has no dispatch arm", method);
+    }
+    for method in &arms {
+      assert!(
+        RPC_METHODS.contains(method),
+        "{} is missing from RPC_METHODS",
+        method
+      );
+    }
+  }
+}
@@ -1,16 +1,51 @@
-use std::sync::Arc;
+use std::{
+  collections::HashMap,
+  net::{IpAddr, Ipv4Addr, SocketAddr},
+  sync::Arc,
+};
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use axum::{
+  body::Bytes,
+  extract::{Path, State},
+  http::StatusCode,
+  middleware,
+  response::{
+    sse::{Event as SseEvent, KeepAlive, Sse},
+    IntoResponse,
+  },
+  routing::{get, post},
+  Json, Router,
+};
+use futures_util::{stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager};
-use tokio::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tokio::sync::{broadcast::error::RecvError, Mutex};
 
 #[cfg(target_os = "linux")]
 use crate::linux::*;
 #[cfg(not(target_os = "linux"))]
 use crate::not_linux::*;
+use crate::{
+  control_auth::{create_control_token, require_auth},
+  control_discovery::{write_control_discovery, ControlDiscovery},
+  control_paths::control_token_path,
+  control_rpc::handle_rpc,
+  diagnostics::collect_diagnostics,
+  event_stream::{subscribe_events, StreamEvent},
+  extension::{
+    disable_extension, enable_extension, get_extension_state, get_extensions, install_extension,
+    register_dev_extension, run_action, uninstall_extension, update_extension,
+  },
+  preferences::load_preferences,
+  types::WindowShownPayload,
+};
 
-const SERVER_PORT: u16 = 7777;
+const DEFAULT_SERVER_PORT: u16 = 7777;
+
+/// Env var overriding the TCP bind address (`host:port` or just a port)
+const CONTROL_ADDR_ENV: &str = "RUA_CONTROL_ADDR";
+/// Env var allowing the control server to listen on non-loopback addresses
+const ALLOW_REMOTE_ENV: &str = "RUA_CONTROL_ALLOW_REMOTE";
 
 #[derive(Clone)]
 pub struct AppState {
@@ -21,6 +56,33 @@ pub struct AppState {
 pub struct Response {
   success: bool,
   message: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  data: Option<serde_json::Value>,
+}
+
+/// Request body of `/actions/run`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunActionRequest {
+  action_id: String,
+  #[serde(default)]
+  args: HashMap<String, String>,
+}
+
+/// Body of the `/extensions/*` routes
+#[derive(Deserialize, Default)]
+pub struct ExtensionRequest {
+  #[serde(default)]
+  pub id: Option<String>,
+  #[serde(default)]
+  pub source: Option<String>,
+}
+
+/// Body of `/dev`; a missing path unregisters the dev extension
+#[derive(Deserialize, Default)]
+pub struct DevRequest {
+  #[serde(default)]
+  pub path: Option<String>,
 }
 
 #[tauri::command]
@@ -31,51 +93,348 @@ pub async fn hide_window_command(app: AppHandle) -> Result<String, String> {
   Ok("OK".to_string())
 }
 
+/// Get the app handle, or the response to return while it isn't available
+async fn app_handle(state: &AppState) -> Result<AppHandle, (StatusCode, Json<Response>)> {
+  state.app_handle.lock().await.clone().ok_or_else(|| {
+    (
+      StatusCode::INTERNAL_SERVER_ERROR,
+      Json(Response {
+        success: false,
+        message: "App handle not available".to_string(),
+        data: None,
+      }),
+    )
+  })
+}
+
+/// Run a window operation against the main window and wrap the result in a response
+async fn with_main_window(
+  state: &AppState,
+  operation: &str,
+  f: impl FnOnce(WebviewWindow) -> anyhow::Result<String>,
+) -> (StatusCode, Json<Response>) {
+  let app = match app_handle(state).await {
+    Ok(app) => app,
+    Err(response) => return response,
+  };
+
+  let Some(window) = app.get_webview_window("main") else {
+    return (
+      StatusCode::INTERNAL_SERVER_ERROR,
+      Json(Response {
+        success: false,
+        message: "Main window not found".to_string(),
+        data: None,
+      }),
+    );
+  };
+
+  match f(window) {
+    Ok(message) => (
+      StatusCode::OK,
+      Json(Response {
+        success: true,
+        message,
+        data: None,
+      }),
+    ),
+    Err(e) => (
+      StatusCode::INTERNAL_SERVER_ERROR,
+      Json(Response {
+        success: false,
+        message: format!("Failed to {} window: {}", operation, e),
+        data: None,
+      }),
+    ),
+  }
+}
+
 /// Toggle window visibility
 async fn toggle_window(State(state): State<AppState>) -> impl IntoResponse {
-  let app_handle = state.app_handle.lock().await;
-
-  if let Some(app) = app_handle.as_ref() {
-    if let Some(window) = app.get_webview_window("main") {
-      let result = match window.is_visible() {
-        Ok(true) => hide_window(window),
-        Ok(false) => show_window(window),
-        Err(e) => Err(anyhow::anyhow!(e)),
+  with_main_window(&state, "toggle", |window| match window.is_visible() {
+    Ok(true) => hide_window(window),
+    Ok(false) => show_window(window, WindowShownPayload::default()),
+    Err(e) => Err(anyhow::anyhow!(e)),
+  })
+  .await
+}
+
+/// Show the window, optionally with a pre-filled query and a target view
+async fn show_window_with(
+  State(state): State<AppState>,
+  request: Option<Json<WindowShownPayload>>,
+) -> impl IntoResponse {
+  let payload = request.map(|Json(payload)| payload).unwrap_or_default();
+  with_main_window(&state, "show", |window| show_window(window, payload)).await
+}
+
+/// Hide the window
+async fn hide_window_route(State(state): State<AppState>) -> impl IntoResponse {
+  with_main_window(&state, "hide", hide_window).await
+}
+
+/// Run an extension action without opening the UI
+async fn run_action_route(
+  State(state): State<AppState>,
+  Json(request): Json<RunActionRequest>,
+) -> impl IntoResponse {
+  let app = match app_handle(&state).await {
+    Ok(app) => app,
+    Err(response) => return response,
+  };
+
+  match run_action(&app, &request.action_id, request.args).await {
+    Ok(data) => (
+      StatusCode::OK,
+      Json(Response {
+        success: true,
+        message: format!("Action {} completed", request.action_id),
+        data: Some(data),
+      }),
+    ),
+    Err(e) => (
+      StatusCode::BAD_REQUEST,
+      Json(Response {
+        success: false,
+        message: e,
+        data: None,
+      }),
+    ),
+  }
+}
+
+fn to_data<T: Serialize>(value: T) -> Result<Option<serde_json::Value>, String> {
+  serde_json::to_value(value)
+    .map(Some)
+    .map_err(|e| format!("Failed to serialize: {}", e))
+}
+
+/// Run an extension management operation for `ruactl ext`
+async fn extension_operation(
+  app: AppHandle,
+  operation: &str,
+  request: ExtensionRequest,
+) -> Result<(String, Option<serde_json::Value>), String> {
+  let require_id = || request.id.clone().ok_or("Missing extension id".to_string());
+
+  match operation {
+    "list" => {
+      let extensions = get_extensions(app).await?;
+      let message = format!("{} extensions installed", extensions.len());
+      Ok((message, to_data(extensions)?))
+    }
+    "info" => {
+      let id = require_id()?;
+      let state = get_extension_state(&app, &id)?;
+      let extension = get_extensions(app)
+        .await?
+        .into_iter()
+        .find(|ext| ext.manifest.id == id)
+        .ok_or_else(|| format!("Extension not installed: {}", id))?;
+      let data = serde_json::json!({ "extension": extension, "state": state });
+      Ok((format!("Extension {}", id), Some(data)))
+    }
+    "install" => {
+      let source = request.source.clone().ok_or("Missing source")?;
+      let extension = install_extension(app, source).await?;
+      let message = format!("Installed {}", extension.manifest.id);
+      Ok((message, to_data(extension)?))
+    }
+    "update" => {
+      let extension = update_extension(app, require_id()?).await?;
+      let message = format!(
+        "Updated {} to {}",
+        extension.manifest.id, extension.manifest.version
+      );
+      Ok((message, to_data(extension)?))
+    }
+    "enable" | "disable" | "uninstall" => {
+      let id = require_id()?;
+      let extension = get_extensions(app.clone())
+        .await?
+        .into_iter()
+        .find(|ext| ext.manifest.id == id)
+        .ok_or_else(|| format!("Extension not installed: {}", id))?;
+
+      let done = match operation {
+        "enable" => enable_extension(app, id.clone()).await.map(|_| "enabled")?,
+        "disable" => disable_extension(app, id.clone())
+          .await
+          .map(|_| "disabled")?,
+        _ => uninstall_extension(app, id.clone(), Some(extension.path))
+          .await
+          .map(|_| "uninstalled")?,
       };
+      Ok((format!("{} {}", id, done), None))
+    }
+    _ => Err(format!("Unknown extension operation: {}", operation)),
+  }
+}
+
+/// Manage extensions: `/extensions/{list,info,install,update,enable,disable,uninstall}`
+async fn extension_route(
+  State(state): State<AppState>,
+  Path(operation): Path<String>,
+  request: Option<Json<ExtensionRequest>>,
+) -> impl IntoResponse {
+  let app = match app_handle(&state).await {
+    Ok(app) => app,
+    Err(response) => return response,
+  };
+
+  let request = request.map(|Json(r)| r).unwrap_or_default();
+  match extension_operation(app, &operation, request).await {
+    Ok((message, data)) => (
+      StatusCode::OK,
+      Json(Response {
+        success: true,
+        message,
+        data,
+      }),
+    ),
+    Err(e) => (
+      StatusCode::BAD_REQUEST,
+      Json(Response {
+        success: false,
+        message: e,
+        data: None,
+      }),
+    ),
+  }
+}
+
+/// Register a directory as the dev extension for `ruactl dev`
+async fn dev_extension_route(
+  State(state): State<AppState>,
+  request: Option<Json<DevRequest>>,
+) -> impl IntoResponse {
+  let app = match app_handle(&state).await {
+    Ok(app) => app,
+    Err(response) => return response,
+  };
+
+  let request = request.map(|Json(r)| r).unwrap_or_default();
+  let result = register_dev_extension(&app, request.path.as_deref()).and_then(|info| {
+    let message = match &info {
+      Some(info) => format!("Registered dev extension {}", info.manifest.id),
+      None => "Unregistered dev extension".to_string(),
+    };
+    Ok((message, to_data(info)?))
+  });
+
+  match result {
+    Ok((message, data)) => (
+      StatusCode::OK,
+      Json(Response {
+        success: true,
+        message,
+        data,
+      }),
+    ),
+    Err(e) => (
+      StatusCode::BAD_REQUEST,
+      Json(Response {
+        success: false,
+        message: e,
+        data: None,
+      }),
+    ),
+  }
+}
 
-      match result {
-        Ok(message) => (
-          StatusCode::OK,
-          Json(Response {
-            success: true,
-            message,
-          }),
-        ),
-        Err(e) => (
-          StatusCode::INTERNAL_SERVER_ERROR,
-          Json(Response {
-            success: false,
-            message: format!("Failed to toggle window: {}", e),
-          }),
-        ),
+/// JSON-RPC 2.0 bridge to the backend commands
+///
+/// RPC errors are reported in the JSON-RPC response body with status 200, as
+/// the spec expects. Notifications get an empty 204 response.
+async fn rpc_route(State(state): State<AppState>, body: Bytes) -> axum::response::Response {
+  let app = match app_handle(&state).await {
+    Ok(app) => app,
+    Err(response) => return response.into_response(),
+  };
+
+  match handle_rpc(&app, &body).await {
+    Some(response) => Json(response).into_response(),
+    None => StatusCode::NO_CONTENT.into_response(),
+  }
+}
+
+/// Stream app events as Server-Sent Events
+///
+/// The first event reports the current window visibility so subscribers can
+/// render their initial state without waiting for a change.
+async fn event_stream_route(State(state): State<AppState>) -> impl IntoResponse {
+  let visible = state
+    .app_handle
+    .lock()
+    .await
+    .as_ref()
+    .and_then(|app| app.get_webview_window("main"))
+    .and_then(|window| window.is_visible().ok())
+    .unwrap_or(false);
+
+  let initial = StreamEvent {
+    event: "rua://window-state".to_string(),
+    payload: serde_json::json!({ "visible": visible }),
+    timestamp: chrono::Utc::now().to_rfc3339(),
+  };
+
+  let receiver = subscribe_events();
+  let events = stream::unfold(
+    (Some(initial), receiver),
+    |(initial, mut receiver)| async move {
+      if let Some(initial) = initial {
+        return Some((initial, (None, receiver)));
       }
-    } else {
-      (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(Response {
-          success: false,
-          message: "Main window not found".to_string(),
-        }),
-      )
-    }
-  } else {
-    (
+      loop {
+        match receiver.recv().await {
+          Ok(event) => return Some((event, (None, receiver))),
+          Err(RecvError::Lagged(skipped)) => {
+            eprintln!("[events] Subscriber lagged, skipped {} events", skipped);
+          }
+          Err(RecvError::Closed) => return None,
+        }
+      }
+    },
+  )
+  .map(|event| {
+    SseEvent::default()
+      .event(event.event.clone())
+      .json_data(&event)
+  });
+
+  Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Report the app's view of its environment for `ruactl doctor`
+async fn diagnostics_route(State(state): State<AppState>) -> impl IntoResponse {
+  let app = match app_handle(&state).await {
+    Ok(app) => app,
+    Err(response) => return response,
+  };
+
+  // Detection runs external commands, keep it off the async workers
+  let result = tokio::task::spawn_blocking(move || collect_diagnostics(&app))
+    .await
+    .map_err(|e| format!("Failed to collect diagnostics: {}", e))
+    .and_then(to_data);
+
+  match result {
+    Ok(data) => (
+      StatusCode::OK,
+      Json(Response {
+        success: true,
+        message: "Diagnostics collected".to_string(),
+        data,
+      }),
+    ),
+    Err(e) => (
       StatusCode::INTERNAL_SERVER_ERROR,
       Json(Response {
         success: false,
-        message: "App handle not available".to_string(),
+        message: e,
+        data: None,
       }),
-    )
+    ),
   }
 }
 
@@ -86,29 +445,212 @@ async fn health_check() -> impl IntoResponse {
     Json(Response {
       success: true,
       message: "Rua control server is running".to_string(),
+      data: None,
     }),
   )
 }
 
+/// Parse a bind address given as `host:port` or just a port (bound on localhost)
+fn parse_bind_address(value: &str) -> Result<SocketAddr, String> {
+  let value = value.trim();
+  if let Ok(port) = value.parse::<u16>() {
+    return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+  }
+  value
+    .parse()
+    .map_err(|e| format!("Invalid control server address \"{}\": {}", value, e))
+}
+
+/// Resolve the TCP bind address
+///
+/// `RUA_CONTROL_ADDR` takes precedence over the `controlServerHost` and
+/// `controlServerPort` preferences in `system.advanced`. Port 0 lets the OS
+/// pick a free port.
+fn control_bind_address(app: &AppHandle) -> Result<SocketAddr, String> {
+  if let Ok(value) = std::env::var(CONTROL_ADDR_ENV) {
+    return parse_bind_address(&value);
+  }
+
+  let preferences = load_preferences(app)?;
+  let advanced = preferences.get("system.advanced");
+
+  let host = match advanced
+    .and_then(|prefs| prefs.get("controlServerHost"))
+    .and_then(|value| value.as_str())
+    .map(str::trim)
+    .filter(|host| !host.is_empty())
+  {
+    Some(host) => host
+      .parse()
+      .map_err(|e| format!("Invalid control server host \"{}\": {}", host, e))?,
+    None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+  };
+
+  let port = match advanced.and_then(|prefs| prefs.get("controlServerPort")) {
+    Some(value) => value
+      .as_u64()
+      .and_then(|port| u16::try_from(port).ok())
+      .ok_or_else(|| format!("Invalid control server port: {}", value))?,
+    None => DEFAULT_SERVER_PORT,
+  };
+
+  Ok(SocketAddr::new(host, port))
+}
+
+/// Whether the user opted into a control server reachable from other hosts
+///
+/// Set with `RUA_CONTROL_ALLOW_REMOTE=1` or the `controlServerAllowRemote`
+/// preference in `system.advanced`.
+fn allow_remote_control(app: &AppHandle) -> bool {
+  if let Ok(value) = std::env::var(ALLOW_REMOTE_ENV) {
+    return value == "1" || value.eq_ignore_ascii_case("true");
+  }
+
+  load_preferences(app)
+    .ok()
+    .and_then(|preferences| {
+      preferences
+        .get("system.advanced")?
+        .get("controlServerAllowRemote")?
+        .as_bool()
+    })
+    .unwrap_or(false)
+}
+
+/// Refuse a non-loopback bind address unless remote access was opted into
+///
+/// The TCP listener speaks plain HTTP, so the token and every RPC call would
+/// cross the network unencrypted. Returns a warning to surface when allowed.
+fn check_bind_address(address: SocketAddr, allow_remote: bool) -> Result<Option<String>, String> {
+  if address.ip().is_loopback() {
+    return Ok(None);
+  }
+  if !allow_remote {
+    return Err(format!(
+      "Refusing to bind control server to non-loopback address {} (set {}=1 to allow it)",
+      address, ALLOW_REMOTE_ENV
+    ));
+  }
+  Ok(Some(format!(
+    "Control server is reachable from other hosts on {} over unencrypted HTTP",
+    address
+  )))
+}
+
 /// Start the control server
 pub async fn start_server(app_handle: AppHandle) -> anyhow::Result<()> {
+  let bind_address = control_bind_address(&app_handle).and_then(|address| {
+    check_bind_address(address, allow_remote_control(&app_handle)).map(|warning| (address, warning))
+  });
+
   let state = AppState {
     app_handle: Arc::new(Mutex::new(Some(app_handle))),
   };
 
+  let token = Arc::new(create_control_token()?);
+  println!(
+    "Rua control token written to {}",
+    control_token_path().display()
+  );
+
   let app = Router::new()
     .route("/toggle", post(toggle_window))
+    .route("/show", post(show_window_with))
+    .route("/hide", post(hide_window_route))
+    .route("/actions/run", post(run_action_route))
+    .route("/events", get(event_stream_route))
+    .route("/rpc", post(rpc_route))
+    .route("/extensions/:operation", post(extension_route))
+    .route("/dev", post(dev_extension_route))
+    .route("/diagnostics", post(diagnostics_route))
     .route("/health", post(health_check))
+    .layer(middleware::from_fn_with_state(token, require_auth))
     .with_state(state);
 
-  let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", SERVER_PORT)).await?;
+  let mut discovery = ControlDiscovery {
+    pid: std::process::id(),
+    ..Default::default()
+  };
+  let mut servers = tokio::task::JoinSet::new();
 
-  println!(
-    "Rua control server listening on http://127.0.0.1:{}",
-    SERVER_PORT
-  );
+  // Prefer the per-user Unix socket; TCP stays available for older ruactl builds
+  #[cfg(unix)]
+  match crate::control_socket::bind_unix_socket() {
+    Ok((listener, socket_path)) => {
+      println!(
+        "Rua control server listening on unix:{}",
+        socket_path.display()
+      );
+      discovery.socket = Some(socket_path);
+      servers.spawn(crate::control_socket::serve_unix_socket(
+        listener,
+        app.clone(),
+      ));
+    }
+    Err(e) => discovery
+      .errors
+      .push(format!("Failed to start control socket: {}", e)),
+  }
+
+  let tcp_listener = match bind_address {
+    Ok((address, warning)) => {
+      discovery.errors.extend(warning);
+      tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to bind control server to {}: {}", address, e))
+    }
+    Err(e) => Err(e),
+  };
+
+  match tcp_listener {
+    Ok(listener) => {
+      let url = format!("http://{}", listener.local_addr()?);
+      println!("Rua control server listening on {}", url);
+      discovery.url = Some(url);
+      servers.spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+          eprintln!("Control server error: {}", e);
+        }
+      });
+    }
+    Err(e) => discovery.errors.push(e),
+  }
+
+  if let Err(e) = write_control_discovery(&discovery) {
+    eprintln!("Failed to write control discovery file: {}", e);
+  }
 
-  axum::serve(listener, app).await?;
+  if !discovery.errors.is_empty() {
+    let message = discovery.errors.join("\n");
+    eprintln!("{}", message);
+    notify("Rua control server", &message);
+    if servers.is_empty() {
+      anyhow::bail!(message);
+    }
+  }
+
+  // Serve until every listener has stopped
+  while servers.join_next().await.is_some() {}
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_check_bind_address() {
+    let loopback = parse_bind_address("7777").unwrap();
+    assert_eq!(loopback, "127.0.0.1:7777".parse().unwrap());
+    assert_eq!(check_bind_address(loopback, false), Ok(None));
+    assert_eq!(
+      check_bind_address("[::1]:7777".parse().unwrap(), false),
+      Ok(None)
+    );
+
+    let any = parse_bind_address("0.0.0.0:7777").unwrap();
+    assert!(check_bind_address(any, false).is_err());
+    assert!(check_bind_address(any, true).unwrap().is_some());
+  }
+}
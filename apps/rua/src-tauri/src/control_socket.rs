@@ -0,0 +1,102 @@
+//! Control Socket Module
+//!
+//! Serves the control server router on a Unix domain socket at
+//! `$XDG_RUNTIME_DIR/rua/control.sock`. Unlike the TCP listener, the socket is
+//! private to the user: connections whose peer credentials (SO_PEERCRED) don't
+//! match the UID running rua are dropped before any request is read.
+
+use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+use anyhow::anyhow;
+use axum::Router;
+use hyper_util::{
+  rt::{TokioExecutor, TokioIo},
+  server::conn::auto::Builder,
+  service::TowerToHyperService,
+};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::control_paths::control_socket_path;
+
+/// Bind the control socket, replacing a stale one, and restrict it to the user
+pub fn bind_unix_socket() -> anyhow::Result<(UnixListener, PathBuf)> {
+  let socket_path = control_socket_path().ok_or_else(|| anyhow!("XDG_RUNTIME_DIR is not set"))?;
+  let socket_dir = socket_path
+    .parent()
+    .ok_or_else(|| anyhow!("Invalid control socket path: {:?}", socket_path))?;
+
+  fs::create_dir_all(socket_dir)?;
+  fs::set_permissions(socket_dir, fs::Permissions::from_mode(0o700))?;
+
+  // Remove a stale socket left behind by a previous run
+  if socket_path.exists() {
+    fs::remove_file(&socket_path)?;
+  }
+
+  let listener = UnixListener::bind(&socket_path)?;
+  fs::set_permissions(&socket_path, fs::Permissions::from_mode(0o600))?;
+
+  Ok((listener, socket_path))
+}
+
+/// Only accept connections from processes running as the owner
+fn check_peer(stream: &UnixStream, owner_uid: u32) -> Result<(), String> {
+  let cred = stream
+    .peer_cred()
+    .map_err(|e| format!("Failed to read peer credentials: {}", e))?;
+  if cred.uid() != owner_uid {
+    return Err(format!(
+      "Rejected connection from uid {} (owner is {})",
+      cred.uid(),
+      owner_uid
+    ));
+  }
+  Ok(())
+}
+
+/// Serve the router on the control socket
+pub async fn serve_unix_socket(listener: UnixListener, router: Router) {
+  // SAFETY: getuid has no preconditions and cannot fail
+  let owner_uid = unsafe { libc::getuid() };
+
+  loop {
+    let (stream, _) = match listener.accept().await {
+      Ok(conn) => conn,
+      Err(e) => {
+        eprintln!("[control.sock] Failed to accept connection: {}", e);
+        continue;
+      }
+    };
+
+    if let Err(e) = check_peer(&stream, owner_uid) {
+      eprintln!("[control.sock] {}", e);
+      continue;
+    }
+
+    let service = TowerToHyperService::new(router.clone());
+    tokio::spawn(async move {
+      if let Err(e) = Builder::new(TokioExecutor::new())
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+      {
+        eprintln!("[control.sock] Connection error: {}", e);
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_check_peer() {
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    let (stream, _peer) = UnixStream::pair().unwrap();
+
+    assert!(check_peer(&stream, uid).is_ok());
+    let error = check_peer(&stream, uid + 1).unwrap_err();
+    assert!(error.contains(&format!("uid {}", uid)));
+  }
+}
@@ -0,0 +1,237 @@
+//! Deep Link Module
+//!
+//! Handles `rua://` URLs passed on the command line, either on first launch
+//! or forwarded to the running instance by the single-instance plugin:
+//!
+//! - `rua://show?query=foo&view=ext.id.action` shows the window
+//! - `rua://run/ext.id.action?key=value` runs an action with arguments
+//! - `rua://install?source=github:owner/repo` installs an extension after
+//!   the user confirms it
+//!
+//! Links received before the main window has loaded are queued until the
+//! frontend calls `deep_links_ready`.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+#[cfg(target_os = "linux")]
+use crate::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::*;
+use crate::{
+  extension::{install_extension, run_action},
+  types::WindowShownPayload,
+};
+
+/// URL scheme handled by rua
+pub const DEEP_LINK_SCHEME: &str = "rua";
+
+lazy_static::lazy_static! {
+    /// Links waiting for the frontend, `None` once it is ready
+    static ref PENDING_LINKS: Mutex<Option<Vec<DeepLink>>> = Mutex::new(Some(Vec::new()));
+}
+
+/// A parsed `rua://` link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+  Show(WindowShownPayload),
+  Run {
+    action_id: String,
+    args: HashMap<String, String>,
+  },
+  Install {
+    source: String,
+  },
+}
+
+/// Check whether a command-line argument is a `rua://` link
+pub fn is_deep_link(arg: &str) -> bool {
+  arg
+    .get(..DEEP_LINK_SCHEME.len() + 1)
+    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", DEEP_LINK_SCHEME)))
+}
+
+/// Parse a `rua://` link
+pub fn parse_deep_link(link: &str) -> Result<DeepLink, String> {
+  let url = Url::parse(link).map_err(|e| format!("Invalid link {}: {}", link, e))?;
+  if url.scheme() != DEEP_LINK_SCHEME {
+    return Err(format!("Not a {}:// link: {}", DEEP_LINK_SCHEME, link));
+  }
+
+  let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+  let path = url.path().trim_matches('/');
+
+  match url
+    .host_str()
+    .unwrap_or_default()
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    "" | "show" => Ok(DeepLink::Show(WindowShownPayload {
+      query: params.get("query").cloned(),
+      view: params.get("view").cloned(),
+      ..Default::default()
+    })),
+    "run" if !path.is_empty() => Ok(DeepLink::Run {
+      action_id: path.to_string(),
+      args: params,
+    }),
+    "run" => Err(format!("Missing action ID in {}", link)),
+    "install" => match params.get("source") {
+      Some(source) if !source.trim().is_empty() => Ok(DeepLink::Install {
+        source: source.trim().to_string(),
+      }),
+      _ => Err(format!("Missing source in {}", link)),
+    },
+    other => Err(format!("Unknown link command \"{}\" in {}", other, link)),
+  }
+}
+
+/// Report a failed link to the user
+fn notify_error(message: String) {
+  eprintln!("[DeepLink] {}", message);
+  notify("Rua", &message);
+}
+
+/// Handle a `rua://` link now, or queue it until the frontend is ready
+pub fn dispatch_deep_link(app: &AppHandle, link: &str) {
+  match parse_deep_link(link) {
+    Ok(deep_link) => dispatch(app, deep_link),
+    Err(e) => notify_error(e),
+  }
+}
+
+/// Handle a parsed link now, or queue it until the frontend is ready
+pub fn dispatch(app: &AppHandle, deep_link: DeepLink) {
+  if let Ok(mut pending) = PENDING_LINKS.lock() {
+    if let Some(pending) = pending.as_mut() {
+      pending.push(deep_link);
+      return;
+    }
+  }
+
+  handle_deep_link(app, deep_link);
+}
+
+/// Run a parsed link
+pub fn handle_deep_link(app: &AppHandle, deep_link: DeepLink) {
+  match deep_link {
+    DeepLink::Show(payload) => {
+      let Some(window) = app.get_webview_window("main") else {
+        return notify_error("Main window not found".to_string());
+      };
+      if let Err(e) = show_window(window, payload) {
+        notify_error(format!("Failed to show window: {}", e));
+      }
+    }
+    DeepLink::Run { action_id, args } => {
+      let app = app.clone();
+      tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_action(&app, &action_id, args).await {
+          notify_error(e);
+        }
+      });
+    }
+    DeepLink::Install { source } => confirm_install(app, source),
+  }
+}
+
+/// Ask the user before installing anything from a link
+fn confirm_install(app: &AppHandle, source: String) {
+  let app_handle = app.clone();
+
+  app
+    .dialog()
+    .message(format!(
+      "A link asks Rua to install an extension from:\n\n{}\n\nExtensions can run code on \
+       your computer. Only install extensions from sources you trust.",
+      source
+    ))
+    .title("Install extension?")
+    .kind(MessageDialogKind::Warning)
+    .buttons(MessageDialogButtons::OkCancelCustom(
+      "Install".to_string(),
+      "Cancel".to_string(),
+    ))
+    .show(move |confirmed| {
+      if !confirmed {
+        return;
+      }
+
+      tauri::async_runtime::spawn(async move {
+        match install_extension(app_handle, source).await {
+          Ok(extension) => {
+            notify(
+              "Extension installed",
+              &format!("{} {}", extension.manifest.name, extension.manifest.version),
+            );
+          }
+          Err(e) => notify_error(format!("Failed to install extension: {}", e)),
+        }
+      });
+    });
+}
+
+/// Handle the links queued while the main window was loading
+#[tauri::command]
+pub fn deep_links_ready(app: AppHandle) -> Result<(), String> {
+  let pending = PENDING_LINKS
+    .lock()
+    .map_err(|e| format!("Lock error: {}", e))?
+    .take()
+    .unwrap_or_default();
+
+  for deep_link in pending {
+    handle_deep_link(&app, deep_link);
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_deep_link() {
+    assert_eq!(
+      parse_deep_link("rua://show?query=hello%20world").unwrap(),
+      DeepLink::Show(WindowShownPayload {
+        query: Some("hello world".to_string()),
+        ..Default::default()
+      })
+    );
+    assert_eq!(
+      parse_deep_link("rua://").unwrap(),
+      DeepLink::Show(WindowShownPayload::default())
+    );
+    assert_eq!(
+      parse_deep_link("rua://run/fzdwx.clip.list?query=foo").unwrap(),
+      DeepLink::Run {
+        action_id: "fzdwx.clip.list".to_string(),
+        args: HashMap::from([("query".to_string(), "foo".to_string())]),
+      }
+    );
+    assert_eq!(
+      parse_deep_link("rua://install?source=github:owner/repo@v1.0.0").unwrap(),
+      DeepLink::Install {
+        source: "github:owner/repo@v1.0.0".to_string(),
+      }
+    );
+
+    assert!(parse_deep_link("rua://run").is_err());
+    assert!(parse_deep_link("rua://install").is_err());
+    assert!(parse_deep_link("rua://unknown").is_err());
+    assert!(parse_deep_link("https://example.com").is_err());
+  }
+
+  #[test]
+  fn test_is_deep_link() {
+    assert!(is_deep_link("rua://show"));
+    assert!(is_deep_link("RUA://show"));
+    assert!(!is_deep_link("--query"));
+    assert!(!is_deep_link("ru"));
+  }
+}
@@ -0,0 +1,51 @@
+//! Diagnostic Checks Module
+//!
+//! What `ruactl doctor` checks, shared by the app's diagnostics module and
+//! ruactl itself, which includes this file with `#[path]` to diagnose the
+//! environment when rua isn't running. It must only depend on std.
+
+/// External commands used by the backend: (name, purpose, optional)
+pub const DEPENDENCIES: &[(&str, &str, bool)] = &[
+  ("notify-send", "Notification fallback without D-Bus", true),
+  (
+    "xdg-open",
+    "Opening files without a default application",
+    true,
+  ),
+  ("systemd-run", "Launching apps in their own scope", true),
+  ("find", "File search", false),
+  ("fd", "Faster file search, falls back to find", true),
+  ("rifle", "Opening files with rifle", true),
+  ("hyprctl", "Window control on Hyprland", true),
+  ("xclip", "Clipboard fallback on X11", true),
+  ("wl-copy", "Clipboard fallback on Wayland", true),
+  ("wl-paste", "Clipboard fallback on Wayland", true),
+];
+
+/// Environment variables that influence detection
+pub const ENVIRONMENT_VARS: &[&str] = &[
+  "XDG_SESSION_TYPE",
+  "XDG_CURRENT_DESKTOP",
+  "WAYLAND_DISPLAY",
+  "DISPLAY",
+  "HYPRLAND_INSTANCE_SIGNATURE",
+  "XDG_RUNTIME_DIR",
+  "SHELL",
+  "PATH",
+];
+
+/// Terminal emulators in order of preference
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub const TERMINAL_EMULATORS: &[&str] = &[
+  "wezterm",
+  "konsole",        // KDE
+  "gnome-terminal", // GNOME
+  "alacritty",      // Modern, GPU-accelerated
+  "kitty",          // Modern, GPU-accelerated
+  "terminator",     // Feature-rich
+  "tilix",          // Tiling terminal
+  "xfce4-terminal", // XFCE
+  "mate-terminal",  // MATE
+  "lxterminal",     // LXDE
+  "xterm",          // Fallback, always available
+];
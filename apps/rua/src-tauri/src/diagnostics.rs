@@ -0,0 +1,152 @@
+//! Diagnostics Module
+//!
+//! Reports what the running app decided about its environment for
+//! `ruactl doctor`: the detected display server and terminal emulator, the
+//! external tools the backend shells out to, the directories it uses and
+//! features that are off because of the environment.
+//! The app's environment often differs from the shell's (e.g. when it is
+//! started by the compositor), so these are collected in the app process.
+
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::diagnostic_checks::{DEPENDENCIES, ENVIRONMENT_VARS};
+
+#[cfg(target_os = "linux")]
+use crate::linux::{
+  clipboard_watch_error, detect_terminal_emulator, display_server::detect_display_server,
+};
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::clipboard_watch_error;
+
+/// An external command and where it was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+  pub name: String,
+  pub purpose: String,
+  pub optional: bool,
+  /// Resolved path, `None` if not found on PATH
+  pub path: Option<String>,
+}
+
+/// A directory the app reads or writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryStatus {
+  pub name: String,
+  pub path: String,
+  pub exists: bool,
+  pub writable: bool,
+}
+
+/// Diagnostics of the running app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+  pub version: String,
+  pub pid: u32,
+  pub os: String,
+  pub display_server: Option<String>,
+  pub terminal_emulator: Option<String>,
+  pub environment: Vec<(String, Option<String>)>,
+  pub dependencies: Vec<Dependency>,
+  pub directories: Vec<DirectoryStatus>,
+  /// Features unavailable in this environment
+  pub warnings: Vec<String>,
+}
+
+/// Check whether a directory accepts new files
+fn is_writable(dir: &Path) -> bool {
+  let probe = dir.join(format!(".rua-write-test-{}", std::process::id()));
+  let writable = fs::write(&probe, b"").is_ok();
+  let _ = fs::remove_file(&probe);
+  writable
+}
+
+/// Find a command on PATH, or check it directly if it is a path
+fn find_command(name: &str) -> Option<String> {
+  let path = Path::new(name);
+  if path.components().count() > 1 {
+    return path.is_file().then(|| name.to_string());
+  }
+  std::env::split_paths(&std::env::var_os("PATH")?)
+    .map(|dir| dir.join(name))
+    .find(|candidate| candidate.is_file())
+    .map(|p| p.to_string_lossy().to_string())
+}
+
+fn directory_status(name: &str, path: &Path) -> DirectoryStatus {
+  DirectoryStatus {
+    name: name.to_string(),
+    path: path.to_string_lossy().to_string(),
+    exists: path.is_dir(),
+    writable: path.is_dir() && is_writable(path),
+  }
+}
+
+/// Collect diagnostics for the current process
+pub fn collect_diagnostics(app: &AppHandle) -> Diagnostics {
+  #[cfg(target_os = "linux")]
+  let (display_server, terminal_emulator) = (
+    Some(detect_display_server().as_str().to_string()),
+    detect_terminal_emulator(),
+  );
+  #[cfg(not(target_os = "linux"))]
+  let (display_server, terminal_emulator) = (None, None);
+
+  let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+  let mut dependencies: Vec<Dependency> = DEPENDENCIES
+    .iter()
+    .map(|(name, purpose, optional)| Dependency {
+      name: name.to_string(),
+      purpose: purpose.to_string(),
+      optional: *optional,
+      path: find_command(name),
+    })
+    .collect();
+  dependencies.push(Dependency {
+    path: find_command(&shell),
+    name: shell,
+    purpose: "Shell commands".to_string(),
+    optional: false,
+  });
+
+  let mut directories = Vec::new();
+  let app_dirs = [
+    ("data", app.path().app_data_dir()),
+    ("config", app.path().app_config_dir()),
+  ];
+  for (name, dir) in app_dirs {
+    match dir {
+      Ok(dir) => directories.push(directory_status(name, &dir)),
+      Err(e) => eprintln!("Failed to resolve {} dir: {}", name, e),
+    }
+  }
+  #[cfg(target_os = "linux")]
+  if let Some(cache_dir) = crate::linux::get_cache_path().parent() {
+    directories.push(directory_status("cache", cache_dir));
+  }
+  directories.push(directory_status(
+    "runtime",
+    &crate::control_paths::control_runtime_dir(),
+  ));
+
+  Diagnostics {
+    version: app.package_info().version.to_string(),
+    pid: std::process::id(),
+    os: std::env::consts::OS.to_string(),
+    display_server,
+    terminal_emulator,
+    environment: ENVIRONMENT_VARS
+      .iter()
+      .map(|var| (var.to_string(), std::env::var(var).ok()))
+      .collect(),
+    dependencies,
+    directories,
+    warnings: clipboard_watch_error()
+      .map(|e| format!("Clipboard history is not recorded: {}", e))
+      .into_iter()
+      .collect(),
+  }
+}
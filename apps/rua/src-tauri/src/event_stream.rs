@@ -0,0 +1,83 @@
+//! Event Stream Module
+//!
+//! Fans out app events to external subscribers of the control server's
+//! `/events` endpoint (Server-Sent Events). Window visibility, extension
+//! lifecycle, clipboard history, installed application and failed launch
+//! events are forwarded from the Tauri event system; events sent via
+//! `broadcast_event` are published directly since their names are arbitrary.
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Listener};
+use tokio::sync::broadcast;
+
+/// Tauri events forwarded to the stream
+const FORWARDED_EVENTS: &[&str] = &[
+  "rua://window-shown",
+  "rua://window-hidden",
+  "rua://extension-installed",
+  "rua://extension-uninstalled",
+  "rua://extension-enabled",
+  "rua://extension-disabled",
+  "rua://dev-extension-changed",
+  "rua://dev-extension-reload",
+  "rua://dev-extension-error",
+  "rua://clipboard-history-changed",
+  "rua://applications-changed",
+  "rua://launch-failed",
+];
+
+/// Number of events a slow subscriber may lag behind before dropping some
+const CHANNEL_CAPACITY: usize = 256;
+
+/// An event delivered to stream subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamEvent {
+  pub event: String,
+  pub payload: Value,
+  pub timestamp: String,
+}
+
+lazy_static::lazy_static! {
+    static ref EVENT_SENDER: broadcast::Sender<StreamEvent> =
+        broadcast::channel(CHANNEL_CAPACITY).0;
+}
+
+/// Publish an event to all stream subscribers
+pub fn publish_event(event: &str, payload: Value) {
+  // Sending only fails when nobody is subscribed, which is fine
+  let _ = EVENT_SENDER.send(StreamEvent {
+    event: event.to_string(),
+    payload,
+    timestamp: chrono::Utc::now().to_rfc3339(),
+  });
+}
+
+/// Publish an event sent through `broadcast_event`
+///
+/// Events that are already forwarded from the Tauri event system are skipped
+/// so subscribers don't see them twice.
+pub fn publish_broadcast_event(event: &str, payload: Value) {
+  if !FORWARDED_EVENTS.contains(&event) {
+    publish_event(event, payload);
+  }
+}
+
+/// Subscribe to the event stream
+pub fn subscribe_events() -> broadcast::Receiver<StreamEvent> {
+  EVENT_SENDER.subscribe()
+}
+
+/// Forward window and extension lifecycle events to the stream
+pub fn forward_app_events(app: &AppHandle) {
+  for &event in FORWARDED_EVENTS {
+    app.listen_any(event, move |e| {
+      let mut payload = serde_json::from_str(e.payload()).unwrap_or(Value::Null);
+      // Selected text is only meant for the frontend and extensions
+      if let Some(payload) = payload.as_object_mut() {
+        payload.remove("selection");
+      }
+      publish_event(event, payload);
+    });
+  }
+}
@@ -0,0 +1,155 @@
+//! Action Runner Module
+//!
+//! Runs manifest actions (`ext.id.action`) without user interaction, e.g. from
+//! `ruactl run`. View actions open the main window on the action; every other
+//! action is dispatched to the extension's background runtime in the main
+//! window, which reports the outcome back through `resolve_action_run`.
+
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
+  time::Duration,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+use super::get_extensions;
+#[cfg(target_os = "linux")]
+use crate::linux::show_window;
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::show_window;
+use crate::types::WindowShownPayload;
+
+/// How long to wait for the frontend to report an action result
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+type RunResult = Result<Value, String>;
+
+lazy_static::lazy_static! {
+    static ref PENDING_RUNS: Mutex<HashMap<String, oneshot::Sender<RunResult>>> =
+        Mutex::new(HashMap::new());
+}
+
+static NEXT_RUN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Event emitted to the main window to run an action in the background runtime
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunActionEvent {
+  pub request_id: String,
+  pub extension_id: String,
+  pub action_name: String,
+  pub args: HashMap<String, String>,
+}
+
+fn remove_pending(request_id: &str) {
+  if let Ok(mut pending) = PENDING_RUNS.lock() {
+    pending.remove(request_id);
+  }
+}
+
+/// Run an enabled manifest action and wait for its result
+pub async fn run_action(
+  app: &AppHandle,
+  action_id: &str,
+  args: HashMap<String, String>,
+) -> RunResult {
+  let extensions = get_extensions(app.clone()).await?;
+
+  let extension = extensions
+    .iter()
+    .find(|ext| ext.actions.iter().any(|id| id == action_id))
+    .ok_or_else(|| format!("Action not found: {}", action_id))?;
+
+  if !extension.enabled {
+    return Err(format!(
+      "Extension \"{}\" is disabled",
+      extension.manifest.id
+    ));
+  }
+
+  let action_name = &action_id[extension.manifest.id.len() + 1..];
+  let action = extension
+    .manifest
+    .rua
+    .actions
+    .iter()
+    .find(|a| a.name == action_name)
+    .ok_or_else(|| format!("Action not found: {}", action_id))?;
+
+  let window = app
+    .get_webview_window("main")
+    .ok_or("Main window not found")?;
+
+  // View actions need the UI, so open the window on the action instead
+  if action.mode == "view" {
+    let payload = WindowShownPayload {
+      query: args.get("query").cloned(),
+      view: Some(action_id.to_string()),
+      ..Default::default()
+    };
+    let message = show_window(window, payload).map_err(|e| e.to_string())?;
+    return Ok(Value::String(message));
+  }
+
+  let request_id = NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed).to_string();
+  let (tx, rx) = oneshot::channel();
+  PENDING_RUNS
+    .lock()
+    .map_err(|e| format!("Lock error: {}", e))?
+    .insert(request_id.clone(), tx);
+
+  let event = RunActionEvent {
+    request_id: request_id.clone(),
+    extension_id: extension.manifest.id.clone(),
+    action_name: action_name.to_string(),
+    args,
+  };
+
+  if let Err(e) = window.emit("rua://run-action", event) {
+    remove_pending(&request_id);
+    return Err(format!("Failed to dispatch action: {}", e));
+  }
+
+  match tokio::time::timeout(RUN_TIMEOUT, rx).await {
+    Ok(Ok(result)) => result,
+    Ok(Err(_)) => Err(format!("Action {} was cancelled", action_id)),
+    Err(_) => {
+      remove_pending(&request_id);
+      Err(format!(
+        "Action {} timed out after {}s",
+        action_id,
+        RUN_TIMEOUT.as_secs()
+      ))
+    }
+  }
+}
+
+/// Report the outcome of an action dispatched through `rua://run-action`
+#[tauri::command]
+pub fn resolve_action_run(
+  request_id: String,
+  result: Option<Value>,
+  error: Option<String>,
+) -> Result<(), String> {
+  let sender = PENDING_RUNS
+    .lock()
+    .map_err(|e| format!("Lock error: {}", e))?
+    .remove(&request_id)
+    .ok_or_else(|| format!("Unknown or expired action run: {}", request_id))?;
+
+  let outcome = match error {
+    Some(error) => Err(error),
+    None => Ok(result.unwrap_or(Value::Null)),
+  };
+
+  // The waiting side may have timed out in the meantime
+  let _ = sender.send(outcome);
+  Ok(())
+}
@@ -0,0 +1,199 @@
+//! Dev Extension Module
+//!
+//! Owns the extension under development: its path is persisted in the system
+//! preferences so it stays registered across restarts, its directory is watched
+//! for hot reload, and errors reported for it are emitted as events that
+//! `ruactl dev` streams back to the terminal.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use super::extensions::{read_dev_extension, ExtensionInfo};
+use crate::{
+  file_watcher,
+  preferences::{load_preferences, save_preferences},
+};
+
+/// Preference namespace and key holding the dev extension path
+const DEV_NAMESPACE: &str = "system.developer";
+const DEV_PATH_KEY: &str = "devExtensionPath";
+
+/// File watcher slot used for the dev extension
+const DEV_WATCHER: &str = "dev-extension";
+
+/// Payload of `rua://dev-extension-changed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevExtensionChanged {
+  pub path: Option<String>,
+  pub extension_id: Option<String>,
+}
+
+/// Payload of `rua://dev-extension-reload`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevExtensionReload {
+  pub extension_id: String,
+  pub paths: Vec<String>,
+}
+
+/// Payload of `rua://dev-extension-error`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevExtensionError {
+  pub extension_id: Option<String>,
+  /// Where the error came from, e.g. "manifest" or "background"
+  pub source: String,
+  pub message: String,
+}
+
+fn emit_dev_event<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) {
+  if let Err(e) = app.emit(event, payload) {
+    eprintln!("Failed to emit {}: {}", event, e);
+  }
+}
+
+/// Get the persisted dev extension path
+pub fn dev_extension_path(app: &AppHandle) -> Option<PathBuf> {
+  load_preferences(app)
+    .ok()?
+    .get(DEV_NAMESPACE)?
+    .get(DEV_PATH_KEY)?
+    .as_str()
+    .map(PathBuf::from)
+}
+
+fn save_dev_extension_path(app: &AppHandle, path: Option<&Path>) -> Result<(), String> {
+  let mut preferences = load_preferences(app)?;
+  let namespace = preferences.entry(DEV_NAMESPACE.to_string()).or_default();
+  match path {
+    Some(path) => {
+      namespace.insert(
+        DEV_PATH_KEY.to_string(),
+        Value::String(path.to_string_lossy().to_string()),
+      );
+    }
+    None => {
+      namespace.remove(DEV_PATH_KEY);
+    }
+  }
+  save_preferences(app, &preferences)
+}
+
+/// Watch the dev extension directory, re-reading the manifest on every change
+fn watch_dev_extension(app: &AppHandle, path: &Path) -> Result<(), String> {
+  let app_handle = app.clone();
+  let dev_path = path.to_path_buf();
+
+  file_watcher::start_watcher(DEV_WATCHER, path, move |paths| {
+    match read_dev_extension(&dev_path) {
+      Ok(info) => emit_dev_event(
+        &app_handle,
+        "rua://dev-extension-reload",
+        DevExtensionReload {
+          extension_id: info.manifest.id,
+          paths: paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+        },
+      ),
+      Err(message) => emit_dev_event(
+        &app_handle,
+        "rua://dev-extension-error",
+        DevExtensionError {
+          extension_id: None,
+          source: "manifest".to_string(),
+          message,
+        },
+      ),
+    }
+  })
+}
+
+/// Register a directory as the dev extension, or unregister it with `None`
+pub fn register_dev_extension(
+  app: &AppHandle,
+  path: Option<&str>,
+) -> Result<Option<ExtensionInfo>, String> {
+  let Some(path) = path else {
+    file_watcher::stop_watcher(DEV_WATCHER)?;
+    save_dev_extension_path(app, None)?;
+    emit_dev_event(
+      app,
+      "rua://dev-extension-changed",
+      DevExtensionChanged {
+        path: None,
+        extension_id: None,
+      },
+    );
+    return Ok(None);
+  };
+
+  let path = PathBuf::from(path)
+    .canonicalize()
+    .map_err(|e| format!("Failed to resolve dev path {}: {}", path, e))?;
+  let info = read_dev_extension(&path)?;
+
+  watch_dev_extension(app, &path)?;
+  save_dev_extension_path(app, Some(&path))?;
+  emit_dev_event(
+    app,
+    "rua://dev-extension-changed",
+    DevExtensionChanged {
+      path: Some(info.path.clone()),
+      extension_id: Some(info.manifest.id.clone()),
+    },
+  );
+
+  Ok(Some(info))
+}
+
+/// Start watching the persisted dev extension on app startup
+pub fn restore_dev_extension(app: &AppHandle) {
+  let Some(path) = dev_extension_path(app) else {
+    return;
+  };
+
+  if let Err(e) = watch_dev_extension(app, &path) {
+    eprintln!("Failed to watch dev extension {}: {}", path.display(), e);
+  }
+}
+
+/// Get the registered dev extension path
+#[tauri::command]
+pub async fn get_dev_extension(app: AppHandle) -> Result<Option<String>, String> {
+  Ok(dev_extension_path(&app).map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Register (or unregister with `null`) the dev extension
+#[tauri::command]
+pub async fn set_dev_extension(
+  app: AppHandle,
+  path: Option<String>,
+) -> Result<Option<ExtensionInfo>, String> {
+  register_dev_extension(&app, path.as_deref())
+}
+
+/// Report an error raised by the dev extension (e.g. its background script)
+#[tauri::command]
+pub async fn report_dev_extension_error(
+  app: AppHandle,
+  extension_id: Option<String>,
+  source: String,
+  message: String,
+) -> Result<(), String> {
+  emit_dev_event(
+    &app,
+    "rua://dev-extension-error",
+    DevExtensionError {
+      extension_id,
+      source,
+      message,
+    },
+  );
+  Ok(())
+}
@@ -2,10 +2,14 @@
 //!
 //! Handles loading, installing, and managing Rua extensions.
 
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 /// Extension manifest action
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -161,6 +165,9 @@ pub struct ExtensionState {
   #[serde(rename = "updatedAt")]
   pub updated_at: String,
   pub version: String,
+  /// Where the extension was installed from (`github:owner/repo` or a local path), used by updates
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub source: Option<String>,
 }
 
 /// Get the extensions directory path
@@ -211,8 +218,55 @@ fn save_registry(app: &AppHandle, state: &RegistryState) -> Result<(), String> {
   fs::write(&registry_path, content).map_err(|e| format!("Failed to write registry: {}", e))
 }
 
+/// Get the registry state of an extension
+pub fn get_extension_state(
+  app: &AppHandle,
+  extension_id: &str,
+) -> Result<Option<ExtensionState>, String> {
+  Ok(load_registry(app)?.extensions.remove(extension_id))
+}
+
+/// Record an installed extension in the registry, keeping its original install time
+fn register_installed(
+  app: &AppHandle,
+  manifest: &ExtensionManifest,
+  source: String,
+) -> Result<(), String> {
+  let mut registry = load_registry(app)?;
+  let now = chrono::Utc::now().to_rfc3339();
+  let installed_at = registry
+    .extensions
+    .get(&manifest.id)
+    .map(|state| state.installed_at.clone())
+    .unwrap_or_else(|| now.clone());
+
+  registry.extensions.insert(
+    manifest.id.clone(),
+    ExtensionState {
+      id: manifest.id.clone(),
+      enabled: true,
+      installed_at,
+      updated_at: now,
+      version: manifest.version.clone(),
+      source: Some(source),
+    },
+  );
+
+  save_registry(app, &registry)?;
+  emit_extension_event(app, "rua://extension-installed", &manifest.id);
+
+  Ok(())
+}
+
+/// Notify listeners (frontend and control server event stream) of an extension change
+fn emit_extension_event(app: &AppHandle, event: &str, extension_id: &str) {
+  if let Err(e) = app.emit(event, serde_json::json!({ "id": extension_id })) {
+    eprintln!("Failed to emit {}: {}", event, e);
+  }
+}
+
 /// Load manifest from extension directory
-fn load_manifest(extension_path: &PathBuf) -> Result<ExtensionManifest, String> {
+fn load_manifest(extension_path: &Path) -> Result<ExtensionManifest, String> {
   let manifest_path = extension_path.join("manifest.json");
 
   if !manifest_path.exists() {
@@ -511,7 +565,7 @@ fn extract_rua_archive(
   Ok((ext_id.clone(), manifest))
 }
 
-/// Install extension from a path or GitHub (copy to extensions directory)
+/// Install extension from a path, a `.rua` archive or GitHub (copy to extensions directory)
 #[tauri::command]
 pub async fn install_extension(
   app: AppHandle,
@@ -519,8 +573,8 @@ pub async fn install_extension(
 ) -> Result<ExtensionInfo, String> {
   let extensions_dir = get_extensions_dir(&app)?;
 
-  // Check if it's a GitHub source
-  if source_path.starts_with("github:") {
+  let (manifest, source) = if source_path.starts_with("github:") {
+    // Check if it's a GitHub source
     let (owner, repo, version) = parse_github_source(&source_path)
       .ok_or("Invalid GitHub source format. Use: github:owner/repo or github:owner/repo@version")?;
 
@@ -538,87 +592,55 @@ pub async fn install_extension(
 
     let archive_data = download_file(&rua_asset.browser_download_url).await?;
 
-    let (ext_id, manifest) = extract_rua_archive(&archive_data, &extensions_dir)?;
+    let (_, manifest) = extract_rua_archive(&archive_data, &extensions_dir)?;
 
-    // Update registry
-    let mut registry = load_registry(&app)?;
-    let now = chrono::Utc::now().to_rfc3339();
-
-    registry.extensions.insert(
-      ext_id.clone(),
-      ExtensionState {
-        id: ext_id.clone(),
-        enabled: true,
-        installed_at: now.clone(),
-        updated_at: now,
-        version: manifest.version.clone(),
-      },
-    );
-
-    save_registry(&app, &registry)?;
-
-    let target = extensions_dir.join(&ext_id);
-    let action_ids: Vec<String> = manifest
-      .rua
-      .actions
-      .iter()
-      .map(|a| format!("{}.{}", manifest.id, a.name))
-      .collect();
-
-    return Ok(ExtensionInfo {
-      manifest,
-      enabled: true,
-      loaded: true,
-      path: target.to_string_lossy().to_string(),
-      actions: action_ids,
-      error: None,
-    });
-  }
-
-  // Local path installation
-  let source = PathBuf::from(&source_path);
-
-  if !source.exists() {
-    return Err(format!("Source path does not exist: {}", source_path));
-  }
-
-  // Load manifest to get extension ID
-  let manifest = load_manifest(&source)?;
+    // Updates follow the latest release, even if a version was pinned
+    (manifest, format!("github:{}/{}", owner, repo))
+  } else {
+    // Local path installation
+    let source = PathBuf::from(&source_path);
 
-  // Validate manifest before installing
-  validate_manifest(&manifest)?;
+    if !source.exists() {
+      return Err(format!("Source path does not exist: {}", source_path));
+    }
 
-  let ext_id = &manifest.id;
+    // Record an absolute path so updates work regardless of the caller's directory
+    let source_desc = fs::canonicalize(&source)
+      .unwrap_or_else(|_| source.clone())
+      .to_string_lossy()
+      .to_string();
+
+    if source.is_file() {
+      // Packed .rua archive
+      let archive_data = fs::read(&source).map_err(|e| format!("Failed to read archive: {}", e))?;
+      let (_, manifest) = extract_rua_archive(&archive_data, &extensions_dir)?;
+      (manifest, source_desc)
+    } else {
+      // Load manifest to get extension ID
+      let manifest = load_manifest(&source)?;
 
-  // Get target directory
-  let target = extensions_dir.join(ext_id);
+      // Validate manifest before installing
+      validate_manifest(&manifest)?;
 
-  // Remove existing if present
-  if target.exists() {
-    fs::remove_dir_all(&target)
-      .map_err(|e| format!("Failed to remove existing extension: {}", e))?;
-  }
+      // Get target directory
+      let target = extensions_dir.join(&manifest.id);
 
-  // Copy extension directory
-  copy_dir_recursive(&source, &target)?;
+      // Remove existing if present
+      if target.exists() {
+        fs::remove_dir_all(&target)
+          .map_err(|e| format!("Failed to remove existing extension: {}", e))?;
+      }
 
-  // Update registry
-  let mut registry = load_registry(&app)?;
-  let now = chrono::Utc::now().to_rfc3339();
+      // Copy extension directory
+      copy_dir_recursive(&source, &target)?;
 
-  registry.extensions.insert(
-    ext_id.clone(),
-    ExtensionState {
-      id: ext_id.clone(),
-      enabled: true,
-      installed_at: now.clone(),
-      updated_at: now,
-      version: manifest.version.clone(),
-    },
-  );
+      (manifest, source_desc)
+    }
+  };
 
-  save_registry(&app, &registry)?;
+  register_installed(&app, &manifest, source)?;
 
+  let target = extensions_dir.join(&manifest.id);
   let action_ids: Vec<String> = manifest
     .rua
     .actions
@@ -636,6 +658,39 @@ pub async fn install_extension(
   })
 }
 
+/// Reinstall an extension from the source it was installed from
+///
+/// GitHub sources are updated to their latest release. The enabled state is kept.
+pub async fn update_extension(
+  app: AppHandle,
+  extension_id: String,
+) -> Result<ExtensionInfo, String> {
+  let state = get_extension_state(&app, &extension_id)?
+    .ok_or_else(|| format!("Extension not installed: {}", extension_id))?;
+  let source = state.source.ok_or_else(|| {
+    format!(
+      "No install source recorded for {}, reinstall it once to enable updates",
+      extension_id
+    )
+  })?;
+
+  let mut info = install_extension(app.clone(), source).await?;
+  if info.manifest.id != extension_id {
+    return Err(format!(
+      "Source of {} now provides a different extension: {}",
+      extension_id, info.manifest.id
+    ));
+  }
+
+  if !state.enabled {
+    disable_extension(app, extension_id).await?;
+    info.enabled = false;
+    info.loaded = false;
+  }
+
+  Ok(info)
+}
+
 /// Uninstall an extension
 #[tauri::command]
 pub async fn uninstall_extension(
@@ -659,6 +714,7 @@ pub async fn uninstall_extension(
   let mut registry = load_registry(&app)?;
   registry.extensions.remove(&extension_id);
   save_registry(&app, &registry)?;
+  emit_extension_event(&app, "rua://extension-uninstalled", &extension_id);
 
   Ok(())
 }
@@ -677,16 +733,20 @@ pub async fn enable_extension(app: AppHandle, extension_id: String) -> Result<()
     registry.extensions.insert(
       extension_id.clone(),
       ExtensionState {
-        id: extension_id,
+        id: extension_id.clone(),
         enabled: true,
         installed_at: now.clone(),
         updated_at: now,
         version: "0.0.0".to_string(),
+        source: None,
       },
     );
   }
 
-  save_registry(&app, &registry)
+  save_registry(&app, &registry)?;
+  emit_extension_event(&app, "rua://extension-enabled", &extension_id);
+
+  Ok(())
 }
 
 /// Disable an extension
@@ -697,9 +757,26 @@ pub async fn disable_extension(app: AppHandle, extension_id: String) -> Result<(
   if let Some(state) = registry.extensions.get_mut(&extension_id) {
     state.enabled = false;
     state.updated_at = chrono::Utc::now().to_rfc3339();
-    save_registry(&app, &registry)?;
+  } else {
+    // Extensions copied in without a registry entry are enabled by default,
+    // so they need an entry to be disabled
+    let now = chrono::Utc::now().to_rfc3339();
+    registry.extensions.insert(
+      extension_id.clone(),
+      ExtensionState {
+        id: extension_id.clone(),
+        enabled: false,
+        installed_at: now.clone(),
+        updated_at: now,
+        version: "0.0.0".to_string(),
+        source: None,
+      },
+    );
   }
 
+  save_registry(&app, &registry)?;
+  emit_extension_event(&app, "rua://extension-disabled", &extension_id);
+
   Ok(())
 }
 
@@ -714,13 +791,17 @@ pub async fn get_extensions_path(app: AppHandle) -> Result<String, String> {
 /// This allows live preview during development
 #[tauri::command]
 pub async fn load_dev_extension(dev_path: String) -> Result<ExtensionInfo, String> {
-  let path = PathBuf::from(&dev_path);
+  read_dev_extension(&PathBuf::from(dev_path))
+}
 
+/// Read and validate the extension in a dev directory
+pub(crate) fn read_dev_extension(path: &Path) -> Result<ExtensionInfo, String> {
   if !path.exists() {
-    return Err(format!("Dev path does not exist: {}", dev_path));
+    return Err(format!("Dev path does not exist: {}", path.display()));
   }
 
-  let manifest = load_manifest(&path)?;
+  let manifest = load_manifest(path)?;
+  validate_manifest(&manifest)?;
 
   let action_ids: Vec<String> = manifest
     .rua
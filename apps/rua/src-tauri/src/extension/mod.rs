@@ -1,5 +1,9 @@
+mod action_runner;
+mod dev_extension;
 mod extension_storage;
 mod extensions;
 
+pub use action_runner::*;
+pub use dev_extension::*;
 pub use extension_storage::*;
 pub use extensions::*;
@@ -4,6 +4,12 @@ use std::{
 };
 
 use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[cfg(target_os = "linux")]
+use crate::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::not_linux::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -24,9 +30,18 @@ pub fn validate_search_paths(paths: Vec<String>) -> Result<Vec<bool>, String> {
 }
 
 /// Open a file with the specified method
+///
+/// `system` opens it in the default application for its type, found the way
+/// `xdg-open` would, falling back to `xdg-open` itself when there is none.
 #[tauri::command]
-pub async fn open_file(path: String, method: Option<String>) -> Result<(), String> {
-  let open_method = method.as_deref().unwrap_or("xdg-open");
+pub async fn open_file(app: AppHandle, path: String, method: Option<String>) -> Result<(), String> {
+  let open_method = method.as_deref().unwrap_or("system");
+  if open_method == "system" {
+    if let Some(app_id) = default_application_for_file(&path) {
+      launch_application_with(app, app_id, vec![path])?;
+      return Ok(());
+    }
+  }
 
   match open_method {
     "rifle" => {
@@ -35,12 +50,6 @@ pub async fn open_file(path: String, method: Option<String>) -> Result<(), Strin
         .spawn()
         .map_err(|e| format!("Failed to open file with rifle: {}", e))?;
     }
-    "system" => {
-      Command::new("xdg-open")
-        .arg(&path)
-        .spawn()
-        .map_err(|e| format!("Failed to open file: {}", e))?;
-    }
     _ => {
       Command::new("xdg-open")
         .arg(&path)
@@ -1,9 +1,12 @@
 //! File Watcher Module
 //!
 //! Provides file watching capabilities for dev mode hot reload.
+//! Watchers are kept in named slots so the frontend's watcher and the
+//! backend-owned dev extension watcher don't replace each other.
 
 use std::{
-  path::PathBuf,
+  collections::HashMap,
+  path::{Path, PathBuf},
   sync::{Arc, Mutex},
   time::Duration,
 };
@@ -12,19 +15,19 @@ use notify::RecursiveMode;
 use notify_debouncer_mini::{new_debouncer, DebouncedEventKind};
 use tauri::{AppHandle, Emitter};
 
-/// Global state for the file watcher
-struct WatcherState {
+/// Slot used by the `watch_directory` command
+const DEFAULT_WATCHER: &str = "default";
+
+/// A running watcher
+struct Watcher {
   /// The debouncer handle (dropping it stops the watcher)
-  debouncer: Option<notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>>,
+  _debouncer: notify_debouncer_mini::Debouncer<notify::RecommendedWatcher>,
   /// The path being watched
-  watched_path: Option<PathBuf>,
+  path: PathBuf,
 }
 
 lazy_static::lazy_static! {
-    static ref WATCHER_STATE: Arc<Mutex<WatcherState>> = Arc::new(Mutex::new(WatcherState {
-        debouncer: None,
-        watched_path: None,
-    }));
+    static ref WATCHERS: Arc<Mutex<HashMap<String, Watcher>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
 /// Event emitted when files change
@@ -34,107 +37,125 @@ pub struct FileChangeEvent {
   pub kind: String,
 }
 
-/// Start watching a directory for file changes
-/// Emits "file-change" events to the frontend when files change
-#[tauri::command]
-pub async fn watch_directory(app: AppHandle, path: String) -> Result<(), String> {
-  let watch_path = PathBuf::from(&path);
+/// Watch a directory recursively in the given slot, replacing any watcher
+/// already in it. `on_change` receives the changed paths of each debounced batch.
+pub(crate) fn start_watcher<F>(slot: &str, path: &Path, on_change: F) -> Result<(), String>
+where
+  F: Fn(Vec<PathBuf>) + Send + 'static,
+{
+  start_watcher_with_mode(slot, path, RecursiveMode::Recursive, on_change)
+}
 
-  if !watch_path.exists() {
-    return Err(format!("Path does not exist: {}", path));
+/// Like `start_watcher`, with `NonRecursive` watching only the directory's own entries
+pub(crate) fn start_watcher_with_mode<F>(
+  slot: &str,
+  path: &Path,
+  mode: RecursiveMode,
+  on_change: F,
+) -> Result<(), String>
+where
+  F: Fn(Vec<PathBuf>) + Send + 'static,
+{
+  if !path.exists() {
+    return Err(format!("Path does not exist: {}", path.display()));
   }
 
-  if !watch_path.is_dir() {
-    return Err(format!("Path is not a directory: {}", path));
+  if !path.is_dir() {
+    return Err(format!("Path is not a directory: {}", path.display()));
   }
 
   // Stop any existing watcher first
-  stop_watching_internal()?;
-
-  let app_handle = app.clone();
+  stop_watcher(slot)?;
 
   // Create a debounced watcher with 300ms debounce time
   let mut debouncer = new_debouncer(
     Duration::from_millis(300),
-    move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| {
-      match result {
-        Ok(events) => {
-          for event in events {
-            if event.kind == DebouncedEventKind::Any {
-              let event_data = FileChangeEvent {
-                path: event.path.to_string_lossy().to_string(),
-                kind: "change".to_string(),
-              };
-
-              // Emit event to frontend
-              if let Err(e) = app_handle.emit("file-change", event_data) {
-                eprintln!("Failed to emit file-change event: {}", e);
-              }
-            }
-          }
-        }
-        Err(e) => {
-          eprintln!("File watcher error: {:?}", e);
+    move |result: Result<Vec<notify_debouncer_mini::DebouncedEvent>, notify::Error>| match result {
+      Ok(events) => {
+        let paths: Vec<PathBuf> = events
+          .into_iter()
+          .filter(|event| event.kind == DebouncedEventKind::Any)
+          .map(|event| event.path)
+          .collect();
+        if !paths.is_empty() {
+          on_change(paths);
         }
       }
+      Err(e) => {
+        eprintln!("File watcher error: {:?}", e);
+      }
     },
   )
   .map_err(|e| format!("Failed to create file watcher: {}", e))?;
 
-  // Start watching the directory recursively
+  // Start watching the directory
   debouncer
     .watcher()
-    .watch(&watch_path, RecursiveMode::Recursive)
+    .watch(path, mode)
     .map_err(|e| format!("Failed to watch directory: {}", e))?;
 
   // Store the watcher state
-  let mut state = WATCHER_STATE
-    .lock()
-    .map_err(|e| format!("Lock error: {}", e))?;
-  state.debouncer = Some(debouncer);
-  state.watched_path = Some(watch_path);
+  let mut watchers = WATCHERS.lock().map_err(|e| format!("Lock error: {}", e))?;
+  watchers.insert(
+    slot.to_string(),
+    Watcher {
+      _debouncer: debouncer,
+      path: path.to_path_buf(),
+    },
+  );
 
   Ok(())
 }
 
-/// Stop watching the current directory
-#[tauri::command]
-pub async fn stop_watching() -> Result<(), String> {
-  stop_watching_internal()
-}
-
-/// Internal function to stop watching (can be called from sync context)
-fn stop_watching_internal() -> Result<(), String> {
-  let mut state = WATCHER_STATE
-    .lock()
-    .map_err(|e| format!("Lock error: {}", e))?;
+/// Stop the watcher in the given slot (can be called from sync context)
+pub(crate) fn stop_watcher(slot: &str) -> Result<(), String> {
+  let mut watchers = WATCHERS.lock().map_err(|e| format!("Lock error: {}", e))?;
 
   // Dropping the debouncer stops the watcher
-  state.debouncer = None;
-  state.watched_path = None;
+  watchers.remove(slot);
 
   Ok(())
 }
 
+/// Get the path watched in the given slot
+pub(crate) fn watched_path(slot: &str) -> Result<Option<PathBuf>, String> {
+  let watchers = WATCHERS.lock().map_err(|e| format!("Lock error: {}", e))?;
+  Ok(watchers.get(slot).map(|watcher| watcher.path.clone()))
+}
+
+/// Start watching a directory for file changes
+/// Emits "file-change" events to the frontend when files change
+#[tauri::command]
+pub async fn watch_directory(app: AppHandle, path: String) -> Result<(), String> {
+  start_watcher(DEFAULT_WATCHER, Path::new(&path), move |paths| {
+    for path in paths {
+      let event_data = FileChangeEvent {
+        path: path.to_string_lossy().to_string(),
+        kind: "change".to_string(),
+      };
+
+      // Emit event to frontend
+      if let Err(e) = app.emit("file-change", event_data) {
+        eprintln!("Failed to emit file-change event: {}", e);
+      }
+    }
+  })
+}
+
+/// Stop watching the current directory
+#[tauri::command]
+pub async fn stop_watching() -> Result<(), String> {
+  stop_watcher(DEFAULT_WATCHER)
+}
+
 /// Check if currently watching a directory
 #[tauri::command]
 pub async fn is_watching() -> Result<bool, String> {
-  let state = WATCHER_STATE
-    .lock()
-    .map_err(|e| format!("Lock error: {}", e))?;
-  Ok(state.debouncer.is_some())
+  Ok(watched_path(DEFAULT_WATCHER)?.is_some())
 }
 
 /// Get the currently watched path
 #[tauri::command]
 pub async fn get_watched_path() -> Result<Option<String>, String> {
-  let state = WATCHER_STATE
-    .lock()
-    .map_err(|e| format!("Lock error: {}", e))?;
-  Ok(
-    state
-      .watched_path
-      .as_ref()
-      .map(|p| p.to_string_lossy().to_string()),
-  )
+  Ok(watched_path(DEFAULT_WATCHER)?.map(|p| p.to_string_lossy().to_string()))
 }
@@ -7,7 +7,7 @@ use std::{fs, path::Path};
 use serde::{Deserialize, Serialize};
 
 /// Expand environment variables in path (e.g., $HOME)
-fn expand_path(path: &str) -> String {
+pub(crate) fn expand_path(path: &str) -> String {
   let mut result = path.to_string();
 
   // Expand $HOME
@@ -1,8 +1,22 @@
+mod cli;
+mod clipboard_history;
+mod control_auth;
+mod control_discovery;
+mod control_paths;
+mod control_rpc;
 mod control_server;
+#[cfg(unix)]
+mod control_socket;
+mod deep_link;
+mod diagnostic_checks;
+mod diagnostics;
+mod event_stream;
 mod file_search;
 mod file_watcher;
 mod fs_api;
 mod preferences;
+#[cfg(test)]
+mod test_util;
 pub mod types;
 mod webpage_info;
 
@@ -23,12 +37,12 @@ use extension::*;
 use not_linux::*;
 use tauri::{
   http::{Request, Response},
-  App, Emitter, Manager,
+  App, Emitter,
 };
 
 use crate::{settigns_view::new_settings_view, system_tray::setup_tray};
 
-fn setup(app: &mut App) -> anyhow::Result<()> {
+fn setup(app: &mut App, launch_args: &cli::LaunchArgs) -> anyhow::Result<()> {
   // let win = app.get_webview_window("main").unwrap();
   // win.eval("window.location.reload()")?;
   new_settings_view(app.handle(), false)?;
@@ -40,6 +54,29 @@ fn setup(app: &mut App) -> anyhow::Result<()> {
   // Setup system tray
   setup_tray(app)?;
 
+  // Expose window and extension events to control server subscribers
+  event_stream::forward_app_events(app.handle());
+
+  // Record copied text and images for the clipboard history
+  clipboard_history::start_clipboard_history(app.handle());
+
+  // Index the installed applications and keep the index up to date
+  watch_applications(app.handle());
+
+  // Keep watching the registered dev extension across restarts
+  restore_dev_extension(app.handle());
+
+  // Register the rua:// URL handler
+  let app_handle = app.handle().clone();
+  std::thread::spawn(move || {
+    if let Err(e) = register_url_handler(&app_handle) {
+      eprintln!("Failed to register rua:// URL handler: {}", e);
+    }
+  });
+
+  // Show the window unless started with --hidden, and apply the query, view and links
+  cli::handle_launch_args(app.handle(), launch_args);
+
   // Start the control server in a separate thread
   let app_handle = app.handle().clone();
   std::thread::spawn(move || {
@@ -146,33 +183,66 @@ async fn broadcast_event(
   payload: serde_json::Value,
 ) -> Result<(), String> {
   app
-    .emit(&event_name, payload)
-    .map_err(|e| format!("Failed to broadcast event: {}", e))
+    .emit(&event_name, payload.clone())
+    .map_err(|e| format!("Failed to broadcast event: {}", e))?;
+  event_stream::publish_broadcast_event(&event_name, payload);
+  Ok(())
 }
 
 pub fn run() {
+  let launch_args = match cli::parse_args(std::env::args().skip(1)) {
+    Ok(cli::CliCommand::Launch(args)) => args,
+    Ok(cli::CliCommand::Help) => {
+      println!("{}", cli::USAGE);
+      return;
+    }
+    Ok(cli::CliCommand::Version) => {
+      println!("rua {}", env!("CARGO_PKG_VERSION"));
+      return;
+    }
+    Err(e) => {
+      eprintln!("Error: {}\n\n{}", e, cli::USAGE);
+      std::process::exit(2);
+    }
+  };
+
   tauri::Builder::default()
     .plugin(tauri_plugin_http::init())
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_opener::init())
-    .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-      // When another instance tries to start, show and focus the existing window
-      if let Some(window) = app.get_webview_window("main") {
-        let _ = window.show();
-        let _ = window.set_focus();
-      }
+    .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+      // When another instance tries to start, apply its arguments to this one
+      cli::handle_forwarded_args(app, &args);
     }))
     .register_uri_scheme_protocol("ext", handle_ext_protocol)
-    .setup(|app| {
-      setup(app)?;
+    .register_asynchronous_uri_scheme_protocol("icon", handle_icon_protocol)
+    .setup(move |app| {
+      setup(app, &launch_args)?;
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
       get_applications,
       refresh_applications_cache,
       launch_application,
+      launch_application_with,
+      get_file_handlers,
+      set_default_application,
       read_clipboard,
       write_clipboard,
+      get_clipboard_formats,
+      read_clipboard_format,
+      write_clipboard_format,
+      read_clipboard_image,
+      write_clipboard_image,
+      read_clipboard_files,
+      write_clipboard_files,
+      get_selected_text,
+      clipboard_history::get_clipboard_history,
+      clipboard_history::search_clipboard_history,
+      clipboard_history::pin_clipboard_entry,
+      clipboard_history::delete_clipboard_entry,
+      clipboard_history::clear_clipboard_history,
+      clipboard_history::copy_clipboard_entry,
       execute_shell_command,
       execute_shell_command_async,
       webpage_info::fetch_page_info,
@@ -183,12 +253,17 @@ pub fn run() {
       disable_extension,
       get_extensions_path,
       load_dev_extension,
+      get_dev_extension,
+      set_dev_extension,
+      report_dev_extension_error,
+      resolve_action_run,
       hide_window_command,
       file_watcher::watch_directory,
       file_watcher::stop_watching,
       file_watcher::is_watching,
       file_watcher::get_watched_path,
       show_notification,
+      close_notification,
       extension_storage_get,
       extension_storage_set,
       extension_storage_remove,
@@ -209,6 +284,7 @@ pub fn run() {
       preferences::remove_preference,
       preferences::remove_all_preferences,
       broadcast_event,
+      deep_link::deep_links_ready,
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
@@ -1,404 +1,699 @@
 use std::{
-  collections::HashMap, fs, path::PathBuf, process::Command, sync::Mutex, time::SystemTime,
+  collections::{HashMap, HashSet},
+  fs,
+  os::unix::fs::PermissionsExt,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex, MutexGuard, PoisonError},
+  time::SystemTime,
 };
 
+use notify::RecursiveMode;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-use crate::types::Application;
+use super::desktop_entry::{
+  current_desktops, current_locales, expand_exec, split_exec, ExecContext, KeyFile,
+  DESKTOP_ENTRY_GROUP,
+};
+use super::icon_theme::{
+  current_icon_theme, lookup_icon, reload_icon_theme, save_icon_cache, APP_ICON_SIZE,
+};
+use super::launcher::launch_detached;
+use crate::diagnostic_checks::TERMINAL_EMULATORS;
+use crate::file_watcher::start_watcher_with_mode;
+use crate::types::{Application, ApplicationAction};
+
+/// Event emitted with the differences when applications are installed,
+/// removed or changed
+pub const APPLICATIONS_CHANGED_EVENT: &str = "rua://applications-changed";
 
 #[cfg(target_os = "linux")]
 lazy_static::lazy_static! {
-    static ref ICON_CACHE: Mutex<HashMap<String, Option<String>>> = Mutex::new(HashMap::new());
     static ref TERMINAL_EMULATOR: Mutex<Option<String>> = Mutex::new(None);
+    /// Index of the installed applications, `None` until it is loaded
+    ///
+    /// Only held to read or swap the index, so commands reading it never wait
+    /// for desktop files to be parsed.
+    static ref APP_INDEX: Mutex<Option<Arc<AppIndex>>> = Mutex::new(None);
+    /// Held while the index is loaded or updated, one at a time
+    static ref INDEX_UPDATE: Mutex<()> = Mutex::new(());
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AppCache {
-  applications: Vec<Application>,
-  timestamp: u64,
+/// Version of the cache format, bumped when cached applications change meaning
+const CACHE_VERSION: u32 = 3;
+
+/// A desktop file found in an applications directory
+#[derive(Debug)]
+struct DesktopFile {
+  id: String,
+  path: String,
+  modified: SystemTime,
 }
 
-#[tauri::command]
-pub fn get_applications() -> Vec<Application> {
-  // Try to load from cache first
-  if let Some(cached_apps) = load_cache() {
-    return cached_apps;
+/// A parsed desktop file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+  modified: SystemTime,
+  /// `None` for files that aren't listed, e.g. `NoDisplay` entries
+  application: Option<Application>,
+  /// `None` for files that can't be launched, e.g. `Hidden` entries
+  handler: Option<MimeHandler>,
+}
+
+impl IndexEntry {
+  /// Drop the `Icon` keys of icons that don't exist
+  fn resolve_icons(&mut self) {
+    if let Some(application) = &mut self.application {
+      application.icon = application.icon.take().and_then(existing_icon);
+      for action in &mut application.actions {
+        action.icon = action.icon.take().and_then(existing_icon);
+      }
+    }
+    if let Some(handler) = &mut self.handler {
+      handler.icon = handler.icon.take().and_then(existing_icon);
+    }
   }
+}
 
-  eprintln!("Loading applications from disk...");
-  let start = std::time::Instant::now();
+/// An application that can open files, listed or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MimeHandler {
+  pub id: String,
+  pub name: String,
+  pub icon: Option<String>,
+  /// Types from the `MimeType` key
+  pub mime_types: Vec<String>,
+}
 
-  let mut applications = Vec::new();
+/// Parsed desktop files, saved between runs so only new and modified files
+/// have to be parsed again
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct AppIndex {
+  version: u32,
+  /// Environment the entries were parsed in
+  locales: Vec<String>,
+  desktops: Vec<String>,
+  icon_theme: String,
+  /// Entries by desktop file path
+  entries: HashMap<String, IndexEntry>,
+}
 
-  // Common directories for .desktop files on Linux
-  let home = &format!(
-    "{}/.local/share/applications",
-    std::env::var("HOME").unwrap_or_default()
-  );
-  let app_dirs = vec![
-    "/usr/share/applications",
-    "/usr/local/share/applications",
-    home,
-  ];
-
-  for dir in &app_dirs {
-    let path = PathBuf::from(dir);
-    if !path.exists() {
-      continue;
+impl AppIndex {
+  fn new(locales: Vec<String>, desktops: Vec<String>, icon_theme: String) -> Self {
+    Self {
+      version: CACHE_VERSION,
+      locales,
+      desktops,
+      icon_theme,
+      entries: HashMap::new(),
     }
+  }
 
-    for entry in WalkDir::new(path)
-      .max_depth(2)
-      .into_iter()
-      .filter_map(|e| e.ok())
-    {
-      if let Some(ext) = entry.path().extension() {
-        if ext == "desktop" {
-          if let Ok(app) = parse_desktop_file(entry.path()) {
-            applications.push(app);
-          }
+  /// Load the index saved by an earlier run, if it was made in the same
+  /// environment, otherwise start an empty one
+  fn load(locales: Vec<String>, desktops: Vec<String>, icon_theme: String) -> Self {
+    let saved = fs::read_to_string(get_cache_path())
+      .ok()
+      .and_then(|content| serde_json::from_str::<AppIndex>(&content).ok());
+
+    match saved {
+      Some(index)
+        if index.version == CACHE_VERSION
+          && index.locales == locales
+          && index.desktops == desktops
+          && index.icon_theme == icon_theme =>
+      {
+        index
+      }
+      Some(_) => {
+        eprintln!("Application cache is outdated, parsing all desktop files");
+        Self::new(locales, desktops, icon_theme)
+      }
+      None => Self::new(locales, desktops, icon_theme),
+    }
+  }
+
+  fn save(&self) {
+    let cache_path = get_cache_path();
+    match serde_json::to_string(self) {
+      Ok(json) => {
+        if let Err(e) = fs::write(&cache_path, json) {
+          eprintln!("Failed to write cache: {}", e);
         }
       }
+      Err(e) => eprintln!("Failed to serialize cache: {}", e),
     }
   }
 
-  applications.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+  /// Bring the entries up to date with the desktop files in the directories,
+  /// parsing new and modified files in parallel
+  ///
+  /// Returns the number of entries that were added, updated or removed.
+  fn update(&mut self, app_dirs: &[PathBuf]) -> usize {
+    let files = find_desktop_files(app_dirs);
+    let mut parsed: Vec<(String, IndexEntry)> = files
+      .par_iter()
+      .filter(|file| {
+        self
+          .entries
+          .get(&file.path)
+          .is_none_or(|entry| entry.modified != file.modified)
+      })
+      .map(|file| {
+        let path = Path::new(&file.path);
+        let key_file = KeyFile::load(path).ok();
+        let parse = |key_file: &KeyFile| {
+          let id = file.id.clone();
+          parse_desktop_entry(key_file, path, id, &self.locales, &self.desktops).ok()
+        };
+        let entry = IndexEntry {
+          modified: file.modified,
+          application: key_file.as_ref().and_then(parse),
+          handler: key_file
+            .as_ref()
+            .and_then(|key_file| parse_mime_handler(key_file, &file.id, &self.locales)),
+        };
+        (file.path.clone(), entry)
+      })
+      .collect();
+    // Icon lookups share one cache behind a lock, they would serialize the parsing
+    for (_, entry) in &mut parsed {
+      entry.resolve_icons();
+    }
 
-  let duration = start.elapsed();
-  eprintln!(
-    "Loaded {} applications in {:?}",
-    applications.len(),
-    duration
-  );
+    let paths: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    let count = self.entries.len();
+    self.entries.retain(|path, _| paths.contains(path.as_str()));
+    let removed = count - self.entries.len();
+
+    let changed = removed + parsed.len();
+    self.entries.extend(parsed);
+    changed
+  }
 
-  // Save to cache with current timestamp
-  if let Some(timestamp) = get_latest_mtime(&app_dirs.iter().map(|s| *s).collect::<Vec<_>>()) {
-    save_cache(&applications, timestamp);
+  /// Listed applications, sorted by name
+  fn applications(&self) -> Vec<Application> {
+    let mut applications: Vec<Application> = self
+      .entries
+      .values()
+      .filter_map(|entry| entry.application.clone())
+      .collect();
+    applications
+      .sort_by(|a, b| (a.name.to_lowercase(), &a.id).cmp(&(b.name.to_lowercase(), &b.id)));
+    applications
   }
 
-  applications
+  /// Check whether an entry or one of its actions uses an icon
+  fn has_icon(&self, icon: &str) -> bool {
+    self.entries.values().any(|entry| {
+      let application = entry.application.as_ref();
+      let handler_icon = entry
+        .handler
+        .as_ref()
+        .and_then(|handler| handler.icon.as_deref());
+      application
+        .and_then(|app| app.icon.as_deref())
+        .into_iter()
+        .chain(handler_icon)
+        .chain(
+          application
+            .into_iter()
+            .flat_map(|app| &app.actions)
+            .filter_map(|action| action.icon.as_deref()),
+        )
+        .any(|entry_icon| entry_icon == icon)
+    })
+  }
+
+  /// Applications that can open files, sorted by name
+  fn handlers(&self) -> Vec<MimeHandler> {
+    let mut handlers: Vec<MimeHandler> = self
+      .entries
+      .values()
+      .filter_map(|entry| entry.handler.clone())
+      .collect();
+    handlers.sort_by(|a, b| (a.name.to_lowercase(), &a.id).cmp(&(b.name.to_lowercase(), &b.id)));
+    handlers
+  }
 }
 
-#[tauri::command]
-pub fn refresh_applications_cache() -> Result<String, String> {
-  // Delete the cache file
-  let cache_path = get_cache_path();
-  if cache_path.exists() {
-    std::fs::remove_file(&cache_path).map_err(|e| format!("Failed to remove cache: {}", e))?;
-    eprintln!("Cache deleted, will reload on next request");
-    Ok("Cache refreshed successfully".to_string())
-  } else {
-    Ok("No cache to refresh".to_string())
+/// Payload of the applications changed event
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ApplicationsChanged {
+  pub added: Vec<Application>,
+  pub updated: Vec<Application>,
+  /// Desktop file IDs of the removed applications
+  pub removed: Vec<String>,
+}
+
+impl ApplicationsChanged {
+  fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
   }
 }
 
-#[tauri::command]
-pub fn launch_application(exec: String, terminal: bool) -> Result<String, String> {
-  // Remove field codes like %f, %F, %u, %U, etc.
-  let cleaned_exec = exec
-    .split_whitespace()
-    .filter(|part| !part.starts_with('%'))
-    .collect::<Vec<_>>()
-    .join(" ");
-
-  if terminal {
-    // Application needs to run in terminal
-    let terminal_emulator =
-      detect_terminal_emulator().ok_or_else(|| "No terminal emulator found".to_string())?;
+/// Compare two application lists by desktop file ID
+fn diff_applications(old: &[Application], new: &[Application]) -> ApplicationsChanged {
+  let old_by_id: HashMap<&str, &Application> =
+    old.iter().map(|app| (app.id.as_str(), app)).collect();
+  let new_ids: HashSet<&str> = new.iter().map(|app| app.id.as_str()).collect();
+
+  let mut changes = ApplicationsChanged::default();
+  for app in new {
+    match old_by_id.get(app.id.as_str()) {
+      None => changes.added.push(app.clone()),
+      Some(old_app) if *old_app != app => changes.updated.push(app.clone()),
+      Some(_) => {}
+    }
+  }
+  changes.removed = old
+    .iter()
+    .filter(|app| !new_ids.contains(app.id.as_str()))
+    .map(|app| app.id.clone())
+    .collect();
+  changes
+}
 
-    // Build command based on terminal emulator
-    let (cmd, args) = match terminal_emulator.as_str() {
-      "wezterm" => ("wezterm", vec!["-e", &cleaned_exec]),
-      "konsole" => ("konsole", vec!["-e", &cleaned_exec]),
-      "gnome-terminal" => ("gnome-terminal", vec!["--", "sh", "-c", &cleaned_exec]),
-      "alacritty" => ("alacritty", vec!["-e", "sh", "-c", &cleaned_exec]),
-      "kitty" => ("kitty", vec!["sh", "-c", &cleaned_exec]),
-      "terminator" => ("terminator", vec!["-e", &cleaned_exec]),
-      "tilix" => ("tilix", vec!["-e", &cleaned_exec]),
-      "xfce4-terminal" => ("xfce4-terminal", vec!["-e", &cleaned_exec]),
-      "mate-terminal" => ("mate-terminal", vec!["-e", &cleaned_exec]),
-      "lxterminal" => ("lxterminal", vec!["-e", &cleaned_exec]),
-      "xterm" => ("xterm", vec!["-e", &cleaned_exec]),
-      _ => return Err(format!("Unknown terminal emulator: {}", terminal_emulator)),
-    };
+/// Load the saved index and update it with the application directories
+fn load_index() -> AppIndex {
+  let start = std::time::Instant::now();
+  let mut index = AppIndex::load(current_locales(), current_desktops(), current_icon_theme());
+  let changed = index.update(&application_dirs());
+  if changed > 0 {
+    index.save();
+    save_icon_cache();
+  }
 
-    eprintln!("Launching in terminal: {} {:?}", cmd, args);
+  eprintln!(
+    "Loaded {} desktop files in {:?}, {} changed since the last run",
+    index.entries.len(),
+    start.elapsed(),
+    changed
+  );
+  index
+}
 
-    Command::new(cmd)
-      .args(&args)
-      .spawn()
-      .map_err(|e| format!("Failed to launch application in terminal: {}", e))?;
+/// Lock a mutex, going on with its data if a thread panicked holding it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+  mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    Ok(format!(
-      "Launched in {}: {}",
-      terminal_emulator, cleaned_exec
-    ))
-  } else {
-    // Normal application launch
-    Command::new("sh")
-      .arg("-c")
-      .arg(&cleaned_exec)
-      .spawn()
-      .map_err(|e| format!("Failed to launch application: {}", e))?;
+/// The index as loaded so far, `None` while it is loading
+fn current_index() -> Option<Arc<AppIndex>> {
+  lock(&APP_INDEX).clone()
+}
 
-    Ok(format!("Launched: {}", cleaned_exec))
+/// Get the index, loading it first unless it is loaded
+fn ensure_index() -> Arc<AppIndex> {
+  let _update = lock(&INDEX_UPDATE);
+  if let Some(index) = current_index() {
+    return index;
   }
+  let index = Arc::new(load_index());
+  *lock(&APP_INDEX) = Some(index.clone());
+  index
 }
 
-/// Get the cache file path
-fn get_cache_path() -> PathBuf {
-  let cache_dir = std::env::var("XDG_CACHE_HOME")
-    .unwrap_or_else(|_| format!("{}/.cache", std::env::var("HOME").unwrap_or_default()));
-  let mut path = PathBuf::from(cache_dir);
-  path.push("rua");
-  if !path.exists() {
-    let _ = fs::create_dir_all(&path);
+/// Update the index after an application directory changed
+fn rescan_applications() -> ApplicationsChanged {
+  let _update = lock(&INDEX_UPDATE);
+  // The index picks up the change when it is loaded
+  let Some(old) = current_index() else {
+    return ApplicationsChanged::default();
+  };
+
+  let mut index = AppIndex::clone(&old);
+  if index.update(&application_dirs()) == 0 {
+    return ApplicationsChanged::default();
   }
-  path.push("applications.json");
-  path
+  index.save();
+  save_icon_cache();
+  let changes = diff_applications(&old.applications(), &index.applications());
+  *lock(&APP_INDEX) = Some(Arc::new(index));
+  changes
 }
 
-/// Get the latest modification time from application directories
-fn get_latest_mtime(app_dirs: &[&str]) -> Option<u64> {
-  let mut latest: Option<u64> = None;
+fn emit_applications_changed(app: &AppHandle, changes: &ApplicationsChanged) {
+  if changes.is_empty() {
+    return;
+  }
+  if let Err(e) = app.emit(APPLICATIONS_CHANGED_EVENT, changes) {
+    eprintln!("Failed to emit applications changed event: {}", e);
+  }
+}
 
-  for dir in app_dirs {
-    let path = PathBuf::from(dir);
-    if !path.exists() {
-      continue;
-    }
+/// The directory to watch for an application directory: itself, or its
+/// closest existing parent until it is created
+fn watch_target(dir: &Path) -> Option<PathBuf> {
+  if dir.is_dir() {
+    return Some(dir.to_path_buf());
+  }
+  dir
+    .ancestors()
+    .skip(1)
+    .find(|parent| parent.is_dir())
+    .map(Path::to_path_buf)
+}
 
-    for entry in WalkDir::new(path)
-      .max_depth(2)
-      .into_iter()
-      .filter_map(|e| e.ok())
-    {
-      if let Some(ext) = entry.path().extension() {
-        if ext == "desktop" {
-          if let Ok(metadata) = entry.metadata() {
-            if let Ok(modified) = metadata.modified() {
-              if let Ok(duration) = modified.duration_since(SystemTime::UNIX_EPOCH) {
-                let mtime = duration.as_secs();
-                latest = Some(latest.map_or(mtime, |l| l.max(mtime)));
-              }
-            }
-          }
-        }
-      }
+/// Watch an application directory, moving the watcher when the directory or
+/// one of its parents is created or removed
+fn watch_application_dir(app: AppHandle, dir: PathBuf) {
+  let Some(watched) = watch_target(&dir) else {
+    return;
+  };
+  // Of a parent, only its own entries matter, its whole tree may be large
+  let mode = if watched == dir {
+    RecursiveMode::Recursive
+  } else {
+    RecursiveMode::NonRecursive
+  };
+
+  let slot = format!("applications:{}", dir.display());
+  let watched_dir = watched.clone();
+  let result = start_watcher_with_mode(&slot, &watched, mode, move |_paths| {
+    // The new watcher replaces this one in the slot
+    if watch_target(&dir).as_ref() != Some(&watched_dir) {
+      watch_application_dir(app.clone(), dir.clone());
+    }
+    if watched_dir == dir || dir.is_dir() {
+      emit_applications_changed(&app, &rescan_applications());
     }
+  });
+  if let Err(e) = result {
+    eprintln!("Failed to watch {}: {}", watched.display(), e);
   }
+}
+
+/// Load the application index in the background and watch the application
+/// directories, emitting the applications changed event when they change
+pub fn watch_applications(app: &AppHandle) {
+  let app = app.clone();
+  std::thread::spawn(move || {
+    // Load the index before the frontend asks for it
+    ensure_index();
 
-  latest
+    for dir in application_dirs() {
+      watch_application_dir(app.clone(), dir);
+    }
+  });
 }
 
-/// Load applications from cache if valid
-fn load_cache() -> Option<Vec<Application>> {
-  let cache_path = get_cache_path();
-  if !cache_path.exists() {
-    eprintln!("Cache file does not exist");
-    return None;
+/// Get the listed applications, waiting for the index if it is still loading
+#[tauri::command]
+pub async fn get_applications() -> Vec<Application> {
+  if let Some(index) = current_index() {
+    return index.applications();
   }
+  match tauri::async_runtime::spawn_blocking(ensure_index).await {
+    Ok(index) => index.applications(),
+    Err(e) => {
+      eprintln!("Failed to load applications: {}", e);
+      Vec::new()
+    }
+  }
+}
 
-  let cache_content = fs::read_to_string(&cache_path).ok()?;
-  let cache: AppCache = serde_json::from_str(&cache_content).ok()?;
+/// Get the applications that can open files, including unlisted ones
+///
+/// Empty while the index is loading.
+pub(crate) fn mime_handlers() -> Vec<MimeHandler> {
+  current_index()
+    .map(|index| index.handlers())
+    .unwrap_or_default()
+}
 
-  // Get current latest mtime
-  let home = &format!(
-    "{}/.local/share/applications",
-    std::env::var("HOME").unwrap_or_default()
-  );
-  let app_dirs = vec![
-    "/usr/share/applications",
-    "/usr/local/share/applications",
-    home.as_str(),
-  ];
-
-  let current_mtime = get_latest_mtime(&app_dirs)?;
-
-  // Check if cache is still valid
-  if cache.timestamp >= current_mtime {
-    eprintln!(
-      "Using cached applications (cache: {}, current: {})",
-      cache.timestamp, current_mtime
-    );
-    Some(cache.applications)
-  } else {
-    eprintln!(
-      "Cache is outdated (cache: {}, current: {})",
-      cache.timestamp, current_mtime
-    );
-    None
+/// Check whether an installed desktop entry uses an icon name or path
+///
+/// False for every icon while the index is loading.
+pub(crate) fn is_application_icon(icon: &str) -> bool {
+  current_index().is_some_and(|index| index.has_icon(icon))
+}
+
+/// Parse all desktop files again, e.g. after switching icon themes
+#[tauri::command]
+pub async fn refresh_applications_cache(app: AppHandle) -> Result<String, String> {
+  tauri::async_runtime::spawn_blocking(move || reload_index(&app))
+    .await
+    .map_err(|e| format!("Failed to reload applications: {}", e))?
+}
+
+/// Replace the index with a new one parsed from all desktop files
+fn reload_index(app: &AppHandle) -> Result<String, String> {
+  // Pick up a changed icon theme too
+  reload_icon_theme();
+
+  let _update = lock(&INDEX_UPDATE);
+  let old = current_index()
+    .map(|index| index.applications())
+    .unwrap_or_default();
+  let mut index = AppIndex::new(current_locales(), current_desktops(), current_icon_theme());
+  index.update(&application_dirs());
+  index.save();
+  save_icon_cache();
+
+  let applications = index.applications();
+  *lock(&APP_INDEX) = Some(Arc::new(index));
+  emit_applications_changed(app, &diff_applications(&old, &applications));
+  Ok(format!("Reloaded {} applications", applications.len()))
+}
+
+#[tauri::command]
+pub fn launch_application(
+  app: AppHandle,
+  exec: String,
+  terminal: bool,
+  working_dir: Option<String>,
+) -> Result<String, String> {
+  let args = split_exec(&exec)?;
+  for argv in expand_exec(&args, &ExecContext::default()) {
+    spawn_command(&app, &argv, terminal, working_dir.as_deref(), None)?;
   }
+  Ok(format!("Launched: {}", exec))
 }
 
-/// Save applications to cache
-fn save_cache(applications: &[Application], timestamp: u64) {
-  let cache_path = get_cache_path();
-  let cache = AppCache {
-    applications: applications.to_vec(),
-    timestamp,
+/// Open files or URLs in the application with the given desktop file ID
+///
+/// The Exec key decides whether the application is started once for all of
+/// them or once for each. Works for applications hidden from the list too.
+#[tauri::command]
+pub fn launch_application_with(
+  app: AppHandle,
+  app_id: String,
+  uris: Vec<String>,
+) -> Result<String, String> {
+  let path =
+    find_desktop_file(&app_id).ok_or_else(|| format!("Application not found: {}", app_id))?;
+  let file = KeyFile::load(&path)?;
+  let group = DESKTOP_ENTRY_GROUP;
+
+  // A hidden entry in a user directory deletes the application
+  if file.boolean(group, "Hidden") {
+    return Err(format!("Application not found: {}", app_id));
+  }
+  let exec = file
+    .string(group, "Exec")
+    .ok_or_else(|| format!("Application has no Exec key: {}", app_id))?;
+  let name = file.locale_string(group, "Name", &current_locales());
+  let icon = file.string(group, "Icon");
+  let desktop_file = path.to_string_lossy();
+  let context = ExecContext {
+    uris: &uris,
+    icon: icon.as_deref(),
+    name: name.as_deref(),
+    desktop_file: Some(&desktop_file),
   };
 
-  if let Ok(cache_json) = serde_json::to_string(&cache) {
-    if let Err(e) = fs::write(&cache_path, cache_json) {
-      eprintln!("Failed to write cache: {}", e);
-    } else {
-      eprintln!("Cache saved successfully to {:?}", cache_path);
-    }
+  let args = split_exec(&exec)?;
+  let working_dir = file.string(group, "Path");
+  for argv in expand_exec(&args, &context) {
+    spawn_command(
+      &app,
+      &argv,
+      file.boolean(group, "Terminal"),
+      working_dir.as_deref(),
+      Some(&app_id),
+    )?;
   }
+  Ok(format!("Launched {} with {} file(s)", app_id, uris.len()))
 }
 
-/// Get XDG data directories, falling back to default if not set
-/// See: https://specifications.freedesktop.org/basedir-spec/basedir-spec-latest.html
-fn get_xdg_data_dirs() -> Vec<String> {
-  std::env::var("XDG_DATA_DIRS")
-    .unwrap_or_else(|_| "/usr/share:/usr/local/share".to_string())
-    .split(':')
-    .map(|s| s.to_string())
-    .collect()
+/// Quote an argument for a terminal that takes the command as one string
+fn shell_quote(arg: &str) -> String {
+  format!("'{}'", arg.replace('\'', r"'\''"))
 }
 
-/// Generate icon paths following XDG Base Directory Specification
-/// See: https://specifications.freedesktop.org/icon-theme-spec/icon-theme-spec-latest.html
-fn generate_icon_paths(icon_name: &str) -> Vec<String> {
-  let mut paths = Vec::new();
-
-  // Always include the icon name itself first (for named icons)
-  paths.push(icon_name.to_string());
-
-  // Get XDG data directories
-  let xdg_dirs = get_xdg_data_dirs();
-
-  // Icon sizes following XDG icon theme specification (in search order)
-  // Scalable comes first as it's resolution-independent
-  let sizes = vec![
-    "scalable", // SVG preferred for vector graphics
-    "64x64",
-    "48x48",
-    "32x32",
-    "24x24",
-    "16x16",
-    "128x128",
-    "256x256",
-    "512x512",
-  ];
-
-  // Extensions in order of preference (SVG preferred for scalability)
-  let extensions = vec!["svg", "png"];
-
-  // Common icon themes (hicolor is required fallback, others are system-specific)
-  let themes = vec!["hicolor", "breeze", "Adwaita", "oxygen", "gnome", "highcontrast"];
-
-  // Contexts as defined in XDG spec (apps, actions, categories, devices, emblems, mimetypes, places, status)
-  let contexts = vec!["apps", "actions", "categories", "devices", "emblems", "mimetypes", "places", "status"];
-
-  // Generate paths following XDG icon theme specification
-  // Pattern: $XDG_DATA_DIRS/icons/<theme>/<size>/<context>/<name>.<ext>
-  for data_dir in &xdg_dirs {
-    for theme in &themes {
-      // 1. Theme-based icon paths (XDG standard structure)
-      for size in &sizes {
-        for ext in &extensions {
-          // Standard context-based icons
-          for _context in &contexts {
-            paths.push(format!(
-              "{}/icons/{}/{}/{}.{}",
-              data_dir, theme, size, icon_name, ext
-            ));
-          }
-
-          // Legacy location for apps icons (without context)
-          paths.push(format!(
-            "{}/icons/{}/{}/{}",
-            data_dir, theme, size, icon_name
-          ));
-        }
-      }
-
-      // 2. Symbolic icons (Adwaita and breeze specific)
-      // Pattern: icons/<theme>/<context>/symbolic/<name>.svg
-      paths.push(format!("{}/icons/{}/actions/symbolic/{}.svg", data_dir, theme, icon_name));
-      paths.push(format!("{}/icons/{}/status/symbolic/{}.svg", data_dir, theme, icon_name));
-      paths.push(format!("{}/icons/{}/apps/symbolic/{}.svg", data_dir, theme, icon_name));
-
-      // Legacy symbolic paths
-      paths.push(format!(
-        "{}/icons/{}/symbolic/legacy/{}-symbolic.png",
-        data_dir, theme, icon_name
-      ));
-      paths.push(format!(
-        "{}/icons/{}/symbolic/legacy/{}-symbolic.svg",
-        data_dir, theme, icon_name
-      ));
-      paths.push(format!(
-        "{}/icons/{}/16x16/legacy/{}-symbolic.png",
-        data_dir, theme, icon_name
-      ));
+/// Build the command line running `argv` in a terminal emulator
+fn terminal_command(terminal: &str, argv: &[String]) -> Result<Vec<String>, String> {
+  let mut command = vec![terminal.to_string()];
+  match terminal {
+    "gnome-terminal" => command.push("--".to_string()),
+    "wezterm" => command.extend(["start".to_string(), "--".to_string()]),
+    "konsole" | "alacritty" | "xterm" => command.push("-e".to_string()),
+    "kitty" => {}
+    "terminator" | "xfce4-terminal" | "mate-terminal" => command.push("-x".to_string()),
+    // These only take the command as a single string
+    "tilix" | "lxterminal" => {
+      let line = argv.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>();
+      command.extend(["-e".to_string(), line.join(" ")]);
+      return Ok(command);
     }
-
-    // 3. Legacy pixmaps directory (still widely used)
-    // $XDG_DATA_DIRS/pixmaps/<name>.<ext>
-    paths.push(format!("{}/pixmaps/{}.svg", data_dir, icon_name));
-    paths.push(format!("{}/pixmaps/{}.png", data_dir, icon_name));
-    paths.push(format!("{}/pixmaps/{}", data_dir, icon_name));
-
-    // 4. Root icons directory (fallback)
-    paths.push(format!("{}/icons/{}.png", data_dir, icon_name));
+    _ => return Err(format!("Unknown terminal emulator: {}", terminal)),
   }
+  command.extend(argv.iter().cloned());
+  Ok(command)
+}
 
-  paths
+/// Start an expanded command line detached from rua, without a shell in
+/// between
+fn spawn_command(
+  app: &AppHandle,
+  argv: &[String],
+  terminal: bool,
+  working_dir: Option<&str>,
+  app_id: Option<&str>,
+) -> Result<(), String> {
+  let argv = if terminal {
+    // Application needs to run in terminal
+    let terminal_emulator =
+      detect_terminal_emulator().ok_or_else(|| "No terminal emulator found".to_string())?;
+    terminal_command(&terminal_emulator, argv)?
+  } else {
+    argv.to_vec()
+  };
+  launch_detached(app, &argv, working_dir, app_id)
 }
 
-/// Find the actual icon file path for a given icon name
-fn resolve_icon_path(icon_name: &str) -> Option<String> {
-  if icon_name.is_empty() {
-    return None;
+/// Get the cache file path
+pub(crate) fn get_cache_path() -> PathBuf {
+  let cache_dir = std::env::var("XDG_CACHE_HOME")
+    .unwrap_or_else(|_| format!("{}/.cache", std::env::var("HOME").unwrap_or_default()));
+  let mut path = PathBuf::from(cache_dir);
+  path.push("rua");
+  if !path.exists() {
+    let _ = fs::create_dir_all(&path);
   }
+  path.push("applications.json");
+  path
+}
 
-  // Check cache first
-  {
-    let cache = ICON_CACHE.lock().unwrap();
-    if let Some(cached) = cache.get(icon_name) {
-      return cached.clone();
+/// Get the XDG data directories in search order: `XDG_DATA_HOME`, then
+/// `XDG_DATA_DIRS`, with the spec's defaults when they aren't set
+/// See: https://specifications.freedesktop.org/basedir-spec/basedir-spec-latest.html
+pub(crate) fn data_dirs() -> Vec<PathBuf> {
+  // Relative paths are invalid and ignored
+  let data_home = std::env::var("XDG_DATA_HOME")
+    .ok()
+    .filter(|dir| dir.starts_with('/'))
+    .unwrap_or_else(|| format!("{}/.local/share", std::env::var("HOME").unwrap_or_default()));
+  let data_dirs = std::env::var("XDG_DATA_DIRS")
+    .ok()
+    .filter(|dirs| !dirs.is_empty())
+    .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+  let mut dirs = vec![PathBuf::from(data_home)];
+  for dir in data_dirs.split(':').filter(|dir| dir.starts_with('/')) {
+    let dir = PathBuf::from(dir);
+    if !dirs.contains(&dir) {
+      dirs.push(dir);
     }
   }
+  dirs
+}
 
-  // If the icon name is already an absolute path, check if it exists
-  if icon_name.starts_with('/') {
-    if std::path::Path::new(icon_name).exists() {
-      let result = Some(format!("file://{}", icon_name));
-      ICON_CACHE
-        .lock()
-        .unwrap()
-        .insert(icon_name.to_string(), result.clone());
-      return result;
-    }
+/// Directories holding `.desktop` files, most important first
+fn application_dirs() -> Vec<PathBuf> {
+  data_dirs()
+    .into_iter()
+    .map(|dir| dir.join("applications"))
+    .collect()
+}
+
+/// Get the desktop file ID of a file in an applications directory, e.g.
+/// `kde-okular.desktop` for `kde/okular.desktop`
+fn desktop_file_id(app_dir: &Path, path: &Path) -> Option<String> {
+  let parts = path
+    .strip_prefix(app_dir)
+    .ok()?
+    .components()
+    .map(|component| component.as_os_str().to_str())
+    .collect::<Option<Vec<_>>>()?;
+  Some(parts.join("-"))
+}
+
+/// Find a desktop file by its ID in the applications directories
+pub(crate) fn find_desktop_file(id: &str) -> Option<PathBuf> {
+  application_dirs()
+    .iter()
+    .find_map(|app_dir| find_desktop_file_in(app_dir, id))
+}
+
+/// Find a desktop file ID in a directory, where each `-` of the ID may also
+/// stand for a subdirectory
+fn find_desktop_file_in(dir: &Path, id: &str) -> Option<PathBuf> {
+  let path = dir.join(id);
+  if path.is_file() {
+    return Some(path);
   }
+  id.match_indices('-').find_map(|(i, _)| {
+    let subdir = dir.join(&id[..i]);
+    if subdir.is_dir() {
+      find_desktop_file_in(&subdir, &id[i + 1..])
+    } else {
+      None
+    }
+  })
+}
 
-  // Search through common icon paths
-  let paths = generate_icon_paths(icon_name);
-  for path in paths {
-    if std::path::Path::new(&path).exists() {
-      let result = Some(format!("file://{}", path));
-      ICON_CACHE
-        .lock()
-        .unwrap()
-        .insert(icon_name.to_string(), result.clone());
-      return result;
+/// Find the desktop files in the given directories
+///
+/// The first directory containing a desktop file ID wins, so a user's copy
+/// in `XDG_DATA_HOME` shadows the system one, even when it hides the app.
+fn find_desktop_files(app_dirs: &[PathBuf]) -> Vec<DesktopFile> {
+  let mut seen = HashSet::new();
+  let mut files = Vec::new();
+
+  for dir in app_dirs {
+    for entry in WalkDir::new(dir)
+      .follow_links(true)
+      .sort_by_file_name()
+      .into_iter()
+      .filter_map(|e| e.ok())
+    {
+      let path = entry.path();
+      let is_desktop_file = path.extension().is_some_and(|ext| ext == "desktop");
+      if !is_desktop_file || !entry.file_type().is_file() {
+        continue;
+      }
+      let (Some(id), Some(path_str)) = (desktop_file_id(dir, path), path.to_str()) else {
+        continue;
+      };
+      if !seen.insert(id.clone()) {
+        continue;
+      }
+
+      let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+      if let Some(modified) = modified {
+        files.push(DesktopFile {
+          id,
+          path: path_str.to_string(),
+          modified,
+        });
+      }
     }
   }
 
-  // Cache the negative result
-  ICON_CACHE
-    .lock()
-    .unwrap()
-    .insert(icon_name.to_string(), None);
-  None
+  files
+}
+
+/// Keep an `Icon` key if the icon exists, so it can be loaded over `icon://`
+fn existing_icon(icon_name: String) -> Option<String> {
+  lookup_icon(&icon_name, APP_ICON_SIZE).map(|_| icon_name)
 }
 
 /// Detect available terminal emulator
-fn detect_terminal_emulator() -> Option<String> {
+pub(crate) fn detect_terminal_emulator() -> Option<String> {
   // Check cache first
   {
     let cache = TERMINAL_EMULATOR.lock().unwrap();
@@ -407,22 +702,7 @@ fn detect_terminal_emulator() -> Option<String> {
     }
   }
 
-  // List of common terminal emulators in order of preference
-  let terminals = vec![
-    "wezterm",
-    "konsole",        // KDE
-    "gnome-terminal", // GNOME
-    "alacritty",      // Modern, GPU-accelerated
-    "kitty",          // Modern, GPU-accelerated
-    "terminator",     // Feature-rich
-    "tilix",          // Tiling terminal
-    "xfce4-terminal", // XFCE
-    "mate-terminal",  // MATE
-    "lxterminal",     // LXDE
-    "xterm",          // Fallback, always available
-  ];
-
-  for terminal in terminals {
+  for terminal in TERMINAL_EMULATORS {
     // Use which crate to find terminal in PATH (similar to exec.LookPath in Go)
     if which::which(terminal).is_ok() {
       let result = Some(terminal.to_string());
@@ -436,33 +716,303 @@ fn detect_terminal_emulator() -> Option<String> {
   None
 }
 
-fn parse_desktop_file(path: &std::path::Path) -> Result<Application, Box<dyn std::error::Error>> {
-  use freedesktop_desktop_entry::DesktopEntry;
+/// Check whether a `TryExec` program is installed
+fn is_program_available(program: &str) -> bool {
+  if program.starts_with('/') {
+    fs::metadata(program)
+      .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+  } else {
+    which::which(program).is_ok()
+  }
+}
 
-  let contents = std::fs::read_to_string(path)?;
-  let entry = DesktopEntry::decode(path, &contents)?;
+/// Check `OnlyShowIn` and `NotShowIn` against the running desktops
+fn is_shown_in(only_show_in: &[String], not_show_in: &[String], desktops: &[String]) -> bool {
+  let matches = |names: &[String]| {
+    desktops
+      .iter()
+      .any(|desktop| names.iter().any(|name| name.eq_ignore_ascii_case(desktop)))
+  };
 
-  // Skip if NoDisplay is true
-  if entry.no_display() {
-    return Err("Application should not be displayed".into());
-  }
+  (only_show_in.is_empty() || matches(only_show_in)) && !matches(not_show_in)
+}
 
-  let name = entry.name(None).unwrap_or_default().to_string();
-  let exec = entry.exec().unwrap_or_default().to_string();
-  let description = entry.comment(None).map(|s| s.to_string());
-  let terminal = entry.terminal();
+/// Turn a parsed `.desktop` file into an application
+fn parse_desktop_entry(
+  file: &KeyFile,
+  path: &Path,
+  id: String,
+  locales: &[String],
+  desktops: &[String],
+) -> Result<Application, String> {
+  let group = DESKTOP_ENTRY_GROUP;
+
+  if file.get(group, "Type") != Some("Application") {
+    return Err("Not an application".to_string());
+  }
+  // Hidden means the entry was deleted, NoDisplay that it isn't meant for menus
+  if file.boolean(group, "Hidden") || file.boolean(group, "NoDisplay") {
+    return Err("Application should not be displayed".to_string());
+  }
+  if !is_shown_in(
+    &file.list(group, "OnlyShowIn"),
+    &file.list(group, "NotShowIn"),
+    desktops,
+  ) {
+    return Err("Application is not shown in this desktop".to_string());
+  }
+  if let Some(program) = file.string(group, "TryExec") {
+    if !is_program_available(&program) {
+      return Err(format!("{} is not installed", program));
+    }
+  }
 
-  // Resolve icon path
-  let icon = entry
-    .icon()
-    .and_then(|icon_name| resolve_icon_path(icon_name));
+  let name = file
+    .locale_string(group, "Name", locales)
+    .ok_or("Missing Name")?;
+  let exec = file.string(group, "Exec").ok_or("Missing Exec")?;
+
+  // Actions without their own group or Exec can't be launched
+  let actions = file
+    .list(group, "Actions")
+    .into_iter()
+    .filter_map(|id| {
+      let action_group = format!("Desktop Action {}", id);
+      Some(ApplicationAction {
+        name: file.locale_string(&action_group, "Name", locales)?,
+        exec: file.string(&action_group, "Exec")?,
+        icon: file.locale_string(&action_group, "Icon", locales),
+        id,
+      })
+    })
+    .collect();
 
   Ok(Application {
+    id,
     name,
     exec,
-    icon,
-    description,
+    icon: file.locale_string(group, "Icon", locales),
+    description: file.locale_string(group, "Comment", locales),
     path: path.to_string_lossy().to_string(),
-    terminal,
+    terminal: file.boolean(group, "Terminal"),
+    generic_name: file.locale_string(group, "GenericName", locales),
+    keywords: file.locale_list(group, "Keywords", locales),
+    categories: file.list(group, "Categories"),
+    working_dir: file.string(group, "Path").filter(|dir| !dir.is_empty()),
+    startup_wm_class: file.string(group, "StartupWMClass"),
+    actions,
+  })
+}
+
+/// Get what a `.desktop` file opens, for entries that can be launched
+///
+/// Unlike listed applications, this includes `NoDisplay` entries, which
+/// are often meant only for opening files.
+fn parse_mime_handler(file: &KeyFile, id: &str, locales: &[String]) -> Option<MimeHandler> {
+  let group = DESKTOP_ENTRY_GROUP;
+  if file.get(group, "Type") != Some("Application") || file.boolean(group, "Hidden") {
+    return None;
+  }
+  file.get(group, "Exec")?;
+  if let Some(program) = file.string(group, "TryExec") {
+    if !is_program_available(&program) {
+      return None;
+    }
+  }
+
+  Some(MimeHandler {
+    id: id.to_string(),
+    name: file.locale_string(group, "Name", locales)?,
+    icon: file.locale_string(group, "Icon", locales),
+    mime_types: file.list(group, "MimeType"),
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  #[test]
+  fn test_is_shown_in() {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let desktops = names(&["ubuntu", "GNOME"]);
+
+    assert!(is_shown_in(&[], &[], &desktops));
+    assert!(is_shown_in(&names(&["GNOME"]), &[], &desktops));
+    assert!(!is_shown_in(&names(&["KDE"]), &[], &desktops));
+    assert!(!is_shown_in(&[], &names(&["gnome"]), &desktops));
+    assert!(!is_shown_in(&names(&["KDE"]), &[], &[]));
+  }
+
+  #[test]
+  fn test_parse_desktop_entry() {
+    let file = KeyFile::parse(
+      "[Desktop Entry]\n\
+       Type=Application\n\
+       Name=Firefox\n\
+       Name[de]=Firefox-Browser\n\
+       GenericName=Web Browser\n\
+       Comment=Browse the Web\n\
+       Exec=firefox %u\n\
+       Keywords=Internet;WWW;\n\
+       Categories=Network;WebBrowser;\n\
+       Path=/tmp\n\
+       StartupWMClass=firefox\n\
+       Actions=new-private-window;missing;\n\
+       \n\
+       [Desktop Action new-private-window]\n\
+       Name=New Private Window\n\
+       Name[de]=Neues privates Fenster\n\
+       Exec=firefox --private-window %u\n",
+    );
+    let path = Path::new("/usr/share/applications/firefox.desktop");
+    let locales = vec!["de".to_string()];
+
+    let app =
+      parse_desktop_entry(&file, path, "firefox.desktop".to_string(), &locales, &[]).unwrap();
+    assert_eq!(app.name, "Firefox-Browser");
+    assert_eq!(app.generic_name.as_deref(), Some("Web Browser"));
+    assert_eq!(app.description.as_deref(), Some("Browse the Web"));
+    assert_eq!(app.keywords, vec!["Internet", "WWW"]);
+    assert_eq!(app.categories, vec!["Network", "WebBrowser"]);
+    assert_eq!(app.working_dir.as_deref(), Some("/tmp"));
+    assert_eq!(app.startup_wm_class.as_deref(), Some("firefox"));
+    assert_eq!(app.actions.len(), 1);
+    assert_eq!(app.actions[0].id, "new-private-window");
+    assert_eq!(app.actions[0].name, "Neues privates Fenster");
+    assert_eq!(app.actions[0].exec, "firefox --private-window %u");
+
+    for hidden in [
+      "Hidden=true",
+      "OnlyShowIn=KDE;",
+      "TryExec=/nonexistent/firefox",
+      "Type=Link",
+    ] {
+      let file = KeyFile::parse(&format!(
+        "[Desktop Entry]\n{}\nType=Application\nName=Firefox\nExec=firefox\n",
+        hidden
+      ));
+      assert!(parse_desktop_entry(&file, path, String::new(), &locales, &[]).is_err());
+    }
+  }
+
+  #[test]
+  fn test_app_index() {
+    let root = TempDir::new("apps");
+    let user_dir = root.join("user/applications");
+    let system_dir = root.join("system/applications");
+    let app_dirs = [user_dir.clone(), system_dir.clone()];
+    let write_entry = |path: PathBuf, name: &str, extra: &str| {
+      let entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec=true\n{}\n",
+        name, extra
+      );
+      fs::write(path, entry).unwrap();
+    };
+    fs::create_dir_all(user_dir.join("kde")).unwrap();
+    fs::create_dir_all(&system_dir).unwrap();
+    write_entry(user_dir.join("editor.desktop"), "My Editor", "");
+    write_entry(user_dir.join("mail.desktop"), "Mail", "Hidden=true");
+    write_entry(user_dir.join("kde/okular.desktop"), "Okular", "");
+    write_entry(system_dir.join("editor.desktop"), "Editor", "");
+    write_entry(system_dir.join("mail.desktop"), "Mail", "");
+    write_entry(system_dir.join("files.desktop"), "Files", "");
+    write_entry(
+      system_dir.join("viewer.desktop"),
+      "Viewer",
+      "NoDisplay=true\nMimeType=image/png;image/jpeg;",
+    );
+
+    let mut index = AppIndex::new(Vec::new(), Vec::new(), String::new());
+    let parsed = index.update(&app_dirs);
+    let old = index.applications();
+    let handlers = index.handlers();
+    let unchanged = index.update(&app_dirs);
+
+    // Install an app, remove one and change one
+    write_entry(system_dir.join("music.desktop"), "Music", "");
+    fs::remove_file(system_dir.join("files.desktop")).unwrap();
+    let okular = user_dir.join("kde/okular.desktop");
+    write_entry(okular.clone(), "Okular", "Comment=Documents");
+    fs::File::options()
+      .write(true)
+      .open(&okular)
+      .and_then(|file| file.set_modified(SystemTime::UNIX_EPOCH))
+      .unwrap();
+    let changed = index.update(&app_dirs);
+    let found = find_desktop_file_in(&user_dir, "kde-okular.desktop");
+    // Missing directories are watched through their closest existing parent
+    let targets = [
+      watch_target(&user_dir),
+      watch_target(&root.join("flatpak/exports/share/applications")),
+    ];
+
+    // Shadowed system files aren't parsed
+    assert_eq!((parsed, unchanged, changed), (5, 0, 3));
+    assert_eq!(found, Some(okular));
+    assert_eq!(targets, [Some(user_dir.clone()), Some(root.to_path_buf())]);
+    let names = |apps: &[Application]| -> Vec<(String, String)> {
+      apps
+        .iter()
+        .map(|app| (app.id.clone(), app.name.clone()))
+        .collect()
+    };
+    assert_eq!(
+      names(&old),
+      vec![
+        ("files.desktop".to_string(), "Files".to_string()),
+        ("editor.desktop".to_string(), "My Editor".to_string()),
+        ("kde-okular.desktop".to_string(), "Okular".to_string()),
+      ]
+    );
+    // Unlisted entries can still open files, hidden ones can't
+    let handler_ids: Vec<&str> = handlers.iter().map(|handler| handler.id.as_str()).collect();
+    assert_eq!(
+      handler_ids,
+      vec![
+        "files.desktop",
+        "editor.desktop",
+        "kde-okular.desktop",
+        "viewer.desktop"
+      ]
+    );
+    assert_eq!(handlers[3].mime_types, vec!["image/png", "image/jpeg"]);
+
+    let changes = diff_applications(&old, &index.applications());
+    assert_eq!(
+      names(&changes.added),
+      vec![("music.desktop".to_string(), "Music".to_string())]
+    );
+    assert_eq!(
+      names(&changes.updated),
+      vec![("kde-okular.desktop".to_string(), "Okular".to_string())]
+    );
+    assert_eq!(changes.removed, vec!["files.desktop".to_string()]);
+  }
+
+  #[test]
+  fn test_lock_poisoned() {
+    let mutex = Mutex::new(1);
+    let result = std::panic::catch_unwind(|| {
+      let _guard = mutex.lock().unwrap();
+      panic!("poison the lock");
+    });
+    assert!(result.is_err() && mutex.is_poisoned());
+    assert_eq!(*lock(&mutex), 1);
+  }
+
+  #[test]
+  fn test_terminal_command() {
+    let argv = vec!["vim".to_string(), "it's here.txt".to_string()];
+    assert_eq!(
+      terminal_command("gnome-terminal", &argv).unwrap(),
+      vec!["gnome-terminal", "--", "vim", "it's here.txt"]
+    );
+    assert_eq!(
+      terminal_command("tilix", &argv).unwrap(),
+      vec!["tilix", "-e", r"'vim' 'it'\''s here.txt'"]
+    );
+    assert!(terminal_command("unknown", &argv).is_err());
+  }
+}
@@ -0,0 +1,107 @@
+//! Clipboard access through `xclip` and `wl-copy`/`wl-paste`, used when the
+//! native backend is not available. These tools write a single format, so
+//! only the first one is kept when several are written.
+
+use std::{
+  io::Write,
+  process::{Command, Stdio},
+};
+
+use super::{is_text_mime_type, mime_types_from_targets};
+
+/// Run a clipboard tool and return its output, `None` if it failed
+fn read_with(program: &str, args: &[&str]) -> Result<Option<Vec<u8>>, String> {
+  let output = Command::new(program).args(args).output().map_err(|e| {
+    format!(
+      "Failed to execute {}: {}. Make sure it is installed.",
+      program, e
+    )
+  })?;
+
+  if output.status.success() {
+    Ok(Some(output.stdout))
+  } else {
+    // Both tools fail when the clipboard is empty or lacks the format
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    eprintln!("[Clipboard] {} failed: {}", program, stderr.trim());
+    Ok(None)
+  }
+}
+
+/// Run a clipboard tool with the data on its stdin
+fn write_with(program: &str, args: &[&str], data: &[u8]) -> Result<(), String> {
+  let mut child = Command::new(program)
+    .args(args)
+    .stdin(Stdio::piped())
+    .spawn()
+    .map_err(|e| {
+      format!(
+        "Failed to execute {}: {}. Make sure it is installed.",
+        program, e
+      )
+    })?;
+
+  if let Some(mut stdin) = child.stdin.take() {
+    stdin
+      .write_all(data)
+      .map_err(|e| format!("Failed to write to {} stdin: {}", program, e))?;
+  }
+
+  let status = child
+    .wait()
+    .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("{} failed to write to clipboard", program))
+  }
+}
+
+/// List the MIME types the clipboard offers
+pub fn formats(wayland: bool) -> Result<Vec<String>, String> {
+  let output = if wayland {
+    read_with("wl-paste", &["--list-types"])?
+  } else {
+    read_with("xclip", &["-selection", "clipboard", "-t", "TARGETS", "-o"])?
+  };
+
+  let targets: Vec<String> = String::from_utf8_lossy(&output.unwrap_or_default())
+    .lines()
+    .map(|line| line.trim().to_string())
+    .collect();
+  Ok(mime_types_from_targets(&targets))
+}
+
+/// Read a format from the clipboard, `None` if it isn't offered
+pub fn read(wayland: bool, mime_type: &str) -> Result<Option<Vec<u8>>, String> {
+  match (wayland, is_text_mime_type(mime_type)) {
+    (true, true) => read_with("wl-paste", &["--no-newline"]),
+    (true, false) => read_with("wl-paste", &["--no-newline", "--type", mime_type]),
+    (false, true) => read_with("xclip", &["-selection", "clipboard", "-o"]),
+    (false, false) => read_with("xclip", &["-selection", "clipboard", "-t", mime_type, "-o"]),
+  }
+}
+
+/// Read the primary selection as text
+pub fn read_primary(wayland: bool) -> Result<Option<Vec<u8>>, String> {
+  if wayland {
+    read_with("wl-paste", &["--primary", "--no-newline"])
+  } else {
+    read_with("xclip", &["-selection", "primary", "-o"])
+  }
+}
+
+/// Write the first format to the clipboard
+pub fn write(wayland: bool, contents: &[(String, Vec<u8>)]) -> Result<(), String> {
+  let Some((mime_type, data)) = contents.first() else {
+    return Ok(());
+  };
+
+  match (wayland, is_text_mime_type(mime_type)) {
+    (true, true) => write_with("wl-copy", &[], data),
+    (true, false) => write_with("wl-copy", &["--type", mime_type], data),
+    (false, true) => write_with("xclip", &["-selection", "clipboard"], data),
+    (false, false) => write_with("xclip", &["-selection", "clipboard", "-t", mime_type], data),
+  }
+}
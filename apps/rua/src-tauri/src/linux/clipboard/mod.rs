@@ -0,0 +1,306 @@
+//! Clipboard Module
+//!
+//! Reads and writes the clipboard without external tools: on X11 rua owns the
+//! CLIPBOARD selection itself, on Wayland it uses the data-control protocol.
+//! `xclip` and `wl-copy`/`wl-paste` are only used when the native backend
+//! fails, e.g. on compositors without data-control.
+//!
+//! Content is addressed by MIME type. Besides text, there are helpers for
+//! PNG images and for file lists in the formats file managers paste.
+//!
+//! The PRIMARY selection (the highlighted text) is captured when the window is
+//! shown, so actions can work on it without the user copying it first.
+
+mod fallback;
+mod wayland;
+mod x11;
+
+use std::{
+  sync::{mpsc, Mutex},
+  thread,
+  time::Duration,
+};
+
+use tauri::Url;
+
+use super::display_server::detect_display_server;
+
+/// MIME type of UTF-8 text
+pub const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
+/// MIME type of images
+pub const PNG_MIME_TYPE: &str = "image/png";
+/// File lists, understood by most file managers
+pub const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+/// File lists as copied by GNOME Files and other GTK file managers
+pub const GNOME_FILES_MIME_TYPE: &str = "x-special/gnome-copied-files";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// How long to wait for the selection owner when the window is shown
+const SELECTION_TIMEOUT: Duration = Duration::from_millis(500);
+
+lazy_static::lazy_static! {
+    /// PRIMARY selection captured when the window was last shown
+    static ref CAPTURED_SELECTION: Mutex<Option<String>> = Mutex::new(None);
+    /// Why clipboard changes can't be watched, `None` while the watcher runs
+    static ref WATCH_ERROR: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Check whether a MIME type means plain text
+fn is_text_mime_type(mime_type: &str) -> bool {
+  mime_type == "text/plain" || mime_type.eq_ignore_ascii_case(TEXT_MIME_TYPE)
+}
+
+/// Turn X11 target names into MIME types, mapping the X11 text targets to
+/// `TEXT_MIME_TYPE` and dropping other non-MIME targets such as `TARGETS`
+fn mime_types_from_targets(targets: &[String]) -> Vec<String> {
+  let mut mime_types: Vec<String> = Vec::new();
+  for target in targets {
+    let mime_type = match target.as_str() {
+      "UTF8_STRING" | "STRING" | "TEXT" => TEXT_MIME_TYPE,
+      target if target.contains('/') => target,
+      _ => continue,
+    };
+    if !mime_types.iter().any(|m| m == mime_type) {
+      mime_types.push(mime_type.to_string());
+    }
+  }
+  mime_types
+}
+
+/// Clipboard formats for copying files: a URI list, GNOME's format and the
+/// paths as text
+fn file_list_contents(paths: &[String]) -> Result<Vec<(String, Vec<u8>)>, String> {
+  let uris = paths
+    .iter()
+    .map(|path| {
+      Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| format!("Not an absolute path: {}", path))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  Ok(vec![
+    (
+      URI_LIST_MIME_TYPE.to_string(),
+      format!("{}\r\n", uris.join("\r\n")).into_bytes(),
+    ),
+    (
+      GNOME_FILES_MIME_TYPE.to_string(),
+      format!("copy\n{}", uris.join("\n")).into_bytes(),
+    ),
+    (TEXT_MIME_TYPE.to_string(), paths.join("\n").into_bytes()),
+  ])
+}
+
+/// Get the local paths from a URI list, skipping comments and the
+/// `copy`/`cut` line of GNOME's format
+fn parse_uri_list(list: &str) -> Vec<String> {
+  list
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .filter_map(|line| Url::parse(line).ok()?.to_file_path().ok())
+    .map(|path| path.to_string_lossy().to_string())
+    .collect()
+}
+
+/// Run a clipboard operation with the native backend for the session, falling
+/// back to the command-line tools
+fn with_backend<T>(
+  x11: impl FnOnce() -> Result<T, String>,
+  wayland: impl FnOnce() -> Result<T, String>,
+  fallback: impl FnOnce(bool) -> Result<T, String>,
+) -> Result<T, String> {
+  let is_wayland = detect_display_server().is_wayland();
+  let result = if is_wayland { wayland() } else { x11() };
+
+  result.or_else(|e| {
+    eprintln!("[Clipboard] {}, falling back to command-line tool", e);
+    fallback(is_wayland)
+  })
+}
+
+fn read_format(mime_type: &str) -> Result<Option<Vec<u8>>, String> {
+  with_backend(
+    || x11::read(mime_type),
+    || wayland::read(mime_type),
+    |is_wayland| fallback::read(is_wayland, mime_type),
+  )
+}
+
+fn write_formats(contents: &[(String, Vec<u8>)]) -> Result<(), String> {
+  with_backend(
+    || x11::write(contents),
+    || wayland::write(contents),
+    |is_wayland| fallback::write(is_wayland, contents),
+  )
+}
+
+/// Call `on_change` from a background thread whenever the clipboard changes
+///
+/// Uses XFixes on X11 and data-control on Wayland. When neither is available
+/// (e.g. on GNOME), changes are not watched at all rather than polled through
+/// the command-line tools, and `clipboard_watch_error` tells why. `on_change`
+/// may also be called when the content didn't change.
+pub fn watch_clipboard(on_change: impl Fn() + Send + 'static) {
+  thread::spawn(move || {
+    let result = if detect_display_server().is_wayland() {
+      wayland::watch(&on_change)
+    } else {
+      x11::watch(&on_change)
+    };
+
+    if let Err(e) = result {
+      eprintln!("[Clipboard] Stopped watching clipboard changes: {}", e);
+      *WATCH_ERROR.lock().unwrap() = Some(e);
+    }
+  });
+}
+
+/// Why clipboard changes can't be watched, `None` while they are
+pub fn clipboard_watch_error() -> Option<String> {
+  WATCH_ERROR.lock().unwrap().clone()
+}
+
+/// Capture the PRIMARY selection when the window is shown
+///
+/// Returns the captured text, `None` if nothing is selected or the selection
+/// owner didn't answer in time.
+pub fn capture_selection() -> Option<String> {
+  let (result_tx, result_rx) = mpsc::channel();
+  thread::spawn(move || {
+    let result = with_backend(
+      x11::read_primary,
+      wayland::read_primary,
+      fallback::read_primary,
+    );
+    let _ = result_tx.send(result);
+  });
+
+  let selection = match result_rx.recv_timeout(SELECTION_TIMEOUT) {
+    Ok(Ok(data)) => data
+      .map(|data| String::from_utf8_lossy(&data).to_string())
+      .filter(|text| !text.trim().is_empty()),
+    Ok(Err(e)) => {
+      eprintln!("[Clipboard] Failed to read selection: {}", e);
+      None
+    }
+    Err(_) => {
+      eprintln!("[Clipboard] Timed out reading selection");
+      None
+    }
+  };
+
+  *CAPTURED_SELECTION.lock().unwrap() = selection.clone();
+  selection
+}
+
+/// Get the text that was selected when the window was last shown
+#[tauri::command]
+pub fn get_selected_text() -> Option<String> {
+  CAPTURED_SELECTION.lock().unwrap().clone()
+}
+
+/// Read text from the clipboard
+#[tauri::command]
+pub fn read_clipboard() -> Result<String, String> {
+  let data = read_format(TEXT_MIME_TYPE)?.unwrap_or_default();
+  Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// Write text to the clipboard
+#[tauri::command]
+pub fn write_clipboard(text: String) -> Result<(), String> {
+  write_formats(&[(TEXT_MIME_TYPE.to_string(), text.into_bytes())])
+}
+
+/// List the MIME types the clipboard content is available in
+#[tauri::command]
+pub fn get_clipboard_formats() -> Result<Vec<String>, String> {
+  with_backend(x11::formats, wayland::formats, fallback::formats)
+}
+
+/// Read the clipboard content in a MIME type, `null` if it isn't available
+#[tauri::command]
+pub fn read_clipboard_format(mime_type: String) -> Result<Option<Vec<u8>>, String> {
+  read_format(&mime_type)
+}
+
+/// Write content in a MIME type to the clipboard
+#[tauri::command]
+pub fn write_clipboard_format(mime_type: String, data: Vec<u8>) -> Result<(), String> {
+  write_formats(&[(mime_type, data)])
+}
+
+/// Read a PNG image from the clipboard, `null` if there is none
+#[tauri::command]
+pub fn read_clipboard_image() -> Result<Option<Vec<u8>>, String> {
+  read_format(PNG_MIME_TYPE)
+}
+
+/// Write a PNG image to the clipboard
+#[tauri::command]
+pub fn write_clipboard_image(data: Vec<u8>) -> Result<(), String> {
+  if !data.starts_with(PNG_SIGNATURE) {
+    return Err("Clipboard images must be PNG".to_string());
+  }
+  write_formats(&[(PNG_MIME_TYPE.to_string(), data)])
+}
+
+/// Read the files copied in a file manager
+#[tauri::command]
+pub fn read_clipboard_files() -> Result<Vec<String>, String> {
+  let list = match read_format(URI_LIST_MIME_TYPE)? {
+    Some(list) => list,
+    None => read_format(GNOME_FILES_MIME_TYPE)?.unwrap_or_default(),
+  };
+  Ok(parse_uri_list(&String::from_utf8_lossy(&list)))
+}
+
+/// Copy files so they can be pasted into a file manager
+#[tauri::command]
+pub fn write_clipboard_files(paths: Vec<String>) -> Result<(), String> {
+  if paths.is_empty() {
+    return Err("No files to copy".to_string());
+  }
+  write_formats(&file_list_contents(&paths)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mime_types_from_targets() {
+    let targets: Vec<String> = ["TARGETS", "UTF8_STRING", "STRING", "image/png", "TIMESTAMP"]
+      .iter()
+      .map(|t| t.to_string())
+      .collect();
+    assert_eq!(
+      mime_types_from_targets(&targets),
+      vec![TEXT_MIME_TYPE.to_string(), PNG_MIME_TYPE.to_string()]
+    );
+  }
+
+  #[test]
+  fn test_file_list_round_trip() {
+    let paths = vec!["/tmp/a b.txt".to_string(), "/home/me/文件".to_string()];
+    let contents = file_list_contents(&paths).unwrap();
+
+    assert_eq!(contents[0].0, URI_LIST_MIME_TYPE);
+    assert_eq!(
+      String::from_utf8_lossy(&contents[0].1),
+      "file:///tmp/a%20b.txt\r\nfile:///home/me/%E6%96%87%E4%BB%B6\r\n"
+    );
+    assert_eq!(
+      parse_uri_list(&String::from_utf8_lossy(&contents[0].1)),
+      paths
+    );
+    assert_eq!(
+      parse_uri_list(&String::from_utf8_lossy(&contents[1].1)),
+      paths
+    );
+    assert!(file_list_contents(&["relative".to_string()]).is_err());
+  }
+}
@@ -0,0 +1,314 @@
+//! Wayland clipboard backend
+//!
+//! Uses the wlr/ext data-control protocol, which lets clients without a
+//! focused surface access the clipboard. Copied data is served from a thread
+//! in the rua process until another client replaces the selection.
+//!
+//! Changes are watched through data-control selection events, preferring ext
+//! data-control and falling back to wlr; reading and writing go through
+//! wl-clipboard-rs, which does the same.
+
+use std::{io::Read, sync::mpsc, thread};
+
+use wayland_client::{
+  event_created_child,
+  globals::{registry_queue_init, GlobalListContents},
+  protocol::{wl_registry, wl_seat},
+  Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols::ext::data_control::v1::client::{
+  ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+  ext_data_control_manager_v1::{self, ExtDataControlManagerV1},
+  ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+  zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+  zwlr_data_control_manager_v1::{self, ZwlrDataControlManagerV1},
+  zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+use wl_clipboard_rs::{
+  copy::{self, MimeSource, Source},
+  paste::{self, get_contents, get_mime_types, ClipboardType, Seat},
+};
+
+use super::{is_text_mime_type, mime_types_from_targets};
+
+/// List the MIME types the clipboard offers
+pub fn formats() -> Result<Vec<String>, String> {
+  match get_mime_types(ClipboardType::Regular, Seat::Unspecified) {
+    Ok(mime_types) => {
+      let mut mime_types: Vec<String> = mime_types.into_iter().collect();
+      mime_types.sort();
+      Ok(mime_types_from_targets(&mime_types))
+    }
+    Err(paste::Error::ClipboardEmpty) => Ok(Vec::new()),
+    Err(e) => Err(format!("Failed to read Wayland clipboard: {}", e)),
+  }
+}
+
+/// Read a clipboard in a MIME type, `None` if it isn't offered
+fn read_contents(
+  clipboard: ClipboardType,
+  mime_type: paste::MimeType,
+) -> Result<Option<Vec<u8>>, String> {
+  let (mut pipe, _mime_type) = match get_contents(clipboard, Seat::Unspecified, mime_type) {
+    Ok(contents) => contents,
+    Err(paste::Error::ClipboardEmpty | paste::Error::NoMimeType) => return Ok(None),
+    Err(e) => return Err(format!("Failed to read Wayland clipboard: {}", e)),
+  };
+
+  let mut content = Vec::new();
+  pipe
+    .read_to_end(&mut content)
+    .map_err(|e| format!("Failed to read Wayland clipboard: {}", e))?;
+  Ok(Some(content))
+}
+
+/// Read a format from the clipboard, `None` if it isn't offered
+pub fn read(mime_type: &str) -> Result<Option<Vec<u8>>, String> {
+  let paste_mime_type = if is_text_mime_type(mime_type) {
+    paste::MimeType::Text
+  } else {
+    paste::MimeType::Specific(mime_type)
+  };
+  read_contents(ClipboardType::Regular, paste_mime_type)
+}
+
+/// Read the primary selection (the highlighted text) as text
+///
+/// Needs version 2 of wlr data-control, or ext data-control.
+pub fn read_primary() -> Result<Option<Vec<u8>>, String> {
+  read_contents(ClipboardType::Primary, paste::MimeType::Text)
+}
+
+/// Write formats to the clipboard, serving them until another client takes it
+pub fn write(contents: &[(String, Vec<u8>)]) -> Result<(), String> {
+  let sources: Vec<MimeSource> = contents
+    .iter()
+    .map(|(mime_type, data)| MimeSource {
+      source: Source::Bytes(data.clone().into_boxed_slice()),
+      // Text also offers the aliases other clients ask for
+      mime_type: if is_text_mime_type(mime_type) {
+        copy::MimeType::Text
+      } else {
+        copy::MimeType::Specific(mime_type.clone())
+      },
+    })
+    .collect();
+  let (result_tx, result_rx) = mpsc::channel();
+
+  // wl-clipboard-rs forks to serve in the background by default, serve from
+  // a thread instead so the data stays owned by this process
+  thread::spawn(move || {
+    let prepared = match copy::Options::new().prepare_copy_multi(sources) {
+      Ok(prepared) => {
+        let _ = result_tx.send(Ok(()));
+        prepared
+      }
+      Err(e) => {
+        let _ = result_tx.send(Err(format!("Failed to write Wayland clipboard: {}", e)));
+        return;
+      }
+    };
+
+    if let Err(e) = prepared.serve() {
+      eprintln!("[Clipboard] Failed to serve Wayland clipboard: {}", e);
+    }
+  });
+
+  result_rx
+    .recv()
+    .map_err(|e| format!("Failed to write Wayland clipboard: {}", e))?
+}
+
+/// Offer from either data-control protocol
+enum Offer {
+  Ext(ExtDataControlOfferV1),
+  Wlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+  fn destroy(&self) {
+    match self {
+      Offer::Ext(offer) => offer.destroy(),
+      Offer::Wlr(offer) => offer.destroy(),
+    }
+  }
+}
+
+/// State of the selection watcher
+#[derive(Default)]
+struct WatchState {
+  /// Offer of the current selection, destroyed when it is replaced
+  offer: Option<Offer>,
+  changed: bool,
+  finished: bool,
+}
+
+impl WatchState {
+  fn set_selection(&mut self, offer: Option<Offer>) {
+    if let Some(previous) = std::mem::replace(&mut self.offer, offer) {
+      previous.destroy();
+    }
+    self.changed = true;
+  }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &wl_registry::WlRegistry,
+    _: wl_registry::Event,
+    _: &GlobalListContents,
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &wl_seat::WlSeat,
+    _: wl_seat::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<ExtDataControlManagerV1, ()> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &ExtDataControlManagerV1,
+    _: ext_data_control_manager_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<ExtDataControlOfferV1, ()> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &ExtDataControlOfferV1,
+    _: ext_data_control_offer_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for WatchState {
+  fn event(
+    state: &mut Self,
+    _: &ExtDataControlDeviceV1,
+    event: ext_data_control_device_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+    match event {
+      ext_data_control_device_v1::Event::Selection { id } => {
+        state.set_selection(id.map(Offer::Ext))
+      }
+      ext_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => offer.destroy(),
+      ext_data_control_device_v1::Event::Finished => state.finished = true,
+      _ => {}
+    }
+  }
+
+  event_created_child!(WatchState, ExtDataControlDeviceV1, [
+    ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+  ]);
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &ZwlrDataControlManagerV1,
+    _: zwlr_data_control_manager_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for WatchState {
+  fn event(
+    _: &mut Self,
+    _: &ZwlrDataControlOfferV1,
+    _: zwlr_data_control_offer_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for WatchState {
+  fn event(
+    state: &mut Self,
+    _: &ZwlrDataControlDeviceV1,
+    event: zwlr_data_control_device_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+    match event {
+      zwlr_data_control_device_v1::Event::Selection { id } => {
+        state.set_selection(id.map(Offer::Wlr))
+      }
+      zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => offer.destroy(),
+      zwlr_data_control_device_v1::Event::Finished => state.finished = true,
+      _ => {}
+    }
+  }
+
+  event_created_child!(WatchState, ZwlrDataControlDeviceV1, [
+    zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+  ]);
+}
+
+/// Call `on_change` whenever the clipboard selection changes
+///
+/// Only returns on errors, e.g. when the compositor lacks data-control.
+pub fn watch(on_change: &dyn Fn()) -> Result<(), String> {
+  let conn = Connection::connect_to_env()
+    .map_err(|e| format!("Failed to connect to Wayland compositor: {}", e))?;
+  let (globals, mut queue) = registry_queue_init::<WatchState>(&conn)
+    .map_err(|e| format!("Failed to list Wayland globals: {}", e))?;
+  let qh = queue.handle();
+
+  let seat: wl_seat::WlSeat = globals
+    .bind(&qh, 1..=1, ())
+    .map_err(|e| format!("Failed to bind Wayland seat: {}", e))?;
+  if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+    manager.get_data_device(&seat, &qh, ());
+  } else {
+    let manager: ZwlrDataControlManagerV1 = globals.bind(&qh, 1..=2, ()).map_err(|e| {
+      format!(
+        "Compositor supports neither ext nor wlr data-control: {}",
+        e
+      )
+    })?;
+    manager.get_data_device(&seat, &qh, ());
+  }
+
+  let mut state = WatchState::default();
+  loop {
+    queue
+      .blocking_dispatch(&mut state)
+      .map_err(|e| format!("Wayland connection lost: {}", e))?;
+    if state.finished {
+      return Err("Wayland data-control device was destroyed".to_string());
+    }
+    if std::mem::take(&mut state.changed) {
+      on_change();
+    }
+  }
+}
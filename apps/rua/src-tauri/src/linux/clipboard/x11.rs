@@ -0,0 +1,659 @@
+//! X11 clipboard backend
+//!
+//! Writing makes rua the owner of the CLIPBOARD selection: a hidden window on
+//! a dedicated connection answers `SelectionRequest`s from a background thread
+//! for as long as rua runs, so the content survives without a helper process.
+//! Large transfers use the ICCCM `INCR` protocol in both directions.
+//!
+//! Formats are requested by MIME type, which X11 clients use as target atom
+//! names. Text is also offered and read as `UTF8_STRING`/`STRING`. The
+//! PRIMARY selection, i.e. the highlighted text, can be read as text.
+
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  thread,
+  time::{Duration, Instant},
+};
+
+use super::{is_text_mime_type, mime_types_from_targets};
+use x11rb::{
+  connection::{Connection, RequestConnection},
+  protocol::{
+    xfixes::{ConnectionExt as _, SelectionEventMask},
+    xproto::*,
+    Event,
+  },
+  rust_connection::RustConnection,
+  wrapper::ConnectionExt as _,
+  COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE,
+};
+
+/// How long to wait for the selection owner before giving up
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long an `INCR` requestor may take to ask for the next chunk
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+x11rb::atom_manager! {
+  Atoms: AtomsCookie {
+    CLIPBOARD,
+    TARGETS,
+    UTF8_STRING,
+    TEXT,
+    TEXT_PLAIN: b"text/plain",
+    TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+    INCR,
+    RUA_SELECTION,
+  }
+}
+
+lazy_static::lazy_static! {
+    /// Selection owner, started on the first write
+    static ref OWNER: Mutex<Option<Arc<Owner>>> = Mutex::new(None);
+}
+
+fn x11_error<E: std::fmt::Display>(action: &'static str) -> impl Fn(E) -> String {
+  move |e| format!("Failed to {}: {}", action, e)
+}
+
+/// Connect and create a hidden window to own or receive selections
+fn connect() -> Result<(RustConnection, Window, Atoms), String> {
+  let (conn, screen_num) =
+    RustConnection::connect(None).map_err(x11_error("connect to X11 display server"))?;
+  let atoms = Atoms::new(&conn)
+    .map_err(x11_error("intern atoms"))?
+    .reply()
+    .map_err(x11_error("intern atoms"))?;
+
+  let root = conn.setup().roots[screen_num].root;
+  let window = conn.generate_id().map_err(x11_error("create window"))?;
+  conn
+    .create_window(
+      COPY_DEPTH_FROM_PARENT,
+      window,
+      root,
+      0,
+      0,
+      1,
+      1,
+      0,
+      WindowClass::INPUT_ONLY,
+      COPY_FROM_PARENT,
+      &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+    )
+    .map_err(x11_error("create window"))?;
+  conn.flush().map_err(x11_error("flush"))?;
+
+  Ok((conn, window, atoms))
+}
+
+fn intern_atom(conn: &RustConnection, name: &str) -> Result<Atom, String> {
+  Ok(
+    conn
+      .intern_atom(false, name.as_bytes())
+      .map_err(x11_error("intern atom"))?
+      .reply()
+      .map_err(x11_error("intern atom"))?
+      .atom,
+  )
+}
+
+/// Content served while rua owns the selection
+struct Selection {
+  /// Formats by MIME type, in the order they were written
+  formats: Vec<(String, Arc<Vec<u8>>)>,
+  /// Type and data for each target atom, including the text aliases
+  targets: HashMap<Atom, (Atom, Arc<Vec<u8>>)>,
+}
+
+impl Selection {
+  fn new(
+    conn: &RustConnection,
+    atoms: &Atoms,
+    contents: &[(String, Vec<u8>)],
+  ) -> Result<Self, String> {
+    let mut formats = Vec::new();
+    let mut targets = HashMap::new();
+
+    for (mime_type, data) in contents {
+      let data = Arc::new(data.clone());
+      let target = intern_atom(conn, mime_type)?;
+      targets.insert(target, (target, data.clone()));
+      formats.push((mime_type.clone(), data));
+    }
+
+    // Offer text under the names older clients ask for, unless written explicitly
+    if let Some((_, text)) = formats
+      .iter()
+      .find(|(mime_type, _)| is_text_mime_type(mime_type))
+    {
+      for (target, type_) in [
+        (atoms.UTF8_STRING, atoms.UTF8_STRING),
+        (atoms.TEXT, atoms.UTF8_STRING),
+        (AtomEnum::STRING.into(), AtomEnum::STRING.into()),
+        (atoms.TEXT_PLAIN, atoms.TEXT_PLAIN),
+        (atoms.TEXT_PLAIN_UTF8, atoms.TEXT_PLAIN_UTF8),
+      ] {
+        targets
+          .entry(target)
+          .or_insert_with(|| (type_, text.clone()));
+      }
+    }
+
+    Ok(Self { formats, targets })
+  }
+
+  /// Data for a MIME type, matching any text format for text
+  fn get(&self, mime_type: &str) -> Option<Arc<Vec<u8>>> {
+    self
+      .formats
+      .iter()
+      .find(|(format, _)| format == mime_type)
+      .or_else(|| {
+        self
+          .formats
+          .iter()
+          .find(|(format, _)| is_text_mime_type(mime_type) && is_text_mime_type(format))
+      })
+      .map(|(_, data)| data.clone())
+  }
+}
+
+/// An `INCR` transfer to a requestor in progress
+struct Transfer {
+  type_: Atom,
+  data: Arc<Vec<u8>>,
+  offset: usize,
+  /// When the last chunk was sent
+  sent_at: Instant,
+}
+
+/// Owner of the CLIPBOARD selection
+struct Owner {
+  conn: RustConnection,
+  window: Window,
+  atoms: Atoms,
+  /// Current content, `None` once another client took the selection
+  selection: Mutex<Option<Arc<Selection>>>,
+}
+
+impl Owner {
+  /// Get the running owner, starting it if needed
+  fn get() -> Result<Arc<Owner>, String> {
+    let mut owner = OWNER.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(owner) = owner.as_ref() {
+      return Ok(owner.clone());
+    }
+
+    let (conn, window, atoms) = connect()?;
+    let new_owner = Arc::new(Owner {
+      conn,
+      window,
+      atoms,
+      selection: Mutex::new(None),
+    });
+
+    let serving = new_owner.clone();
+    thread::spawn(move || {
+      serving.serve();
+      if let Ok(mut owner) = OWNER.lock() {
+        *owner = None;
+      }
+    });
+
+    *owner = Some(new_owner.clone());
+    Ok(new_owner)
+  }
+
+  /// The running owner's content, if rua owns the selection
+  fn current_selection() -> Option<Arc<Selection>> {
+    let owner = OWNER.lock().ok()?.clone()?;
+    owner.selection()
+  }
+
+  fn selection(&self) -> Option<Arc<Selection>> {
+    self.selection.lock().ok()?.clone()
+  }
+
+  /// Drop the content unless the window owns the selection again
+  ///
+  /// A `SelectionClear` can be read after `write` took the selection back, so
+  /// the owner is checked under the lock `write` takes ownership with.
+  fn release_selection(&self) -> Result<(), String> {
+    let mut selection = self
+      .selection
+      .lock()
+      .map_err(|e| format!("Lock error: {}", e))?;
+    let owner = self
+      .conn
+      .get_selection_owner(self.atoms.CLIPBOARD)
+      .map_err(x11_error("check clipboard owner"))?
+      .reply()
+      .map_err(x11_error("check clipboard owner"))?
+      .owner;
+    if owner != self.window {
+      *selection = None;
+    }
+    Ok(())
+  }
+
+  /// Largest chunk sent in one property change
+  fn chunk_size(&self) -> usize {
+    self.conn.maximum_request_bytes() / 4
+  }
+
+  /// Answer selection requests until the connection breaks
+  fn serve(&self) {
+    let mut transfers: HashMap<(Window, Atom), Transfer> = HashMap::new();
+
+    loop {
+      let event = match self.conn.wait_for_event() {
+        Ok(event) => event,
+        Err(e) => {
+          eprintln!("[Clipboard] X11 connection lost: {}", e);
+          return;
+        }
+      };
+
+      let result = match event {
+        Event::SelectionRequest(request) => self.handle_request(request, &mut transfers),
+        Event::SelectionClear(event) if event.selection == self.atoms.CLIPBOARD => {
+          self.release_selection()
+        }
+        Event::PropertyNotify(event) if event.state == Property::DELETE => {
+          self.continue_transfer(event.window, event.atom, &mut transfers)
+        }
+        Event::DestroyNotify(event) => {
+          transfers.retain(|(requestor, _), _| *requestor != event.window);
+          Ok(())
+        }
+        _ => Ok(()),
+      };
+      // Forget requestors that stopped reading without destroying their window
+      transfers.retain(|_, transfer| transfer.sent_at.elapsed() < TRANSFER_TIMEOUT);
+
+      if let Err(e) = result.and_then(|_| self.conn.flush().map_err(x11_error("flush"))) {
+        eprintln!("[Clipboard] {}", e);
+      }
+    }
+  }
+
+  fn handle_request(
+    &self,
+    request: SelectionRequestEvent,
+    transfers: &mut HashMap<(Window, Atom), Transfer>,
+  ) -> Result<(), String> {
+    // Obsolete clients leave the property empty and expect the target to be used
+    let property = if request.property == NONE {
+      request.target
+    } else {
+      request.property
+    };
+
+    let selection = self
+      .selection()
+      .filter(|_| request.selection == self.atoms.CLIPBOARD);
+    let reply_property = match selection {
+      Some(selection) if request.target == self.atoms.TARGETS => {
+        let mut targets = vec![self.atoms.TARGETS];
+        targets.extend(selection.targets.keys());
+        self
+          .conn
+          .change_property32(
+            PropMode::REPLACE,
+            request.requestor,
+            property,
+            AtomEnum::ATOM,
+            &targets,
+          )
+          .map_err(x11_error("send targets"))?;
+        property
+      }
+      Some(selection) => match selection.targets.get(&request.target) {
+        Some((type_, data)) => {
+          self.send_data(request.requestor, property, *type_, data.clone(), transfers)?;
+          property
+        }
+        None => NONE,
+      },
+      None => NONE,
+    };
+
+    let notify = SelectionNotifyEvent {
+      response_type: SELECTION_NOTIFY_EVENT,
+      sequence: 0,
+      time: request.time,
+      requestor: request.requestor,
+      selection: request.selection,
+      target: request.target,
+      property: reply_property,
+    };
+    self
+      .conn
+      .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+      .map_err(x11_error("notify requestor"))?;
+    Ok(())
+  }
+
+  /// Store the data on the requestor's property, or start an `INCR` transfer
+  fn send_data(
+    &self,
+    requestor: Window,
+    property: Atom,
+    type_: Atom,
+    data: Arc<Vec<u8>>,
+    transfers: &mut HashMap<(Window, Atom), Transfer>,
+  ) -> Result<(), String> {
+    if data.len() <= self.chunk_size() {
+      self
+        .conn
+        .change_property8(PropMode::REPLACE, requestor, property, type_, &data[..])
+        .map_err(x11_error("send selection"))?;
+      return Ok(());
+    }
+
+    // The requestor deletes the property after reading each chunk
+    self
+      .conn
+      .change_window_attributes(
+        requestor,
+        &ChangeWindowAttributesAux::new()
+          .event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY),
+      )
+      .map_err(x11_error("watch requestor"))?;
+    self
+      .conn
+      .change_property32(
+        PropMode::REPLACE,
+        requestor,
+        property,
+        self.atoms.INCR,
+        &[data.len() as u32],
+      )
+      .map_err(x11_error("start incremental transfer"))?;
+    transfers.insert(
+      (requestor, property),
+      Transfer {
+        type_,
+        data,
+        offset: 0,
+        sent_at: Instant::now(),
+      },
+    );
+    Ok(())
+  }
+
+  /// Send the next chunk of an `INCR` transfer, ending with an empty one
+  fn continue_transfer(
+    &self,
+    requestor: Window,
+    property: Atom,
+    transfers: &mut HashMap<(Window, Atom), Transfer>,
+  ) -> Result<(), String> {
+    let Some(transfer) = transfers.get_mut(&(requestor, property)) else {
+      return Ok(());
+    };
+
+    let end = (transfer.offset + self.chunk_size()).min(transfer.data.len());
+    let chunk = &transfer.data[transfer.offset..end];
+    self
+      .conn
+      .change_property8(
+        PropMode::REPLACE,
+        requestor,
+        property,
+        transfer.type_,
+        chunk,
+      )
+      .map_err(x11_error("send selection chunk"))?;
+
+    if chunk.is_empty() {
+      transfers.remove(&(requestor, property));
+    } else {
+      transfer.offset = end;
+      transfer.sent_at = Instant::now();
+    }
+    Ok(())
+  }
+}
+
+/// Write formats to the clipboard, owning the selection until another client takes it
+pub fn write(contents: &[(String, Vec<u8>)]) -> Result<(), String> {
+  let owner = Owner::get()?;
+  let selection = Selection::new(&owner.conn, &owner.atoms, contents)?;
+  let mut current_selection = owner
+    .selection
+    .lock()
+    .map_err(|e| format!("Lock error: {}", e))?;
+  *current_selection = Some(Arc::new(selection));
+
+  owner
+    .conn
+    .set_selection_owner(owner.window, owner.atoms.CLIPBOARD, CURRENT_TIME)
+    .map_err(x11_error("take clipboard ownership"))?;
+  let current = owner
+    .conn
+    .get_selection_owner(owner.atoms.CLIPBOARD)
+    .map_err(x11_error("check clipboard owner"))?
+    .reply()
+    .map_err(x11_error("check clipboard owner"))?
+    .owner;
+
+  if current != owner.window {
+    *current_selection = None;
+    return Err("Failed to take clipboard ownership".to_string());
+  }
+  Ok(())
+}
+
+fn clipboard_atom(atoms: &Atoms) -> Atom {
+  atoms.CLIPBOARD
+}
+
+/// Wait for the next event on a connection, up to `READ_TIMEOUT`
+fn wait_for_event(conn: &RustConnection) -> Result<Event, String> {
+  let deadline = Instant::now() + READ_TIMEOUT;
+  loop {
+    if let Some(event) = conn.poll_for_event().map_err(x11_error("read event"))? {
+      return Ok(event);
+    }
+    if Instant::now() >= deadline {
+      return Err("Timed out waiting for the clipboard owner".to_string());
+    }
+    thread::sleep(Duration::from_millis(5));
+  }
+}
+
+/// Read the whole property, deleting it
+fn take_property(
+  conn: &RustConnection,
+  window: Window,
+  property: Atom,
+) -> Result<GetPropertyReply, String> {
+  conn
+    .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+    .map_err(x11_error("read selection"))?
+    .reply()
+    .map_err(x11_error("read selection"))
+}
+
+/// Convert a selection to `target`, `None` if the owner refuses
+fn convert_selection(
+  conn: &RustConnection,
+  window: Window,
+  atoms: &Atoms,
+  selection: Atom,
+  target: Atom,
+) -> Result<Option<Vec<u8>>, String> {
+  conn
+    .convert_selection(window, selection, target, atoms.RUA_SELECTION, CURRENT_TIME)
+    .map_err(x11_error("request selection"))?;
+  conn.flush().map_err(x11_error("flush"))?;
+
+  let notify = loop {
+    if let Event::SelectionNotify(notify) = wait_for_event(conn)? {
+      break notify;
+    }
+  };
+  if notify.property == NONE {
+    return Ok(None);
+  }
+
+  let reply = take_property(conn, window, atoms.RUA_SELECTION)?;
+  if reply.type_ != atoms.INCR {
+    return Ok(Some(reply.value));
+  }
+
+  // Deleting the INCR property asks the owner for the first chunk
+  let mut data = Vec::new();
+  loop {
+    match wait_for_event(conn)? {
+      Event::PropertyNotify(event)
+        if event.atom == atoms.RUA_SELECTION && event.state == Property::NEW_VALUE =>
+      {
+        let chunk = take_property(conn, window, atoms.RUA_SELECTION)?;
+        if chunk.value.is_empty() {
+          return Ok(Some(data));
+        }
+        data.extend_from_slice(&chunk.value);
+      }
+      _ => continue,
+    }
+  }
+}
+
+/// Read a selection as UTF-8, falling back to Latin-1 `STRING`
+fn read_selection_text(
+  conn: &RustConnection,
+  window: Window,
+  atoms: &Atoms,
+  selection: Atom,
+) -> Result<Option<Vec<u8>>, String> {
+  if let Some(data) = convert_selection(conn, window, atoms, selection, atoms.UTF8_STRING)? {
+    return Ok(Some(data));
+  }
+
+  let data = convert_selection(conn, window, atoms, selection, AtomEnum::STRING.into())?;
+  Ok(data.map(|data| {
+    data
+      .iter()
+      .map(|&b| b as char)
+      .collect::<String>()
+      .into_bytes()
+  }))
+}
+
+/// Connect to read a selection, `None` if nobody owns it
+fn with_reader<T>(
+  selection: impl FnOnce(&Atoms) -> Atom,
+  read: impl FnOnce(&RustConnection, Window, &Atoms, Atom) -> Result<T, String>,
+) -> Result<Option<T>, String> {
+  let (conn, window, atoms) = connect()?;
+  let selection = selection(&atoms);
+  let selection_owner = conn
+    .get_selection_owner(selection)
+    .map_err(x11_error("check selection owner"))?
+    .reply()
+    .map_err(x11_error("check selection owner"))?
+    .owner;
+
+  let result = if selection_owner == NONE {
+    Ok(None)
+  } else {
+    read(&conn, window, &atoms, selection).map(Some)
+  };
+  let _ = conn.destroy_window(window);
+  let _ = conn.flush();
+
+  result
+}
+
+/// List the MIME types the clipboard offers
+pub fn formats() -> Result<Vec<String>, String> {
+  // Answer from memory while rua owns the selection
+  if let Some(selection) = Owner::current_selection() {
+    return Ok(
+      selection
+        .formats
+        .iter()
+        .map(|(mime_type, _)| mime_type.clone())
+        .collect(),
+    );
+  }
+
+  let formats = with_reader(clipboard_atom, |conn, window, atoms, selection| {
+    let Some(data) = convert_selection(conn, window, atoms, selection, atoms.TARGETS)? else {
+      return Ok(Vec::new());
+    };
+
+    let cookies: Vec<_> = data
+      .chunks_exact(4)
+      .map(|atom| u32::from_ne_bytes([atom[0], atom[1], atom[2], atom[3]]))
+      .map(|atom| conn.get_atom_name(atom))
+      .collect::<Result<_, _>>()
+      .map_err(x11_error("read target names"))?;
+    let names: Vec<String> = cookies
+      .into_iter()
+      .filter_map(|cookie| cookie.reply().ok())
+      .map(|reply| String::from_utf8_lossy(&reply.name).to_string())
+      .collect();
+
+    Ok(mime_types_from_targets(&names))
+  })?;
+
+  Ok(formats.unwrap_or_default())
+}
+
+/// Read a format from the clipboard, `None` if it isn't offered
+pub fn read(mime_type: &str) -> Result<Option<Vec<u8>>, String> {
+  if let Some(selection) = Owner::current_selection() {
+    return Ok(selection.get(mime_type).map(|data| data.to_vec()));
+  }
+
+  let data = with_reader(clipboard_atom, |conn, window, atoms, selection| {
+    if is_text_mime_type(mime_type) {
+      return read_selection_text(conn, window, atoms, selection);
+    }
+    let target = intern_atom(conn, mime_type)?;
+    convert_selection(conn, window, atoms, selection, target)
+  })?;
+
+  Ok(data.flatten())
+}
+
+/// Read the PRIMARY selection (the highlighted text) as text
+pub fn read_primary() -> Result<Option<Vec<u8>>, String> {
+  let data = with_reader(|_| AtomEnum::PRIMARY.into(), read_selection_text)?;
+  Ok(data.flatten())
+}
+
+/// Call `on_change` whenever the clipboard owner changes, using XFixes
+///
+/// Only returns on errors, e.g. when the server lacks XFixes.
+pub fn watch(on_change: &dyn Fn()) -> Result<(), String> {
+  let (conn, window, atoms) = connect()?;
+  conn
+    .xfixes_query_version(5, 0)
+    .map_err(x11_error("query XFixes"))?
+    .reply()
+    .map_err(x11_error("query XFixes"))?;
+  conn
+    .xfixes_select_selection_input(
+      window,
+      atoms.CLIPBOARD,
+      SelectionEventMask::SET_SELECTION_OWNER
+        | SelectionEventMask::SELECTION_WINDOW_DESTROY
+        | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+    )
+    .map_err(x11_error("watch clipboard"))?;
+  conn.flush().map_err(x11_error("flush"))?;
+
+  loop {
+    if let Event::XfixesSelectionNotify(_) =
+      conn.wait_for_event().map_err(x11_error("read event"))?
+    {
+      on_change();
+    }
+  }
+}
@@ -1,9 +1,31 @@
+use std::thread;
+
 use anyhow::bail;
 use tauri::{Emitter, WebviewWindow};
 
-use crate::linux::{display_server, hyprland, x11_window};
+use crate::{
+  linux::{capture_selection, display_server, get_selected_text, hyprland, x11_window},
+  types::WindowShownPayload,
+};
+
+/// Emit `rua://window-shown` once the PRIMARY selection is read
+///
+/// The selection owner may be rua's own webview, which can't answer while the
+/// window is being shown, so the selection is read on another thread. The one
+/// from when the window was shown is kept if it was already visible.
+fn emit_window_shown(window: WebviewWindow, mut payload: WindowShownPayload, was_visible: bool) {
+  thread::spawn(move || {
+    payload.selection = if was_visible {
+      get_selected_text()
+    } else {
+      capture_selection()
+    };
+    let _ = window.emit("rua://window-shown", payload);
+  });
+}
 
-pub fn show_window(window: WebviewWindow) -> anyhow::Result<String> {
+pub fn show_window(window: WebviewWindow, payload: WindowShownPayload) -> anyhow::Result<String> {
+  let was_visible = window.is_visible().unwrap_or(false);
   let display_server_type = display_server::detect_display_server();
 
   match display_server_type {
@@ -35,6 +57,9 @@ pub fn show_window(window: WebviewWindow) -> anyhow::Result<String> {
         eprintln!("[X11] Failed to connect, falling back to Tauri API");
       }
     }
+    display_server::DisplayServer::Wayland => {
+      eprintln!("[DisplayServer] Wayland compositor, using Tauri API");
+    }
     display_server::DisplayServer::Unknown => {
       eprintln!("[DisplayServer] Unknown display server, using Tauri API");
     }
@@ -52,7 +77,7 @@ pub fn show_window(window: WebviewWindow) -> anyhow::Result<String> {
   }
 
   // Emit window-shown event
-  let _ = window.emit("rua://window-shown", ());
+  emit_window_shown(window, payload, was_visible);
   Ok("Window shown".to_string())
 }
 
@@ -94,7 +119,7 @@ pub fn hide_window(window: WebviewWindow) -> anyhow::Result<String> {
             eprintln!("[Hyprland] Failed to focus window: {}", e);
           }
           // Emit window-shown event (window is now visible on current workspace)
-          let _ = window.emit("rua://window-shown", ());
+          emit_window_shown(window, WindowShownPayload::default(), false);
           return Ok("Window moved to current workspace".to_string());
         }
         Err(e) => {
@@ -116,6 +141,9 @@ pub fn hide_window(window: WebviewWindow) -> anyhow::Result<String> {
         }
       }
     }
+    display_server::DisplayServer::Wayland => {
+      eprintln!("[DisplayServer] Wayland compositor, using Tauri API");
+    }
     display_server::DisplayServer::Unknown => {
       eprintln!("[DisplayServer] Unknown display server, using Tauri API");
     }
@@ -0,0 +1,486 @@
+//! Desktop Entry Module
+//!
+//! Parses the key file format of the Desktop Entry spec, which `.desktop`
+//! files share with icon theme `index.theme` files and `mimeapps.list`.
+//! Values are unescaped, lists split and localized keys matched against the
+//! user's locale as the spec describes. Exec keys are split into arguments
+//! and their field codes expanded, so commands run without a shell.
+//! See: https://specifications.freedesktop.org/desktop-entry-spec/latest/
+
+use std::{collections::HashMap, fs, path::Path};
+
+use tauri::Url;
+
+/// Group holding the entry itself in a `.desktop` file
+pub const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+
+/// Groups of key-value pairs read from a key file
+#[derive(Debug, Default)]
+pub struct KeyFile {
+  groups: HashMap<String, HashMap<String, String>>,
+}
+
+impl KeyFile {
+  /// Parse key file contents, skipping comments and malformed lines
+  pub fn parse(contents: &str) -> Self {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut group: Option<&mut HashMap<String, String>> = None;
+
+    for line in contents.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        group = Some(groups.entry(name.to_string()).or_default());
+        continue;
+      }
+
+      if let (Some(group), Some((key, value))) = (group.as_mut(), line.split_once('=')) {
+        // The first occurrence of a key wins
+        group
+          .entry(key.trim_end().to_string())
+          .or_insert_with(|| value.trim_start().to_string());
+      }
+    }
+
+    Self { groups }
+  }
+
+  /// Read and parse a key file
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let contents =
+      fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Self::parse(&contents))
+  }
+
+  /// Raw value of a key, without unescaping
+  pub fn get(&self, group: &str, key: &str) -> Option<&str> {
+    self.groups.get(group)?.get(key).map(String::as_str)
+  }
+
+  /// Value of a string key
+  pub fn string(&self, group: &str, key: &str) -> Option<String> {
+    self.get(group, key).map(unescape)
+  }
+
+  /// Value of a boolean key, `false` if it is missing
+  pub fn boolean(&self, group: &str, key: &str) -> bool {
+    self.get(group, key) == Some("true")
+  }
+
+  /// Value of a list key, empty if it is missing
+  pub fn list(&self, group: &str, key: &str) -> Vec<String> {
+    self.get(group, key).map(split_list).unwrap_or_default()
+  }
+
+  /// Raw value of a localized key for the first locale that has one
+  fn get_localized(&self, group: &str, key: &str, locales: &[String]) -> Option<&str> {
+    locales
+      .iter()
+      .find_map(|locale| self.get(group, &format!("{}[{}]", key, locale)))
+      .or_else(|| self.get(group, key))
+  }
+
+  /// Value of a localized string key, see `current_locales`
+  pub fn locale_string(&self, group: &str, key: &str, locales: &[String]) -> Option<String> {
+    self.get_localized(group, key, locales).map(unescape)
+  }
+
+  /// Value of a localized list key, see `current_locales`
+  pub fn locale_list(&self, group: &str, key: &str, locales: &[String]) -> Vec<String> {
+    self
+      .get_localized(group, key, locales)
+      .map(split_list)
+      .unwrap_or_default()
+  }
+}
+
+/// Resolve the `\s`, `\n`, `\t`, `\r` and `\\` escapes of a value
+fn unescape(value: &str) -> String {
+  let mut result = String::with_capacity(value.len());
+  let mut chars = value.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      result.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('s') => result.push(' '),
+      Some('n') => result.push('\n'),
+      Some('t') => result.push('\t'),
+      Some('r') => result.push('\r'),
+      Some('\\') => result.push('\\'),
+      Some(other) => {
+        result.push('\\');
+        result.push(other);
+      }
+      None => result.push('\\'),
+    }
+  }
+  result
+}
+
+/// Split a `;`-separated list, where `\;` is a literal semicolon
+fn split_list(value: &str) -> Vec<String> {
+  let mut items = Vec::new();
+  let mut item = String::new();
+  let mut chars = value.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some(';') => item.push(';'),
+        Some(next) => {
+          item.push('\\');
+          item.push(next);
+        }
+        None => item.push('\\'),
+      },
+      ';' => items.push(std::mem::take(&mut item)),
+      c => item.push(c),
+    }
+  }
+  items.push(item);
+
+  items
+    .iter()
+    .filter(|item| !item.is_empty())
+    .map(|item| unescape(item))
+    .collect()
+}
+
+/// Locale names to look up localized keys with, most specific first
+///
+/// `lang_COUNTRY.ENCODING@MODIFIER` gives `lang_COUNTRY@MODIFIER`,
+/// `lang_COUNTRY`, `lang@MODIFIER` and `lang`.
+fn locale_variants(locale: &str) -> Vec<String> {
+  let (locale, modifier) = match locale.split_once('@') {
+    Some((locale, modifier)) => (locale, Some(modifier)),
+    None => (locale, None),
+  };
+  let locale = locale.split('.').next().unwrap_or_default();
+  if locale.is_empty() || locale == "C" || locale == "POSIX" {
+    return Vec::new();
+  }
+  let (lang, country) = match locale.split_once('_') {
+    Some((lang, country)) => (lang, Some(country)),
+    None => (locale, None),
+  };
+
+  let mut variants = Vec::new();
+  if let (Some(country), Some(modifier)) = (country, modifier) {
+    variants.push(format!("{}_{}@{}", lang, country, modifier));
+  }
+  if let Some(country) = country {
+    variants.push(format!("{}_{}", lang, country));
+  }
+  if let Some(modifier) = modifier {
+    variants.push(format!("{}@{}", lang, modifier));
+  }
+  variants.push(lang.to_string());
+  variants
+}
+
+/// Locale names for localized keys, from the locale used for messages
+pub fn current_locales() -> Vec<String> {
+  ["LC_ALL", "LC_MESSAGES", "LANG"]
+    .iter()
+    .filter_map(|name| std::env::var(name).ok())
+    .find(|value| !value.is_empty())
+    .map(|locale| locale_variants(&locale))
+    .unwrap_or_default()
+}
+
+/// Names of the running desktop environment from `XDG_CURRENT_DESKTOP`
+pub fn current_desktops() -> Vec<String> {
+  std::env::var("XDG_CURRENT_DESKTOP")
+    .unwrap_or_default()
+    .split(':')
+    .filter(|name| !name.is_empty())
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Split an Exec value into arguments
+///
+/// Arguments are separated by spaces and may be double-quoted, with `"`,
+/// `` ` ``, `$` and `\` escaped by a backslash inside quotes. Like GLib,
+/// single quotes and backslash escapes outside quotes are accepted too.
+pub fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+  let unterminated = || format!("Unterminated quote in Exec: {}", exec);
+  let mut args = Vec::new();
+  // `None` between arguments, so `""` still gives an empty argument
+  let mut arg: Option<String> = None;
+  let mut chars = exec.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      ' ' | '\t' | '\n' => args.extend(arg.take()),
+      '"' => {
+        let arg = arg.get_or_insert_with(String::new);
+        loop {
+          match chars.next().ok_or_else(unterminated)? {
+            '"' => break,
+            '\\' => match chars.next().ok_or_else(unterminated)? {
+              c @ ('"' | '`' | '$' | '\\') => arg.push(c),
+              c => {
+                arg.push('\\');
+                arg.push(c);
+              }
+            },
+            c => arg.push(c),
+          }
+        }
+      }
+      '\'' => {
+        let arg = arg.get_or_insert_with(String::new);
+        loop {
+          match chars.next().ok_or_else(unterminated)? {
+            '\'' => break,
+            c => arg.push(c),
+          }
+        }
+      }
+      '\\' => arg
+        .get_or_insert_with(String::new)
+        .push(chars.next().unwrap_or('\\')),
+      c => arg.get_or_insert_with(String::new).push(c),
+    }
+  }
+  args.extend(arg);
+
+  if args.is_empty() {
+    return Err("Exec is empty".to_string());
+  }
+  Ok(args)
+}
+
+/// Values for the field codes of an Exec key
+#[derive(Debug, Default)]
+pub struct ExecContext<'a> {
+  /// Files or URLs to open, for `%f`, `%F`, `%u` and `%U`
+  pub uris: &'a [String],
+  /// Icon key, for `%i`
+  pub icon: Option<&'a str>,
+  /// Translated name, for `%c`
+  pub name: Option<&'a str>,
+  /// Location of the desktop file, for `%k`
+  pub desktop_file: Option<&'a str>,
+}
+
+/// Get the local path of a file given as path or `file://` URL
+fn local_path(uri: &str) -> Option<String> {
+  if uri.starts_with("file:") {
+    let path = Url::parse(uri).ok()?.to_file_path().ok()?;
+    return Some(path.to_string_lossy().to_string());
+  }
+  (!uri.contains("://")).then(|| uri.to_string())
+}
+
+/// Expand the field codes in one argument, for files or URLs given as `uris`
+///
+/// Arguments that are only a field code without value are removed.
+fn expand_arg(arg: &str, uris: &[String], context: &ExecContext) -> Vec<String> {
+  let files = || uris.iter().filter_map(|uri| local_path(uri));
+  match arg {
+    "%F" => return files().collect(),
+    "%U" => return uris.to_vec(),
+    "%i" => {
+      return context
+        .icon
+        .map(|icon| vec!["--icon".to_string(), icon.to_string()])
+        .unwrap_or_default()
+    }
+    "%f" | "%u" | "%c" | "%k" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {
+      let value = match arg {
+        "%f" => files().next(),
+        "%u" => uris.first().cloned(),
+        "%c" => context.name.map(str::to_string),
+        "%k" => context.desktop_file.map(str::to_string),
+        // Deprecated field codes are removed
+        _ => None,
+      };
+      return value.into_iter().collect();
+    }
+    _ => {}
+  }
+
+  // Field codes inside an argument expand to a single value
+  let mut expanded = String::new();
+  let mut chars = arg.chars();
+  while let Some(c) = chars.next() {
+    if c != '%' {
+      expanded.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('%') => expanded.push('%'),
+      Some('f' | 'F') => expanded.extend(files().next()),
+      Some('u' | 'U') => expanded.extend(uris.first().cloned()),
+      Some('c') => expanded.push_str(context.name.unwrap_or_default()),
+      Some('k') => expanded.push_str(context.desktop_file.unwrap_or_default()),
+      Some('i') => expanded.push_str(context.icon.unwrap_or_default()),
+      _ => {}
+    }
+  }
+  vec![expanded]
+}
+
+/// Expand the field codes of Exec arguments into the command lines to run
+///
+/// An Exec key taking a single file or URL (`%f`, `%u`) is run once for each
+/// of them, one taking a list (`%F`, `%U`) once for all of them.
+pub fn expand_exec(args: &[String], context: &ExecContext) -> Vec<Vec<String>> {
+  let has_code = |codes: &[&str]| {
+    args
+      .iter()
+      .any(|arg| codes.iter().any(|code| arg.contains(code)))
+  };
+  let instances: Vec<&[String]> = if has_code(&["%F", "%U"]) || context.uris.len() < 2 {
+    vec![context.uris]
+  } else if has_code(&["%f", "%u"]) {
+    context.uris.chunks(1).collect()
+  } else {
+    vec![&[]]
+  };
+
+  instances
+    .into_iter()
+    .map(|uris| {
+      args
+        .iter()
+        .flat_map(|arg| expand_arg(arg, uris, context))
+        .collect()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_key_file() {
+    let file = KeyFile::parse(
+      "# Comment\n\
+       [Desktop Entry]\n\
+       Name = Files\n\
+       Name[de]=Dateien\n\
+       Name=Ignored\n\
+       Comment=Line one\\nLine\\stwo\\\\\n\
+       Keywords=folder;manager;semi\\;colon;\n\
+       Keywords[de]=Ordner;\n\
+       Terminal=true\n\
+       \n\
+       [Desktop Action new-window]\n\
+       Name=New Window\n",
+    );
+
+    let locales = vec!["de_DE".to_string(), "de".to_string()];
+    assert_eq!(
+      file.string(DESKTOP_ENTRY_GROUP, "Name").as_deref(),
+      Some("Files")
+    );
+    assert_eq!(
+      file
+        .locale_string(DESKTOP_ENTRY_GROUP, "Name", &locales)
+        .as_deref(),
+      Some("Dateien")
+    );
+    assert_eq!(
+      file.string(DESKTOP_ENTRY_GROUP, "Comment").as_deref(),
+      Some("Line one\nLine two\\")
+    );
+    assert_eq!(
+      file.list(DESKTOP_ENTRY_GROUP, "Keywords"),
+      vec!["folder", "manager", "semi;colon"]
+    );
+    assert_eq!(
+      file.locale_list(DESKTOP_ENTRY_GROUP, "Keywords", &locales),
+      vec!["Ordner"]
+    );
+    assert!(file.boolean(DESKTOP_ENTRY_GROUP, "Terminal"));
+    assert!(!file.boolean(DESKTOP_ENTRY_GROUP, "NoDisplay"));
+    assert_eq!(
+      file.string("Desktop Action new-window", "Name").as_deref(),
+      Some("New Window")
+    );
+  }
+
+  #[test]
+  fn test_locale_variants() {
+    assert_eq!(
+      locale_variants("sr_RS.UTF-8@latin"),
+      vec!["sr_RS@latin", "sr_RS", "sr@latin", "sr"]
+    );
+    assert_eq!(locale_variants("de_DE.UTF-8"), vec!["de_DE", "de"]);
+    assert_eq!(locale_variants("fr"), vec!["fr"]);
+    assert!(locale_variants("C.UTF-8").is_empty());
+  }
+
+  #[test]
+  fn test_split_exec() {
+    let args = |exec: &str| split_exec(exec).unwrap();
+    assert_eq!(args("firefox %u"), vec!["firefox", "%u"]);
+    assert_eq!(
+      args(r#""/opt/My App/app" --title "say \"hi\" for \$5" ''"#),
+      vec!["/opt/My App/app", "--title", "say \"hi\" for $5", ""]
+    );
+    assert_eq!(
+      args("sh -c 'echo  hi'  a\\ b"),
+      vec!["sh", "-c", "echo  hi", "a b"]
+    );
+    assert!(split_exec(r#"app "unterminated"#).is_err());
+    assert!(split_exec("  ").is_err());
+  }
+
+  #[test]
+  fn test_expand_exec() {
+    let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    let uris = args(&["file:///tmp/a%20b.txt", "https://example.com", "/tmp/c"]);
+    let context = ExecContext {
+      uris: &uris,
+      icon: Some("editor"),
+      name: Some("Editor"),
+      desktop_file: Some("/usr/share/applications/editor.desktop"),
+    };
+
+    assert_eq!(
+      expand_exec(
+        &args(&["editor", "%F", "%i", "--name=%c", "100%%"]),
+        &context
+      ),
+      vec![args(&[
+        "editor",
+        "/tmp/a b.txt",
+        "/tmp/c",
+        "--icon",
+        "editor",
+        "--name=Editor",
+        "100%"
+      ])]
+    );
+    assert_eq!(
+      expand_exec(&args(&["browser", "%u"]), &context),
+      vec![
+        args(&["browser", "file:///tmp/a%20b.txt"]),
+        args(&["browser", "https://example.com"]),
+        args(&["browser", "/tmp/c"]),
+      ]
+    );
+    assert_eq!(
+      expand_exec(&args(&["viewer", "%f", "%k"]), &ExecContext::default()),
+      vec![args(&["viewer"])]
+    );
+    assert_eq!(
+      expand_exec(&args(&["app", "%U"]), &context),
+      vec![args(&[
+        "app",
+        "file:///tmp/a%20b.txt",
+        "https://example.com",
+        "/tmp/c"
+      ])]
+    );
+  }
+}
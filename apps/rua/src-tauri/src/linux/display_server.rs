@@ -1,10 +1,12 @@
-use std::{env, process::Command};
+use std::{env, process::Command, sync::OnceLock};
 
 /// 显示服务器类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DisplayServer {
   /// Hyprland (Wayland compositor with special features)
   Hyprland,
+  /// Other Wayland compositor
+  Wayland,
   /// Generic X11 display server
   X11,
   /// Unknown or unsupported display server
@@ -16,10 +18,16 @@ impl DisplayServer {
   pub fn as_str(&self) -> &'static str {
     match self {
       DisplayServer::Hyprland => "Hyprland",
+      DisplayServer::Wayland => "Wayland",
       DisplayServer::X11 => "X11",
       DisplayServer::Unknown => "Unknown",
     }
   }
+
+  /// Whether this is a Wayland session
+  pub fn is_wayland(&self) -> bool {
+    matches!(self, DisplayServer::Hyprland | DisplayServer::Wayland)
+  }
 }
 
 /// 检测当前显示服务器
@@ -27,9 +35,17 @@ impl DisplayServer {
 /// 检测顺序：
 /// 1. 检查 HYPRLAND_INSTANCE_SIGNATURE 环境变量（Hyprland 特有）
 /// 2. 尝试执行 hyprctl version 命令
-/// 3. 检查 DISPLAY 环境变量（X11）
-/// 4. 都失败则返回 Unknown
+/// 3. 检查 WAYLAND_DISPLAY 环境变量（其他 Wayland 合成器）
+/// 4. 检查 DISPLAY 环境变量（X11）
+/// 5. 都失败则返回 Unknown
+///
+/// 结果在首次检测后缓存，之后的调用不会再执行 hyprctl
 pub fn detect_display_server() -> DisplayServer {
+  static DETECTED: OnceLock<DisplayServer> = OnceLock::new();
+  *DETECTED.get_or_init(detect)
+}
+
+fn detect() -> DisplayServer {
   // 1. 优先检测 Hyprland
   if env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
     eprintln!("[DisplayServer] Detected Hyprland via HYPRLAND_INSTANCE_SIGNATURE");
@@ -41,7 +57,18 @@ pub fn detect_display_server() -> DisplayServer {
     return DisplayServer::Hyprland;
   }
 
-  // 2. 检测 X11
+  // 2. 检测其他 Wayland 合成器（XWayland 下 DISPLAY 也会被设置，所以先于 X11）
+  if let Ok(display) = env::var("WAYLAND_DISPLAY") {
+    if !display.is_empty() {
+      eprintln!(
+        "[DisplayServer] Detected Wayland via WAYLAND_DISPLAY={}",
+        display
+      );
+      return DisplayServer::Wayland;
+    }
+  }
+
+  // 3. 检测 X11
   if let Ok(display) = env::var("DISPLAY") {
     if !display.is_empty() {
       eprintln!("[DisplayServer] Detected X11 via DISPLAY={}", display);
@@ -49,7 +76,7 @@ pub fn detect_display_server() -> DisplayServer {
     }
   }
 
-  // 3. 未知显示服务器
+  // 4. 未知显示服务器
   eprintln!("[DisplayServer] Unknown display server, will fallback to Tauri API");
   DisplayServer::Unknown
 }
@@ -0,0 +1,224 @@
+//! Icon Protocol Module
+//!
+//! Serves icons to the webview over `icon://`, so it doesn't need access to
+//! the filesystem through the asset protocol. The path is a theme icon name
+//! or an absolute image path, `size` the wanted size in pixels:
+//!
+//! `icon://localhost/firefox?size=48`
+//!
+//! Names are resolved through the user's icon theme and SVGs rasterized to
+//! PNG at the requested size. Paths are only served if an installed desktop
+//! entry uses them or they are in an icon directory.
+
+use std::{
+  collections::hash_map::DefaultHasher,
+  fs,
+  hash::{Hash, Hasher},
+  path::Path,
+  time::SystemTime,
+};
+
+use resvg::{tiny_skia, usvg};
+use tauri::{
+  http::{Request, Response, Uri},
+  UriSchemeContext, UriSchemeResponder, Wry,
+};
+
+use super::{
+  applications::is_application_icon,
+  icon_theme::{icon_dirs, lookup_icon, APP_ICON_SIZE},
+};
+
+/// Largest size an icon can be requested at
+const MAX_ICON_SIZE: u32 = 512;
+
+/// How long the webview may use an icon before checking it again
+const CACHE_CONTROL: &str = "max-age=86400";
+
+/// Content type of an image file the webview can show, from its extension
+fn image_content_type(path: &Path) -> Option<&'static str> {
+  match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+    "png" => Some("image/png"),
+    "svg" => Some("image/svg+xml"),
+    "jpg" | "jpeg" => Some("image/jpeg"),
+    "ico" => Some("image/x-icon"),
+    "webp" => Some("image/webp"),
+    _ => None,
+  }
+}
+
+/// Check whether an absolute icon path may be served
+fn is_allowed_icon_path(path: &str) -> bool {
+  if is_application_icon(path) {
+    return true;
+  }
+  // Resolve `..` and symlinks, they must not lead out of the icon directories
+  let Ok(path) = fs::canonicalize(path) else {
+    return false;
+  };
+  icon_dirs()
+    .iter()
+    .filter_map(|dir| fs::canonicalize(dir).ok())
+    .any(|dir| path.starts_with(dir))
+}
+
+/// Get the icon name or path and the size of an `icon://` URL
+fn parse_icon_uri(uri: &Uri) -> Option<(String, u32)> {
+  let name = urlencoding::decode(uri.path().trim_start_matches('/')).ok()?;
+  let size = match uri
+    .query()
+    .unwrap_or_default()
+    .split('&')
+    .find_map(|pair| pair.strip_prefix("size="))
+  {
+    Some(size) => size.parse().ok()?,
+    None => APP_ICON_SIZE,
+  };
+
+  (!name.is_empty() && (1..=MAX_ICON_SIZE).contains(&size)).then(|| (name.to_string(), size))
+}
+
+/// Render an SVG to a square PNG, centered and scaled to fit
+fn rasterize_svg(data: &[u8], size: u32) -> Result<Vec<u8>, String> {
+  let tree = usvg::Tree::from_data(data, &usvg::Options::default())
+    .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+  let mut pixmap =
+    tiny_skia::Pixmap::new(size, size).ok_or_else(|| format!("Invalid icon size: {}", size))?;
+
+  let svg_size = tree.size();
+  let scale = size as f32 / svg_size.width().max(svg_size.height());
+  let transform = tiny_skia::Transform::from_scale(scale, scale).post_translate(
+    (size as f32 - svg_size.width() * scale) / 2.0,
+    (size as f32 - svg_size.height() * scale) / 2.0,
+  );
+  resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+  pixmap
+    .encode_png()
+    .map_err(|e| format!("Failed to encode PNG: {}", e))
+}
+
+/// Tag identifying an icon file at a size, changing when the file does
+fn icon_etag(path: &Path, size: u32) -> String {
+  let mut hasher = DefaultHasher::new();
+  path.hash(&mut hasher);
+  let modified = fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+    .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+    .map(|duration| duration.as_secs())
+    .unwrap_or_default();
+  format!("\"{:x}-{}-{}\"", hasher.finish(), modified, size)
+}
+
+fn error_response(status: u16, message: &str) -> Response<Vec<u8>> {
+  Response::builder()
+    .status(status)
+    .header("Content-Type", "text/plain")
+    .body(message.as_bytes().to_vec())
+    .unwrap()
+}
+
+/// Build the response to an `icon://` request
+fn icon_response(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+  let Some((name, size)) = parse_icon_uri(request.uri()) else {
+    return error_response(
+      400,
+      "Expected icon://localhost/<name or path>?size=<pixels>",
+    );
+  };
+  // Paths are limited to application icons, this must not expose other files
+  if name.starts_with('/') {
+    if image_content_type(Path::new(&name)).is_none() {
+      return error_response(403, "Not an image");
+    }
+    if !is_allowed_icon_path(&name) {
+      return error_response(403, "Not an application icon");
+    }
+  }
+  let Some(path) = lookup_icon(&name, size) else {
+    return error_response(404, &format!("Icon not found: {}", name));
+  };
+  let Some(content_type) = image_content_type(&path) else {
+    return error_response(415, &format!("Unsupported icon format: {}", path.display()));
+  };
+
+  let etag = icon_etag(&path, size);
+  let cached = request
+    .headers()
+    .get("If-None-Match")
+    .is_some_and(|value| value.as_bytes() == etag.as_bytes());
+  if cached {
+    return Response::builder()
+      .status(304)
+      .header("ETag", etag)
+      .body(Vec::new())
+      .unwrap();
+  }
+
+  let icon = fs::read(&path)
+    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    .and_then(|data| match content_type {
+      "image/svg+xml" => Ok((rasterize_svg(&data, size)?, "image/png")),
+      _ => Ok((data, content_type)),
+    });
+  match icon {
+    Ok((data, content_type)) => Response::builder()
+      .status(200)
+      .header("Content-Type", content_type)
+      .header("Cache-Control", CACHE_CONTROL)
+      .header("ETag", etag)
+      .body(data)
+      .unwrap(),
+    Err(e) => {
+      eprintln!("[icon://] {}", e);
+      error_response(500, &e)
+    }
+  }
+}
+
+/// Handle the `icon://` protocol
+pub fn handle_icon_protocol(
+  _ctx: UriSchemeContext<'_, Wry>,
+  request: Request<Vec<u8>>,
+  responder: UriSchemeResponder,
+) {
+  // Lookups and rasterizing take a moment, keep them off the webview's thread
+  tauri::async_runtime::spawn_blocking(move || responder.respond(icon_response(&request)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_icon_uri() {
+    let parse = |uri: &str| parse_icon_uri(&uri.parse().unwrap());
+    assert_eq!(
+      parse("icon://localhost/firefox?size=32"),
+      Some(("firefox".to_string(), 32))
+    );
+    assert_eq!(
+      parse("icon://localhost/%2Fopt%2FMy%20App%2Ficon.svg"),
+      Some(("/opt/My App/icon.svg".to_string(), APP_ICON_SIZE))
+    );
+    assert_eq!(parse("icon://localhost/firefox?size=4096"), None);
+    assert_eq!(parse("icon://localhost/firefox?size=big"), None);
+    assert_eq!(parse("icon://localhost/"), None);
+  }
+
+  #[test]
+  fn test_rasterize_svg() {
+    let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="8">
+      <rect width="16" height="8" fill="red"/>
+    </svg>"#;
+    let png = rasterize_svg(svg, 64).unwrap();
+    let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+
+    assert_eq!((pixmap.width(), pixmap.height()), (64, 64));
+    // Scaled to the full width and centered vertically
+    assert_eq!(pixmap.pixel(32, 32).unwrap().red(), 255);
+    assert_eq!(pixmap.pixel(32, 4).unwrap().alpha(), 0);
+    assert!(rasterize_svg(b"not svg", 64).is_err());
+  }
+}
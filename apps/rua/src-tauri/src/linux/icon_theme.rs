@@ -0,0 +1,581 @@
+//! Icon Theme Module
+//!
+//! Looks up icons as the Icon Theme spec describes: the user's theme is read
+//! from the desktop's settings, its `index.theme` lists the icon directories
+//! and the sizes they hold, and lookups fall back through the `Inherits` chain
+//! down to hicolor, picking the closest size when none matches exactly.
+//!
+//! Resolved icons are cached on disk per theme and size until a directory of
+//! the themes changes.
+//! See: https://specifications.freedesktop.org/icon-theme-spec/latest/
+
+use std::{
+  collections::{HashMap, HashSet},
+  ffi::OsString,
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+  sync::Mutex,
+  time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::applications::{data_dirs, get_cache_path};
+use super::desktop_entry::{current_desktops, KeyFile};
+
+/// Theme every theme falls back to
+const FALLBACK_THEME: &str = "hicolor";
+
+/// Group holding the theme description in `index.theme`
+const ICON_THEME_GROUP: &str = "Icon Theme";
+
+/// Icon file extensions in order of preference, without XPM as webviews
+/// can't show it
+const ICON_EXTENSIONS: &[&str] = &["png", "svg"];
+
+/// Size application icons are looked up at
+pub const APP_ICON_SIZE: u32 = 48;
+
+lazy_static::lazy_static! {
+    /// Theme chain and lookups of the current session, loaded on first use
+    static ref ICON_LOOKUP: Mutex<Option<IconLookup>> = Mutex::new(None);
+}
+
+/// How a theme directory matches sizes, from its `Type` key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeType {
+  Fixed,
+  Scalable,
+  Threshold,
+}
+
+/// A subdirectory of an icon theme, as described in `index.theme`
+#[derive(Debug, Clone)]
+struct ThemeDir {
+  path: String,
+  size: u32,
+  scale: u32,
+  min_size: u32,
+  max_size: u32,
+  threshold: u32,
+  size_type: SizeType,
+}
+
+impl ThemeDir {
+  /// Read a directory's group, `None` if it has no valid `Size`
+  fn from_index(index: &KeyFile, path: &str) -> Option<Self> {
+    let number = |key: &str| index.get(path, key)?.trim().parse::<u32>().ok();
+    let size = number("Size")?;
+    let size_type = match index.get(path, "Type") {
+      Some("Fixed") => SizeType::Fixed,
+      Some("Scalable") => SizeType::Scalable,
+      _ => SizeType::Threshold,
+    };
+
+    Some(Self {
+      path: path.to_string(),
+      size,
+      scale: number("Scale").unwrap_or(1),
+      min_size: number("MinSize").unwrap_or(size),
+      max_size: number("MaxSize").unwrap_or(size),
+      threshold: number("Threshold").unwrap_or(2),
+      size_type,
+    })
+  }
+
+  /// Whether the directory's icons are meant for a size
+  fn matches_size(&self, size: u32, scale: u32) -> bool {
+    if self.scale != scale {
+      return false;
+    }
+    match self.size_type {
+      SizeType::Fixed => self.size == size,
+      SizeType::Scalable => (self.min_size..=self.max_size).contains(&size),
+      SizeType::Threshold => self.size.abs_diff(size) <= self.threshold,
+    }
+  }
+
+  /// How far the directory's icons are from a size, in scaled pixels
+  fn size_distance(&self, size: u32, scale: u32) -> u32 {
+    let target = size * scale;
+    let (min, max) = match self.size_type {
+      SizeType::Fixed => (self.size, self.size),
+      SizeType::Scalable => (self.min_size, self.max_size),
+      SizeType::Threshold => (
+        self.size.saturating_sub(self.threshold),
+        self.size + self.threshold,
+      ),
+    };
+    (min * self.scale).saturating_sub(target) + target.saturating_sub(max * self.scale)
+  }
+}
+
+/// An installed icon theme
+#[derive(Debug)]
+struct IconTheme {
+  /// The theme's directory in each base directory, e.g. `/usr/share/icons/Adwaita`
+  roots: Vec<PathBuf>,
+  dirs: Vec<ThemeDir>,
+  inherits: Vec<String>,
+}
+
+impl IconTheme {
+  /// Load a theme by name, `None` if no base directory has its `index.theme`
+  fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+    let roots: Vec<PathBuf> = base_dirs
+      .iter()
+      .map(|base_dir| base_dir.join(name))
+      .filter(|root| root.is_dir())
+      .collect();
+    let index = roots
+      .iter()
+      .find_map(|root| KeyFile::load(&root.join("index.theme")).ok())?;
+
+    let mut dir_names = comma_list(&index, "Directories");
+    dir_names.extend(comma_list(&index, "ScaledDirectories"));
+    Some(Self {
+      roots,
+      dirs: dir_names
+        .iter()
+        .filter_map(|dir| ThemeDir::from_index(&index, dir))
+        .collect(),
+      inherits: comma_list(&index, "Inherits"),
+    })
+  }
+}
+
+/// Split a comma-separated list of the theme description
+fn comma_list(index: &KeyFile, key: &str) -> Vec<String> {
+  index
+    .get(ICON_THEME_GROUP, key)
+    .unwrap_or_default()
+    .split(',')
+    .map(str::trim)
+    .filter(|item| !item.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// Load a theme followed by the themes it inherits from, depth first, ending
+/// with hicolor
+fn theme_chain(name: &str, base_dirs: &[PathBuf]) -> Vec<IconTheme> {
+  fn add_theme(
+    name: &str,
+    base_dirs: &[PathBuf],
+    themes: &mut Vec<IconTheme>,
+    seen: &mut HashSet<String>,
+  ) {
+    if !seen.insert(name.to_string()) {
+      return;
+    }
+    let Some(theme) = IconTheme::load(name, base_dirs) else {
+      return;
+    };
+    let inherits = theme.inherits.clone();
+    themes.push(theme);
+    for parent in &inherits {
+      add_theme(parent, base_dirs, themes, seen);
+    }
+  }
+
+  let mut themes = Vec::new();
+  let mut seen = HashSet::new();
+  add_theme(name, base_dirs, &mut themes, &mut seen);
+  add_theme(FALLBACK_THEME, base_dirs, &mut themes, &mut seen);
+  themes
+}
+
+/// File names of the directories searched so far, so each is only read once
+#[derive(Debug, Default)]
+struct DirContents(HashMap<PathBuf, HashSet<OsString>>);
+
+impl DirContents {
+  /// Get the path of a file if it exists in a directory
+  fn find(&mut self, dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let names = self.0.entry(dir.to_path_buf()).or_insert_with(|| {
+      fs::read_dir(dir)
+        .map(|entries| {
+          entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name())
+            .collect()
+        })
+        .unwrap_or_default()
+    });
+    names
+      .contains(OsString::from(file_name).as_os_str())
+      .then(|| dir.join(file_name))
+  }
+}
+
+/// Look up an icon in one theme, the closest size if none matches exactly
+fn lookup_in_theme(
+  theme: &IconTheme,
+  files: &mut DirContents,
+  name: &str,
+  size: u32,
+  scale: u32,
+) -> Option<PathBuf> {
+  let mut closest: Option<(u32, PathBuf)> = None;
+  for dir in &theme.dirs {
+    for root in &theme.roots {
+      for ext in ICON_EXTENSIONS {
+        let Some(path) = files.find(&root.join(&dir.path), &format!("{}.{}", name, ext)) else {
+          continue;
+        };
+        if dir.matches_size(size, scale) {
+          return Some(path);
+        }
+        let distance = dir.size_distance(size, scale);
+        if closest.as_ref().is_none_or(|(min, _)| distance < *min) {
+          closest = Some((distance, path));
+        }
+      }
+    }
+  }
+  closest.map(|(_, path)| path)
+}
+
+/// Look up an icon through a theme chain, then directly in the fallback
+/// directories
+fn find_icon(
+  themes: &[IconTheme],
+  fallback_dirs: &[PathBuf],
+  files: &mut DirContents,
+  name: &str,
+  size: u32,
+) -> Option<PathBuf> {
+  // Icon keys should omit the extension, but some include it anyway
+  let stem = ICON_EXTENSIONS
+    .iter()
+    .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+    .unwrap_or(name);
+
+  themes
+    .iter()
+    .find_map(|theme| lookup_in_theme(theme, files, stem, size, 1))
+    .or_else(|| {
+      fallback_dirs.iter().find_map(|dir| {
+        ICON_EXTENSIONS
+          .iter()
+          .find_map(|ext| files.find(dir, &format!("{}.{}", stem, ext)))
+          .or_else(|| (stem != name).then(|| files.find(dir, name)).flatten())
+      })
+    })
+}
+
+/// Lookups persisted between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IconCache {
+  theme: String,
+  /// Latest modification time of the theme directories
+  timestamp: u64,
+  /// Resolved icons by `<name>@<size>`, `None` if the icon wasn't found
+  icons: HashMap<String, Option<PathBuf>>,
+}
+
+/// The user's theme chain with the lookups made in it
+struct IconLookup {
+  themes: Vec<IconTheme>,
+  fallback_dirs: Vec<PathBuf>,
+  files: DirContents,
+  cache: IconCache,
+  /// Whether the cache has lookups that aren't saved yet
+  changed: bool,
+}
+
+impl IconLookup {
+  /// Load the user's theme chain and the cached lookups, if still valid
+  fn load() -> Self {
+    let theme = current_icon_theme();
+    let themes = theme_chain(&theme, &icon_base_dirs());
+    let fallback_dirs = icon_dirs();
+
+    let timestamp = latest_mtime(&themes, &fallback_dirs);
+    let cache = load_icon_cache()
+      .filter(|cache| cache.theme == theme && cache.timestamp == timestamp)
+      .unwrap_or_else(|| {
+        eprintln!("[Icons] Using icon theme {}", theme);
+        IconCache {
+          theme,
+          timestamp,
+          icons: HashMap::new(),
+        }
+      });
+
+    Self {
+      themes,
+      fallback_dirs,
+      files: DirContents::default(),
+      cache,
+      changed: false,
+    }
+  }
+
+  fn find(&mut self, name: &str, size: u32) -> Option<PathBuf> {
+    let key = format!("{}@{}", name, size);
+    if let Some(cached) = self.cache.icons.get(&key) {
+      return cached.clone();
+    }
+
+    let path = find_icon(
+      &self.themes,
+      &self.fallback_dirs,
+      &mut self.files,
+      name,
+      size,
+    );
+    self.cache.icons.insert(key, path.clone());
+    self.changed = true;
+    path
+  }
+}
+
+/// Directories themes are installed in, most important first
+fn icon_base_dirs() -> Vec<PathBuf> {
+  let home = std::env::var("HOME").unwrap_or_default();
+  let mut dirs = vec![PathBuf::from(home).join(".icons")];
+  dirs.extend(data_dirs().into_iter().map(|dir| dir.join("icons")));
+  dirs
+}
+
+/// Directories icon files are installed in: the theme directories, then the
+/// `pixmaps` directories
+pub(crate) fn icon_dirs() -> Vec<PathBuf> {
+  let mut dirs = icon_base_dirs();
+  dirs.extend(data_dirs().into_iter().map(|dir| dir.join("pixmaps")));
+  dirs
+}
+
+/// Latest modification time of the directories icons are looked up in
+///
+/// Installing an icon changes the directory it's put into, so this changes
+/// whenever a lookup could give a different result.
+fn latest_mtime(themes: &[IconTheme], fallback_dirs: &[PathBuf]) -> u64 {
+  let theme_dirs = themes.iter().flat_map(|theme| {
+    theme.roots.iter().flat_map(|root| {
+      std::iter::once(root.clone()).chain(theme.dirs.iter().map(|dir| root.join(&dir.path)))
+    })
+  });
+
+  theme_dirs
+    .chain(fallback_dirs.iter().cloned())
+    .filter_map(|dir| fs::metadata(dir).ok()?.modified().ok())
+    .filter_map(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+    .map(|duration| duration.as_secs())
+    .max()
+    .unwrap_or(0)
+}
+
+fn icon_cache_path() -> PathBuf {
+  get_cache_path().with_file_name("icons.json")
+}
+
+fn load_icon_cache() -> Option<IconCache> {
+  let content = fs::read_to_string(icon_cache_path()).ok()?;
+  serde_json::from_str(&content).ok()
+}
+
+/// Get the user's XDG config directory
+pub(crate) fn config_home() -> PathBuf {
+  std::env::var("XDG_CONFIG_HOME")
+    .ok()
+    .filter(|dir| dir.starts_with('/'))
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".config"))
+}
+
+/// Icon theme set in GNOME's settings
+fn gsettings_icon_theme() -> Option<String> {
+  let output = Command::new("gsettings")
+    .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())?;
+  let theme = String::from_utf8_lossy(&output.stdout)
+    .trim()
+    .trim_matches('\'')
+    .to_string();
+  (!theme.is_empty()).then_some(theme)
+}
+
+/// Icon theme set in GTK's `settings.ini`
+fn gtk_icon_theme() -> Option<String> {
+  ["gtk-4.0", "gtk-3.0"].iter().find_map(|version| {
+    let settings = KeyFile::load(&config_home().join(version).join("settings.ini")).ok()?;
+    let theme = settings
+      .get("Settings", "gtk-icon-theme-name")?
+      .trim_matches('"');
+    (!theme.is_empty()).then(|| theme.to_string())
+  })
+}
+
+/// Icon theme set in KDE's `kdeglobals`
+fn kde_icon_theme() -> Option<String> {
+  let globals = KeyFile::load(&config_home().join("kdeglobals")).ok()?;
+  globals
+    .get("Icons", "Theme")
+    .filter(|theme| !theme.is_empty())
+    .map(str::to_string)
+}
+
+/// Name of the icon theme the user selected in their desktop's settings
+pub fn current_icon_theme() -> String {
+  let is_kde = current_desktops()
+    .iter()
+    .any(|desktop| desktop.eq_ignore_ascii_case("KDE"));
+  if is_kde {
+    // Plasma doesn't write the theme when it is the default
+    return kde_icon_theme().unwrap_or_else(|| "breeze".to_string());
+  }
+
+  gsettings_icon_theme()
+    .or_else(gtk_icon_theme)
+    .or_else(kde_icon_theme)
+    .unwrap_or_else(|| FALLBACK_THEME.to_string())
+}
+
+/// Find an icon of the user's theme by name, or check that an absolute icon
+/// path exists
+pub fn lookup_icon(name: &str, size: u32) -> Option<PathBuf> {
+  if name.is_empty() {
+    return None;
+  }
+  if name.starts_with('/') {
+    let path = PathBuf::from(name);
+    return path.is_file().then_some(path);
+  }
+
+  ICON_LOOKUP
+    .lock()
+    .unwrap()
+    .get_or_insert_with(IconLookup::load)
+    .find(name, size)
+}
+
+/// Write new lookups to the disk cache
+pub fn save_icon_cache() {
+  let mut lookup = ICON_LOOKUP.lock().unwrap();
+  let Some(lookup) = lookup.as_mut().filter(|lookup| lookup.changed) else {
+    return;
+  };
+
+  match serde_json::to_string(&lookup.cache) {
+    Ok(json) => match fs::write(icon_cache_path(), json) {
+      Ok(()) => lookup.changed = false,
+      Err(e) => eprintln!("[Icons] Failed to write icon cache: {}", e),
+    },
+    Err(e) => eprintln!("[Icons] Failed to serialize icon cache: {}", e),
+  }
+}
+
+/// Forget the loaded theme, so the next lookup reads the settings again
+pub fn reload_icon_theme() {
+  *ICON_LOOKUP.lock().unwrap() = None;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  fn theme_dir(index: &str, path: &str) -> ThemeDir {
+    ThemeDir::from_index(&KeyFile::parse(index), path).unwrap()
+  }
+
+  #[test]
+  fn test_theme_dir_sizes() {
+    let fixed = theme_dir("[48x48/apps]\nSize=48\nType=Fixed", "48x48/apps");
+    assert!(fixed.matches_size(48, 1));
+    assert!(!fixed.matches_size(48, 2));
+    assert_eq!(fixed.size_distance(32, 1), 16);
+    assert_eq!(fixed.size_distance(64, 1), 16);
+
+    let scalable = theme_dir(
+      "[scalable/apps]\nSize=128\nMinSize=8\nMaxSize=512\nType=Scalable",
+      "scalable/apps",
+    );
+    assert!(scalable.matches_size(48, 1));
+    assert_eq!(scalable.size_distance(1024, 1), 512);
+
+    // Threshold is the default type, with a threshold of 2
+    let threshold = theme_dir("[22x22]\nSize=22", "22x22");
+    assert!(threshold.matches_size(24, 1));
+    assert!(!threshold.matches_size(25, 1));
+    assert_eq!(threshold.size_distance(16, 1), 4);
+  }
+
+  #[test]
+  fn test_find_icon() {
+    let root = TempDir::new("icons");
+    let base_dir = root.join("icons");
+    let pixmaps = root.join("pixmaps");
+    let write = |path: &str, contents: &str| {
+      let path = root.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, contents).unwrap();
+    };
+    write(
+      "icons/Test/index.theme",
+      "[Icon Theme]\nName=Test\nInherits=Parent\nDirectories=16x16/apps,48x48/apps\n\n\
+       [16x16/apps]\nSize=16\nType=Fixed\n\n[48x48/apps]\nSize=48\nType=Fixed\n",
+    );
+    write(
+      "icons/Parent/index.theme",
+      "[Icon Theme]\nName=Parent\nInherits=Test\nDirectories=scalable/apps\n\n\
+       [scalable/apps]\nSize=64\nMinSize=16\nMaxSize=256\nType=Scalable\n",
+    );
+    write(
+      "icons/hicolor/index.theme",
+      "[Icon Theme]\nName=Hicolor\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Fixed\n",
+    );
+    write("icons/Test/16x16/apps/editor.png", "");
+    write("icons/Test/48x48/apps/editor.png", "");
+    write("icons/Test/16x16/apps/small.png", "");
+    write("icons/Parent/scalable/apps/browser.svg", "");
+    write("icons/hicolor/48x48/apps/browser.png", "");
+    write("icons/hicolor/48x48/apps/terminal.png", "");
+    write("pixmaps/legacy.xpm", "");
+    write("pixmaps/old.png", "");
+
+    let themes = theme_chain("Test", std::slice::from_ref(&base_dir));
+    let fallback_dirs = [base_dir, pixmaps];
+    let mut files = DirContents::default();
+    let mut find = |name: &str, size: u32| {
+      find_icon(&themes, &fallback_dirs, &mut files, name, size).map(|path| {
+        path
+          .strip_prefix(&root)
+          .unwrap()
+          .to_string_lossy()
+          .to_string()
+      })
+    };
+
+    let found = [
+      find("editor", 48),
+      find("editor", 16),
+      find("small", 48),
+      find("browser", 48),
+      find("terminal", 48),
+      find("old.png", 48),
+      find("legacy", 48),
+    ];
+
+    assert_eq!(
+      found,
+      [
+        Some("icons/Test/48x48/apps/editor.png".to_string()),
+        Some("icons/Test/16x16/apps/editor.png".to_string()),
+        // The closest size of the theme beats an exact size in a parent
+        Some("icons/Test/16x16/apps/small.png".to_string()),
+        Some("icons/Parent/scalable/apps/browser.svg".to_string()),
+        Some("icons/hicolor/48x48/apps/terminal.png".to_string()),
+        Some("pixmaps/old.png".to_string()),
+        None,
+      ]
+    );
+    // The inheritance cycle back to Test is ignored
+    assert_eq!(themes.len(), 3);
+  }
+}
@@ -0,0 +1,550 @@
+//! Launcher Module
+//!
+//! Starts applications detached from rua, so they neither linger as zombies
+//! nor go down with it. Each app is double-forked into a new session and
+//! adopted by init; with a systemd user manager it also gets its own
+//! `app-rua-<id>-<random>.scope` unit, as desktop environments do.
+//!
+//! Apps that exit with an error right after starting are reported with the
+//! end of their stderr, and each launch gets an activation token so the
+//! compositor or window manager lets the new window take focus. Stderr goes
+//! to the journal, which bounds its size and doesn't depend on rua reading
+//! it, and the end is read back from there; without journald it's discarded.
+
+use std::{
+  io::{self, Write},
+  net::Shutdown,
+  os::{
+    fd::OwnedFd,
+    unix::{net::UnixStream, process::CommandExt},
+  },
+  path::Path,
+  process::{Child, Command, Stdio},
+  sync::atomic::{AtomicU32, Ordering},
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use wayland_client::{
+  globals::{registry_queue_init, GlobalListContents},
+  protocol::wl_registry,
+  Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols::xdg::activation::v1::client::{
+  xdg_activation_token_v1::{self, XdgActivationTokenV1},
+  xdg_activation_v1::XdgActivationV1,
+};
+use x11rb::{
+  connection::Connection as _,
+  protocol::{xproto::*, Event},
+  rust_connection::RustConnection,
+  wrapper::ConnectionExt as _,
+  COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
+};
+
+use super::display_server::detect_display_server;
+use super::notification::notify;
+
+/// Event emitted when a launched application exits with an error right away
+pub const LAUNCH_FAILED_EVENT: &str = "rua://launch-failed";
+
+/// How long after starting an exit counts as a failed launch
+const EARLY_EXIT_WINDOW: Duration = Duration::from_secs(3);
+
+/// How often the intermediate child checks whether the app exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lines of stderr kept for reporting a failed launch
+const STDERR_LINES: usize = 20;
+
+/// Socket of journald's stream transport, which services' output goes to
+const JOURNAL_STREAM_SOCKET: &str = "/run/systemd/journal/stdout";
+
+/// How often reading a failed app's stderr from the journal is tried, as
+/// journald stores lines shortly after they are written
+const JOURNAL_READ_ATTEMPTS: u32 = 5;
+
+/// How long to wait between those attempts
+const JOURNAL_READ_DELAY: Duration = Duration::from_millis(100);
+
+/// Number of launches, to make startup IDs unique
+static LAUNCH_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// An application that exited with an error right after starting
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchFailure {
+  /// Desktop file ID, if the app was launched from one
+  pub app_id: Option<String>,
+  pub command: String,
+  /// Exit code, 128 plus the signal number if the app was killed by one
+  pub exit_code: Option<i32>,
+  /// Last lines the app wrote to stderr
+  pub stderr: String,
+}
+
+/// Name for logs and notifications, the desktop file ID without `.desktop`
+/// or the program's file name
+fn app_name(argv: &[String], app_id: Option<&str>) -> String {
+  match app_id {
+    Some(id) => id.strip_suffix(".desktop").unwrap_or(id).to_string(),
+    None => Path::new(&argv[0])
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+      .unwrap_or_else(|| argv[0].clone()),
+  }
+}
+
+/// Escape a string for use in a unit name, like `systemd-escape`
+fn escape_unit_name(name: &str) -> String {
+  let mut escaped = String::new();
+  for (i, byte) in name.bytes().enumerate() {
+    let allowed = byte.is_ascii_alphanumeric() || byte == b':' || byte == b'_' || byte == b'.';
+    if allowed && !(i == 0 && byte == b'.') {
+      escaped.push(byte as char);
+    } else {
+      escaped.push_str(&format!("\\x{:02x}", byte));
+    }
+  }
+  escaped
+}
+
+/// Whether apps can be started in scopes of the user's systemd manager
+fn has_systemd_user_manager() -> bool {
+  let has_manager = std::env::var("XDG_RUNTIME_DIR")
+    .is_ok_and(|dir| Path::new(&dir).join("systemd/private").exists());
+  has_manager && which::which("systemd-run").is_ok()
+}
+
+/// Command starting the app in a transient scope unit
+///
+/// `systemd-run --scope` executes the app itself, so it runs as the app.
+fn systemd_scope_command(argv: &[String], name: &str) -> Command {
+  let unit = format!(
+    "app-rua-{}-{:08x}.scope",
+    escape_unit_name(name),
+    rand::random::<u32>()
+  );
+  let mut command = Command::new("systemd-run");
+  command
+    .args(["--user", "--scope", "--quiet", "--collect"])
+    .arg(format!("--unit={}", unit))
+    .arg("--")
+    .args(argv);
+  command
+}
+
+/// Wait for the app to exit, up to `EARLY_EXIT_WINDOW`
+///
+/// Returns its exit status, 0 if it exited successfully or is still running.
+/// Runs in the forked intermediate child, so it must be async-signal-safe.
+fn early_exit_status(pid: libc::pid_t) -> i32 {
+  let mut waited = Duration::ZERO;
+  while waited < EARLY_EXIT_WINDOW {
+    let mut status = 0;
+    if unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid {
+      if libc::WIFEXITED(status) {
+        return libc::WEXITSTATUS(status);
+      }
+      if libc::WIFSIGNALED(status) {
+        return 128 + libc::WTERMSIG(status);
+      }
+    }
+    thread::sleep(EXIT_POLL_INTERVAL);
+    waited += EXIT_POLL_INTERVAL;
+  }
+  0
+}
+
+/// Start the command double-forked in a new session
+///
+/// The intermediate child exits when the app does, with its exit status, or
+/// once `EARLY_EXIT_WINDOW` passed, and the app is adopted by init. Failures
+/// to execute the app are still reported by `spawn`, as the app inherits the
+/// pipe that reports them.
+fn detach(command: &mut Command) {
+  unsafe {
+    command.pre_exec(|| {
+      if libc::setsid() == -1 {
+        return Err(io::Error::last_os_error());
+      }
+      let pid = libc::fork();
+      match pid {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(()),
+        _ => {
+          // Close the pipe `spawn` waits on, only the app's exec counts
+          if libc::syscall(libc::SYS_close_range, 3, u32::MAX, 0) == -1 {
+            for fd in 3..1024 {
+              libc::close(fd);
+            }
+          }
+          libc::_exit(early_exit_status(pid))
+        }
+      }
+    });
+  }
+}
+
+/// Connect a stream for the app's stderr to the journal, logging its lines
+/// with the identifier
+fn journal_stream(socket: &Path, identifier: &str) -> io::Result<UnixStream> {
+  let stream = UnixStream::connect(socket)?;
+  // Identifier, unit ID, priority, level prefix, forwarding to syslog, kmsg
+  // and the console, as `systemd-cat` sends them
+  let header = format!("{}\n\n{}\n0\n0\n0\n0\n", identifier, libc::LOG_WARNING);
+  (&stream).write_all(header.as_bytes())?;
+  stream.shutdown(Shutdown::Read)?;
+  Ok(stream)
+}
+
+/// Read the last lines logged with the identifier since a time, in seconds
+/// since the epoch
+fn journal_tail(identifier: &str, since: u64) -> Result<String, String> {
+  let mut command = Command::new("journalctl");
+  command
+    .args(["--quiet", "--no-pager", "--output=cat"])
+    .arg(format!("--lines={}", STDERR_LINES))
+    .arg(format!("--since=@{}", since))
+    .arg(format!("SYSLOG_IDENTIFIER={}", identifier))
+    // SAFETY: getuid has no preconditions and cannot fail
+    .arg(format!("_UID={}", unsafe { libc::getuid() }));
+
+  for _ in 0..JOURNAL_READ_ATTEMPTS {
+    let output = command
+      .output()
+      .map_err(|e| format!("Failed to run journalctl: {}", e))?;
+    let lines = String::from_utf8_lossy(&output.stdout);
+    if !lines.trim().is_empty() {
+      return Ok(lines.trim_end().to_string());
+    }
+    thread::sleep(JOURNAL_READ_DELAY);
+  }
+  Ok(String::new())
+}
+
+fn report_failure(app: &AppHandle, name: &str, failure: LaunchFailure) {
+  eprintln!(
+    "[Launcher] {} exited early with code {:?}: {}",
+    failure.command, failure.exit_code, failure.stderr
+  );
+  let body = match (failure.stderr.lines().last(), failure.exit_code) {
+    (Some(line), _) => line.to_string(),
+    (None, Some(code)) => format!("Exited with code {}", code),
+    (None, None) => "Exited right after starting".to_string(),
+  };
+  notify(&format!("Failed to start {}", name), &body);
+  if let Err(e) = app.emit(LAUNCH_FAILED_EVENT, &failure) {
+    eprintln!("[Launcher] Failed to emit launch failed event: {}", e);
+  }
+}
+
+/// Wait for the intermediate child, reporting the app if it failed right away
+///
+/// Apps that hand over to a running instance exit right away too, but
+/// successfully, so only exit codes count as failure. `logged_since` is the
+/// launch time if the app's stderr goes to the journal under its name.
+fn supervise(
+  app: AppHandle,
+  name: String,
+  mut failure: LaunchFailure,
+  mut child: Child,
+  logged_since: Option<u64>,
+) {
+  thread::spawn(move || {
+    let status = match child.wait() {
+      Ok(status) => status,
+      Err(e) => return eprintln!("[Launcher] Failed to wait for {}: {}", name, e),
+    };
+    if status.success() {
+      return;
+    }
+
+    failure.exit_code = status.code();
+    if let Some(since) = logged_since {
+      failure.stderr = journal_tail(&name, since).unwrap_or_else(|e| {
+        eprintln!("[Launcher] Failed to read stderr of {}: {}", name, e);
+        String::new()
+      });
+    }
+    report_failure(&app, &name, failure);
+  });
+}
+
+/// Start an application detached from rua
+///
+/// Errors are returned when the app can't be executed at all; apps that exit
+/// with an error right after starting are reported with a notification and
+/// the launch failed event.
+pub fn launch_detached(
+  app: &AppHandle,
+  argv: &[String],
+  working_dir: Option<&str>,
+  app_id: Option<&str>,
+) -> Result<(), String> {
+  if argv.is_empty() {
+    return Err("Exec has no program".to_string());
+  }
+  let name = app_name(argv, app_id);
+  let use_scope = has_systemd_user_manager();
+  let mut command = if use_scope {
+    systemd_scope_command(argv, &name)
+  } else {
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    command
+  };
+  detach(&mut command);
+
+  let launched = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|time| time.as_secs())
+    .unwrap_or_default();
+  let (stderr, logged_since) = match journal_stream(Path::new(JOURNAL_STREAM_SOCKET), &name) {
+    Ok(stream) => (Stdio::from(OwnedFd::from(stream)), Some(launched)),
+    Err(e) => {
+      eprintln!(
+        "[Launcher] Not logging stderr of {} to the journal: {}",
+        name, e
+      );
+      (Stdio::null(), None)
+    }
+  };
+  command.stdin(Stdio::null()).stderr(stderr);
+  if let Some(dir) = working_dir.filter(|dir| Path::new(dir).is_dir()) {
+    command.current_dir(dir);
+  }
+  // Never pass on the token rua itself was started with
+  command.env_remove("XDG_ACTIVATION_TOKEN");
+  command.env_remove("DESKTOP_STARTUP_ID");
+  if let Some(token) = activation_token(&name) {
+    if detect_display_server().is_wayland() {
+      command.env("XDG_ACTIVATION_TOKEN", &token);
+    }
+    command.env("DESKTOP_STARTUP_ID", &token);
+  }
+
+  eprintln!("[Launcher] Launching {:?} (scope: {})", argv, use_scope);
+  let child = command
+    .spawn()
+    .map_err(|e| format!("Failed to launch {}: {}", argv[0], e))?;
+
+  let failure = LaunchFailure {
+    app_id: app_id.map(str::to_string),
+    command: argv.join(" "),
+    exit_code: None,
+    stderr: String::new(),
+  };
+  supervise(app.clone(), name, failure, child, logged_since);
+  Ok(())
+}
+
+/// Get a token that lets the launched app's window take focus
+///
+/// On Wayland the compositor issues an xdg-activation token, on X11 the
+/// token is a startup notification ID with the current server time.
+fn activation_token(app_name: &str) -> Option<String> {
+  let result = if detect_display_server().is_wayland() {
+    wayland_activation_token(app_name)
+  } else {
+    x11_startup_id(app_name)
+  };
+  result
+    .map_err(|e| eprintln!("[Launcher] No activation token: {}", e))
+    .ok()
+}
+
+/// State of an activation token request
+#[derive(Default)]
+struct ActivationState {
+  token: Option<String>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for ActivationState {
+  fn event(
+    _: &mut Self,
+    _: &wl_registry::WlRegistry,
+    _: wl_registry::Event,
+    _: &GlobalListContents,
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<XdgActivationV1, ()> for ActivationState {
+  fn event(
+    _: &mut Self,
+    _: &XdgActivationV1,
+    _: <XdgActivationV1 as wayland_client::Proxy>::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+  }
+}
+
+impl Dispatch<XdgActivationTokenV1, ()> for ActivationState {
+  fn event(
+    state: &mut Self,
+    _: &XdgActivationTokenV1,
+    event: xdg_activation_token_v1::Event,
+    _: &(),
+    _: &Connection,
+    _: &QueueHandle<Self>,
+  ) {
+    if let xdg_activation_token_v1::Event::Done { token } = event {
+      state.token = Some(token);
+    }
+  }
+}
+
+/// Request an xdg-activation token from the compositor
+///
+/// rua's window is hidden by the time apps start, so the token carries no
+/// surface and it is up to the compositor whether the app may take focus.
+fn wayland_activation_token(app_name: &str) -> Result<String, String> {
+  let conn = Connection::connect_to_env()
+    .map_err(|e| format!("Failed to connect to Wayland compositor: {}", e))?;
+  let (globals, mut queue) = registry_queue_init::<ActivationState>(&conn)
+    .map_err(|e| format!("Failed to list Wayland globals: {}", e))?;
+  let qh = queue.handle();
+
+  let activation: XdgActivationV1 = globals
+    .bind(&qh, 1..=1, ())
+    .map_err(|e| format!("Compositor does not support xdg-activation: {}", e))?;
+  let request = activation.get_activation_token(&qh, ());
+  request.set_app_id(app_name.to_string());
+  request.commit();
+
+  let mut state = ActivationState::default();
+  let token = loop {
+    queue
+      .blocking_dispatch(&mut state)
+      .map_err(|e| format!("Wayland connection lost: {}", e))?;
+    if let Some(token) = state.token.take() {
+      break token;
+    }
+  };
+  request.destroy();
+  activation.destroy();
+  Ok(token)
+}
+
+/// Get the X server's current time, from the timestamp of a property change
+fn x11_server_time() -> Result<u32, String> {
+  let x11_error = |e: &dyn std::fmt::Display| format!("X11 error: {}", e);
+  let (conn, screen_num) = RustConnection::connect(None).map_err(|e| x11_error(&e))?;
+  let root = conn.setup().roots[screen_num].root;
+  let window = conn.generate_id().map_err(|e| x11_error(&e))?;
+  conn
+    .create_window(
+      COPY_DEPTH_FROM_PARENT,
+      window,
+      root,
+      0,
+      0,
+      1,
+      1,
+      0,
+      WindowClass::INPUT_ONLY,
+      COPY_FROM_PARENT,
+      &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+    )
+    .map_err(|e| x11_error(&e))?;
+  conn
+    .change_property8(
+      PropMode::APPEND,
+      window,
+      AtomEnum::WM_NAME,
+      AtomEnum::STRING,
+      &[],
+    )
+    .map_err(|e| x11_error(&e))?;
+  conn.flush().map_err(|e| x11_error(&e))?;
+
+  loop {
+    if let Event::PropertyNotify(event) = conn.wait_for_event().map_err(|e| x11_error(&e))? {
+      let _ = conn.destroy_window(window);
+      let _ = conn.flush();
+      return Ok(event.time);
+    }
+  }
+}
+
+/// Build a startup notification ID, whose timestamp tells the window
+/// manager the launch was requested by the user just now
+fn x11_startup_id(app_name: &str) -> Result<String, String> {
+  let time = x11_server_time()?;
+  let count = LAUNCH_COUNT.fetch_add(1, Ordering::Relaxed);
+  Ok(format!(
+    "rua-{}-{}-{}_TIME{}",
+    std::process::id(),
+    count,
+    escape_unit_name(app_name),
+    time
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{io::Read, os::unix::net::UnixListener};
+
+  use super::*;
+  use crate::test_util::TempDir;
+
+  #[test]
+  fn test_escape_unit_name() {
+    assert_eq!(escape_unit_name("org.gnome.Nautilus"), "org.gnome.Nautilus");
+    assert_eq!(escape_unit_name("kde-okular"), "kde\\x2dokular");
+    assert_eq!(escape_unit_name(".hidden app"), "\\x2ehidden\\x20app");
+  }
+
+  #[test]
+  fn test_detach() {
+    let spawn = |script: &str| {
+      let mut command = Command::new("sh");
+      command.args(["-c", script]).stderr(Stdio::null());
+      detach(&mut command);
+      command.spawn().unwrap()
+    };
+
+    // The intermediate child exits with the app's status
+    assert_eq!(
+      spawn("echo failed >&2; exit 3").wait().unwrap().code(),
+      Some(3)
+    );
+    let status = spawn("kill -TERM $$").wait().unwrap();
+    assert_eq!(status.code(), Some(128 + libc::SIGTERM));
+    assert!(spawn("true").wait().unwrap().success());
+
+    // Programs that can't be executed are still reported by spawn
+    let mut command = Command::new("/nonexistent/program");
+    detach(&mut command);
+    assert!(command.spawn().is_err());
+  }
+
+  #[test]
+  fn test_journal_stream() {
+    let dir = TempDir::new("journal-stream");
+    let socket = dir.join("stdout");
+    let listener = UnixListener::bind(&socket).unwrap();
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "echo starting >&2; echo failed >&2"]);
+    let stream = journal_stream(&socket, "org.example.App").unwrap();
+    command.stderr(OwnedFd::from(stream));
+    detach(&mut command);
+    assert!(command.spawn().unwrap().wait().unwrap().success());
+    drop(command);
+
+    let mut logged = String::new();
+    let (mut server, _) = listener.accept().unwrap();
+    server.read_to_string(&mut logged).unwrap();
+    assert_eq!(
+      logged,
+      "org.example.App\n\n4\n0\n0\n0\n0\nstarting\nfailed\n"
+    );
+  }
+}
@@ -0,0 +1,535 @@
+//! MIME Applications Module
+//!
+//! Finds the applications that can open a file and the default one for its
+//! type, from the `mimeapps.list` files and the `MimeType` key of desktop
+//! files, and changes the default in the user's `mimeapps.list`.
+//! See: https://specifications.freedesktop.org/mime-apps-spec/latest/
+
+use std::{
+  collections::HashSet,
+  fs, iter,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use serde::Serialize;
+
+use super::applications::{data_dirs, mime_handlers, MimeHandler};
+use super::desktop_entry::{current_desktops, KeyFile};
+use super::icon_theme::config_home;
+use super::mime_database::{mime_database, MimeDatabase};
+
+const DEFAULT_APPLICATIONS_GROUP: &str = "Default Applications";
+const ADDED_ASSOCIATIONS_GROUP: &str = "Added Associations";
+const REMOVED_ASSOCIATIONS_GROUP: &str = "Removed Associations";
+
+/// An application offered to open a file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenWithApplication {
+  /// Desktop file ID, for `launch_application_with`
+  pub id: String,
+  pub name: String,
+  /// Icon name from the icon theme or absolute path
+  pub icon: Option<String>,
+  pub is_default: bool,
+}
+
+/// The type of a file and the applications that can open it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandlers {
+  pub mime_type: String,
+  /// Applications in order of preference, the default first
+  pub applications: Vec<OpenWithApplication>,
+}
+
+/// Get the XDG config directories in search order: `XDG_CONFIG_HOME`, then
+/// `XDG_CONFIG_DIRS`, with the spec's default when it isn't set
+fn config_dirs() -> Vec<PathBuf> {
+  let config_dirs = std::env::var("XDG_CONFIG_DIRS")
+    .ok()
+    .filter(|dirs| !dirs.is_empty())
+    .unwrap_or_else(|| "/etc/xdg".to_string());
+
+  let mut dirs = vec![config_home()];
+  for dir in config_dirs.split(':').filter(|dir| dir.starts_with('/')) {
+    let dir = PathBuf::from(dir);
+    if !dirs.contains(&dir) {
+      dirs.push(dir);
+    }
+  }
+  dirs
+}
+
+/// Paths of the `mimeapps.list` files, most important first: in each config
+/// directory, then each applications directory, the files of the current
+/// desktops before the generic one
+fn mimeapps_list_paths(
+  config_dirs: &[PathBuf],
+  data_dirs: &[PathBuf],
+  desktops: &[String],
+) -> Vec<PathBuf> {
+  let app_dirs = data_dirs.iter().map(|dir| dir.join("applications"));
+  config_dirs
+    .iter()
+    .cloned()
+    .chain(app_dirs)
+    .flat_map(|dir| {
+      desktops
+        .iter()
+        .map(|desktop| dir.join(format!("{}-mimeapps.list", desktop.to_lowercase())))
+        .chain(iter::once(dir.join("mimeapps.list")))
+        .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// Associations between types and applications
+struct MimeApps {
+  /// Parsed `mimeapps.list` files, most important first
+  lists: Vec<KeyFile>,
+  /// Installed applications that can open files
+  handlers: Vec<MimeHandler>,
+  db: Arc<MimeDatabase>,
+}
+
+impl MimeApps {
+  fn load() -> Self {
+    let paths = mimeapps_list_paths(&config_dirs(), &data_dirs(), &current_desktops());
+    Self {
+      lists: paths
+        .iter()
+        .filter_map(|path| KeyFile::load(path).ok())
+        .collect(),
+      handlers: mime_handlers(),
+      db: mime_database(),
+    }
+  }
+
+  fn handler(&self, id: &str) -> Option<&MimeHandler> {
+    self.handlers.iter().find(|handler| handler.id == id)
+  }
+
+  /// Desktop file IDs listed for a type or one of its aliases in a group
+  fn listed(&self, list: &KeyFile, group: &str, mime_type: &str) -> Vec<String> {
+    self
+      .db
+      .names(mime_type)
+      .into_iter()
+      .flat_map(|name| list.list(group, name))
+      .collect()
+  }
+
+  /// Applications associated with a type itself, in order of preference
+  ///
+  /// Added associations come first, in the order of their files, then the
+  /// desktop files listing the type. Removed associations hide applications
+  /// from less important files and from desktop files.
+  fn associated(&self, mime_type: &str) -> Vec<&MimeHandler> {
+    let mut removed = HashSet::new();
+    let mut handlers: Vec<&MimeHandler> = Vec::new();
+    for list in &self.lists {
+      for id in self.listed(list, ADDED_ASSOCIATIONS_GROUP, mime_type) {
+        let handler = self.handler(&id);
+        if let Some(handler) = handler.filter(|_| !removed.contains(&id)) {
+          if !handlers.iter().any(|added| added.id == id) {
+            handlers.push(handler);
+          }
+        }
+      }
+      removed.extend(self.listed(list, REMOVED_ASSOCIATIONS_GROUP, mime_type));
+    }
+
+    for handler in &self.handlers {
+      let opens_type = handler
+        .mime_types
+        .iter()
+        .any(|handled| self.db.canonical(handled) == mime_type);
+      if opens_type
+        && !removed.contains(&handler.id)
+        && !handlers.iter().any(|added| added.id == handler.id)
+      {
+        handlers.push(handler);
+      }
+    }
+    handlers
+  }
+
+  /// Default application of a type itself: the first installed one from
+  /// the most important file setting a default, else the most preferred
+  /// associated one
+  fn default_for(&self, mime_type: &str) -> Option<&MimeHandler> {
+    self
+      .lists
+      .iter()
+      .flat_map(|list| self.listed(list, DEFAULT_APPLICATIONS_GROUP, mime_type))
+      .find_map(|id| self.handler(&id))
+      .or_else(|| self.associated(mime_type).into_iter().next())
+  }
+
+  /// Default and all applications for a type, falling back to those of its
+  /// parent types, e.g. `text/plain` for source code
+  fn applications_for(&self, mime_type: &str) -> (Option<&MimeHandler>, Vec<&MimeHandler>) {
+    let types = self.db.with_ancestors(mime_type);
+    let default = types
+      .iter()
+      .find_map(|mime_type| self.default_for(mime_type));
+
+    let mut handlers: Vec<&MimeHandler> = default.into_iter().collect();
+    for mime_type in &types {
+      for handler in self.associated(mime_type) {
+        if !handlers.iter().any(|added| added.id == handler.id) {
+          handlers.push(handler);
+        }
+      }
+    }
+    (default, handlers)
+  }
+}
+
+/// Format desktop file IDs as a `mimeapps.list` value
+fn list_value(ids: &[String]) -> String {
+  ids.iter().map(|id| format!("{};", id)).collect()
+}
+
+/// Set a key of a group in key file contents, or remove it with `None`,
+/// keeping all other lines as they are
+fn set_key(contents: &str, group: &str, key: &str, value: Option<&str>) -> String {
+  let header = format!("[{}]", group);
+  let new_line = value.map(|value| format!("{}={}", key, value));
+  let mut lines: Vec<&str> = contents.lines().collect();
+
+  match lines.iter().position(|line| line.trim() == header) {
+    Some(start) => {
+      let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |n| start + 1 + n);
+      let existing = (start + 1..end).find(|&i| {
+        lines[i]
+          .split_once('=')
+          .is_some_and(|(name, _)| name.trim() == key)
+      });
+      match (existing, &new_line) {
+        (Some(i), Some(new_line)) => lines[i] = new_line,
+        (Some(i), None) => {
+          lines.remove(i);
+        }
+        (None, Some(new_line)) => {
+          // After the group's last line, before blank lines separating groups
+          let at = (start + 1..end)
+            .rev()
+            .find(|&i| !lines[i].trim().is_empty())
+            .map_or(start + 1, |i| i + 1);
+          lines.insert(at, new_line);
+        }
+        (None, None) => {}
+      }
+    }
+    None => {
+      if let Some(new_line) = &new_line {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+          lines.push("");
+        }
+        lines.extend([header.as_str(), new_line]);
+      }
+    }
+  }
+
+  let mut contents = lines.join("\n");
+  contents.push('\n');
+  contents
+}
+
+/// Make an application the default for a type in `mimeapps.list` contents
+///
+/// Like GLib, the application is also put first in the added associations
+/// and no longer listed in the removed ones.
+fn set_default(contents: &str, mime_type: &str, app_id: &str) -> String {
+  let file = KeyFile::parse(contents);
+  let others = |group: &str| -> Vec<String> {
+    file
+      .list(group, mime_type)
+      .into_iter()
+      .filter(|id| id != app_id)
+      .collect()
+  };
+  let added: Vec<String> = iter::once(app_id.to_string())
+    .chain(others(ADDED_ASSOCIATIONS_GROUP))
+    .collect();
+  let removed = others(REMOVED_ASSOCIATIONS_GROUP);
+
+  let contents = set_key(
+    contents,
+    DEFAULT_APPLICATIONS_GROUP,
+    mime_type,
+    Some(&list_value(&[app_id.to_string()])),
+  );
+  let contents = set_key(
+    &contents,
+    ADDED_ASSOCIATIONS_GROUP,
+    mime_type,
+    Some(&list_value(&added)),
+  );
+  let removed = (!removed.is_empty()).then(|| list_value(&removed));
+  set_key(
+    &contents,
+    REMOVED_ASSOCIATIONS_GROUP,
+    mime_type,
+    removed.as_deref(),
+  )
+}
+
+/// Get the desktop file ID of the default application for a file
+pub fn default_application_for_file(path: &str) -> Option<String> {
+  let apps = MimeApps::load();
+  let mime_type = apps.db.type_for_file(Path::new(path));
+  let (default, _) = apps.applications_for(&mime_type);
+  default.map(|handler| handler.id.clone())
+}
+
+/// Get the type of a file and all applications that can open it, for an
+/// "Open With" menu
+#[tauri::command]
+pub fn get_file_handlers(path: String) -> Result<FileHandlers, String> {
+  let path = Path::new(&path);
+  if !path.exists() {
+    return Err(format!("File not found: {}", path.display()));
+  }
+
+  let apps = MimeApps::load();
+  let mime_type = apps.db.type_for_file(path);
+  let (default, handlers) = apps.applications_for(&mime_type);
+  let default_id = default.map(|handler| handler.id.as_str());
+  let applications = handlers
+    .into_iter()
+    .map(|handler| OpenWithApplication {
+      id: handler.id.clone(),
+      name: handler.name.clone(),
+      icon: handler.icon.clone(),
+      is_default: Some(handler.id.as_str()) == default_id,
+    })
+    .collect();
+
+  Ok(FileHandlers {
+    mime_type,
+    applications,
+  })
+}
+
+/// Check that a MIME type follows the `type/subtype` grammar (RFC 6838), so
+/// it can't break the key file it is written to
+fn is_valid_mime_type(mime_type: &str) -> bool {
+  let is_name = |name: &str| {
+    name.len() <= 127
+      && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+      && name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+  };
+  matches!(mime_type.split_once('/'), Some((kind, subtype)) if is_name(kind) && is_name(subtype))
+}
+
+/// Make an application the default for a type in the user's `mimeapps.list`
+///
+/// Desktop-specific files in the user's config directory take precedence,
+/// so those setting a default for the type are updated too.
+#[tauri::command]
+pub fn set_default_application(mime_type: String, app_id: String) -> Result<String, String> {
+  if !is_valid_mime_type(&mime_type) {
+    return Err(format!("Invalid MIME type: {}", mime_type));
+  }
+  let apps = MimeApps::load();
+  if apps.handler(&app_id).is_none() {
+    return Err(format!("Application not found: {}", app_id));
+  }
+  let mime_type = apps.db.canonical(&mime_type);
+  let config_home = config_home();
+
+  for desktop in current_desktops() {
+    let path = config_home.join(format!("{}-mimeapps.list", desktop.to_lowercase()));
+    let Ok(contents) = fs::read_to_string(&path) else {
+      continue;
+    };
+    if KeyFile::parse(&contents)
+      .get(DEFAULT_APPLICATIONS_GROUP, mime_type)
+      .is_some()
+    {
+      fs::write(&path, set_default(&contents, mime_type, &app_id))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+  }
+
+  let path = config_home.join("mimeapps.list");
+  let contents = fs::read_to_string(&path).unwrap_or_default();
+  fs::create_dir_all(&config_home)
+    .map_err(|e| format!("Failed to create {}: {}", config_home.display(), e))?;
+  fs::write(&path, set_default(&contents, mime_type, &app_id))
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+  Ok(format!("{} is now the default for {}", app_id, mime_type))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn handler(id: &str, mime_types: &[&str]) -> MimeHandler {
+    MimeHandler {
+      id: id.to_string(),
+      name: id.to_string(),
+      icon: None,
+      mime_types: mime_types.iter().map(|t| t.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn test_mimeapps_list_paths() {
+    let paths = mimeapps_list_paths(
+      &[PathBuf::from("/home/me/.config"), PathBuf::from("/etc/xdg")],
+      &[PathBuf::from("/usr/share")],
+      &["ubuntu".to_string(), "GNOME".to_string()],
+    );
+    let paths: Vec<&str> = paths.iter().map(|path| path.to_str().unwrap()).collect();
+    assert_eq!(
+      paths,
+      vec![
+        "/home/me/.config/ubuntu-mimeapps.list",
+        "/home/me/.config/gnome-mimeapps.list",
+        "/home/me/.config/mimeapps.list",
+        "/etc/xdg/ubuntu-mimeapps.list",
+        "/etc/xdg/gnome-mimeapps.list",
+        "/etc/xdg/mimeapps.list",
+        "/usr/share/applications/ubuntu-mimeapps.list",
+        "/usr/share/applications/gnome-mimeapps.list",
+        "/usr/share/applications/mimeapps.list",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_applications_for() {
+    let apps = MimeApps {
+      lists: vec![
+        KeyFile::parse(
+          "[Default Applications]\n\
+           text/markdown=missing.desktop;\n\
+           [Added Associations]\n\
+           text/markdown=typora.desktop;\n\
+           [Removed Associations]\n\
+           text/markdown=gedit.desktop;\n",
+        ),
+        KeyFile::parse(
+          "[Default Applications]\n\
+           text/markdown=code.desktop;\n\
+           text/plain=gedit.desktop;\n\
+           [Added Associations]\n\
+           text/markdown=gedit.desktop;code.desktop;\n",
+        ),
+      ],
+      handlers: vec![
+        handler("code.desktop", &["text/plain"]),
+        handler("gedit.desktop", &["text/plain", "text/markdown"]),
+        handler("typora.desktop", &[]),
+        handler("vim.desktop", &["text/plain"]),
+      ],
+      db: Arc::new(MimeDatabase::default()),
+    };
+    let ids = |handlers: Vec<&MimeHandler>| -> Vec<String> {
+      handlers.iter().map(|handler| handler.id.clone()).collect()
+    };
+
+    // Uninstalled defaults are skipped, removals only hide less important ones
+    let (default, handlers) = apps.applications_for("text/markdown");
+    assert_eq!(
+      default.map(|handler| handler.id.as_str()),
+      Some("code.desktop")
+    );
+    assert_eq!(
+      ids(handlers),
+      vec![
+        "code.desktop",
+        "typora.desktop",
+        "gedit.desktop",
+        "vim.desktop"
+      ]
+    );
+
+    // Without a default, the most preferred associated application is used
+    let (default, handlers) = apps.applications_for("text/x-python");
+    assert_eq!(
+      default.map(|handler| handler.id.as_str()),
+      Some("gedit.desktop")
+    );
+    assert_eq!(
+      ids(handlers),
+      vec!["gedit.desktop", "code.desktop", "vim.desktop"]
+    );
+    assert!(apps.applications_for("image/png").0.is_none());
+  }
+
+  #[test]
+  fn test_set_default() {
+    let contents = "# My defaults\n\
+                    [Default Applications]\n\
+                    text/plain=gedit.desktop;\n\
+                    \n\
+                    [Removed Associations]\n\
+                    text/plain=code.desktop;vim.desktop;\n";
+    assert_eq!(
+      set_default(contents, "text/plain", "code.desktop"),
+      "# My defaults\n\
+       [Default Applications]\n\
+       text/plain=code.desktop;\n\
+       \n\
+       [Removed Associations]\n\
+       text/plain=vim.desktop;\n\
+       \n\
+       [Added Associations]\n\
+       text/plain=code.desktop;\n"
+    );
+    assert_eq!(
+      set_default("", "image/png", "eog.desktop"),
+      "[Default Applications]\n\
+       image/png=eog.desktop;\n\
+       \n\
+       [Added Associations]\n\
+       image/png=eog.desktop;\n"
+    );
+    assert_eq!(
+      set_key(
+        "[Added Associations]\na=x;\nb=y;\n",
+        "Added Associations",
+        "a",
+        None
+      ),
+      "[Added Associations]\nb=y;\n"
+    );
+  }
+
+  #[test]
+  fn test_is_valid_mime_type() {
+    for valid in [
+      "text/plain",
+      "image/svg+xml",
+      "x-scheme-handler/https",
+      "application/vnd.ms-excel",
+    ] {
+      assert!(is_valid_mime_type(valid), "{}", valid);
+    }
+    for invalid in [
+      "",
+      "text",
+      "text/",
+      "/plain",
+      "text/plain/x",
+      "text/plain\n[Default Applications]",
+      "text/plain=evil.desktop",
+      "[text]/plain",
+      "text/ plain",
+    ] {
+      assert!(!is_valid_mime_type(invalid), "{}", invalid);
+    }
+  }
+}
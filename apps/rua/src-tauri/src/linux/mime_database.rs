@@ -0,0 +1,677 @@
+//! MIME Database Module
+//!
+//! Detects the MIME type of files from the shared-mime-info database in the
+//! `mime` directory of each XDG data directory: by file name with the globs,
+//! and by content with the magic rules when the name is ambiguous or unknown.
+//! Also knows the aliases and parent types of each type, so applications
+//! for `text/plain` are offered for source code too.
+//! See: https://specifications.freedesktop.org/shared-mime-info-spec/latest/
+
+use std::{
+  cmp::Reverse,
+  collections::{HashMap, HashSet, VecDeque},
+  fs::{self, File},
+  io::Read,
+  iter,
+  os::unix::fs::FileTypeExt,
+  path::Path,
+  sync::{Arc, Mutex},
+  time::SystemTime,
+};
+
+use glob::{MatchOptions, Pattern};
+
+use super::applications::data_dirs;
+
+/// Database files, whose modification times tell when to load it again
+const DATABASE_FILES: [&str; 5] = ["globs2", "globs", "magic", "aliases", "subclasses"];
+
+/// Weight of globs from the old `globs` format, which has none
+const DEFAULT_GLOB_WEIGHT: u32 = 50;
+
+/// Most bytes of a file read for magic rules
+const MAX_MAGIC_EXTENT: usize = 64 * 1024;
+
+/// Type of files that are neither text nor matched by any rule
+pub const FALLBACK_MIME_TYPE: &str = "application/octet-stream";
+
+lazy_static::lazy_static! {
+    /// Loaded database and the modification time of its newest file
+    static ref MIME_DATABASE: Mutex<Option<(Option<SystemTime>, Arc<MimeDatabase>)>> =
+        Mutex::new(None);
+}
+
+/// A file name pattern of a MIME type
+#[derive(Debug)]
+struct Glob {
+  weight: u32,
+  mime_type: String,
+  /// Pattern to match, lowercase unless it is case-sensitive
+  pattern: String,
+  case_sensitive: bool,
+  /// Compiled pattern, for patterns that aren't a literal name or `*.ext`
+  compiled: Option<Pattern>,
+}
+
+impl Glob {
+  fn new(weight: u32, mime_type: &str, pattern: &str, case_sensitive: bool) -> Self {
+    let pattern = if case_sensitive {
+      pattern.to_string()
+    } else {
+      pattern.to_lowercase()
+    };
+    let is_simple = |s: &str| !s.contains(['*', '?', '[']);
+    let compiled = if is_simple(&pattern) || pattern.strip_prefix('*').is_some_and(is_simple) {
+      None
+    } else {
+      Pattern::new(&pattern).ok()
+    };
+    Self {
+      weight,
+      mime_type: mime_type.to_string(),
+      pattern,
+      case_sensitive,
+      compiled,
+    }
+  }
+
+  fn matches(&self, name: &str, lowercase_name: &str) -> bool {
+    let name = if self.case_sensitive {
+      name
+    } else {
+      lowercase_name
+    };
+    match (&self.compiled, self.pattern.strip_prefix('*')) {
+      (Some(pattern), _) => pattern.matches_with(name, MatchOptions::new()),
+      (None, Some(suffix)) => name.ends_with(suffix),
+      (None, None) => name == self.pattern,
+    }
+  }
+}
+
+/// A magic rule, matching a value at an offset of the contents
+#[derive(Debug)]
+struct MagicRule {
+  /// Nesting level, rules are only checked when their parent matched
+  indent: usize,
+  offset: usize,
+  value: Vec<u8>,
+  mask: Option<Vec<u8>>,
+  /// Number of offsets from `offset` the value may start at
+  range: usize,
+}
+
+impl MagicRule {
+  fn matches(&self, data: &[u8]) -> bool {
+    (self.offset..self.offset + self.range).any(|start| {
+      let Some(bytes) = data.get(start..start + self.value.len()) else {
+        return false;
+      };
+      match &self.mask {
+        Some(mask) => bytes
+          .iter()
+          .zip(&self.value)
+          .zip(mask)
+          .all(|((byte, value), mask)| byte & mask == value & mask),
+        None => bytes == self.value.as_slice(),
+      }
+    })
+  }
+
+  /// Bytes of the contents the rule looks at
+  fn extent(&self) -> usize {
+    self.offset + self.range + self.value.len()
+  }
+}
+
+/// The magic rules of a MIME type
+#[derive(Debug)]
+struct Magic {
+  priority: u32,
+  mime_type: String,
+  rules: Vec<MagicRule>,
+}
+
+/// Check whether any rule matches together with one of its nested rules,
+/// if it has any
+fn rules_match(rules: &[MagicRule], data: &[u8]) -> bool {
+  let mut i = 0;
+  while i < rules.len() {
+    let indent = rules[i].indent;
+    let end = rules[i + 1..]
+      .iter()
+      .position(|rule| rule.indent <= indent)
+      .map_or(rules.len(), |n| i + 1 + n);
+    if rules[i].matches(data) && (end == i + 1 || rules_match(&rules[i + 1..end], data)) {
+      return true;
+    }
+    i = end;
+  }
+  false
+}
+
+/// Read a decimal number, stopping at the first other byte
+fn read_number(data: &[u8], pos: &mut usize) -> Option<usize> {
+  let start = *pos;
+  while data.get(*pos).is_some_and(u8::is_ascii_digit) {
+    *pos += 1;
+  }
+  std::str::from_utf8(&data[start..*pos]).ok()?.parse().ok()
+}
+
+/// Parse one rule line of a magic file:
+/// `[indent]>offset=<length><value>[&mask][~word-size][+range]\n`
+fn parse_magic_rule(data: &[u8], pos: &mut usize) -> Option<MagicRule> {
+  let indent = if data.get(*pos) == Some(&b'>') {
+    0
+  } else {
+    read_number(data, pos)?
+  };
+  (data.get(*pos) == Some(&b'>')).then_some(())?;
+  *pos += 1;
+  let offset = read_number(data, pos)?;
+  (data.get(*pos) == Some(&b'=')).then_some(())?;
+  let length = u16::from_be_bytes([*data.get(*pos + 1)?, *data.get(*pos + 2)?]) as usize;
+  *pos += 3;
+  let mut value = data.get(*pos..*pos + length)?.to_vec();
+  *pos += length;
+
+  let mut mask = None;
+  if data.get(*pos) == Some(&b'&') {
+    mask = Some(data.get(*pos + 1..*pos + 1 + length)?.to_vec());
+    *pos += 1 + length;
+  }
+  let mut word_size = 1;
+  if data.get(*pos) == Some(&b'~') {
+    *pos += 1;
+    word_size = read_number(data, pos)?;
+  }
+  let mut range = 1;
+  if data.get(*pos) == Some(&b'+') {
+    *pos += 1;
+    range = read_number(data, pos)?;
+  }
+  // Skip extensions this parser doesn't know
+  while data.get(*pos).is_some_and(|&byte| byte != b'\n') {
+    *pos += 1;
+  }
+  *pos += 1;
+
+  // Values of words are stored big-endian
+  if cfg!(target_endian = "little") && (word_size == 2 || word_size == 4) {
+    value.chunks_mut(word_size).for_each(<[u8]>::reverse);
+    if let Some(mask) = mask.as_mut() {
+      mask.chunks_mut(word_size).for_each(<[u8]>::reverse);
+    }
+  }
+
+  Some(MagicRule {
+    indent,
+    offset,
+    value,
+    mask,
+    range: range.max(1),
+  })
+}
+
+/// Parse a `magic` file, returning the types listed with `__NOMAGIC__` too,
+/// whose rules from less important directories are to be ignored
+fn parse_magic(data: &[u8]) -> Option<(Vec<Magic>, Vec<String>)> {
+  let mut pos = data.strip_prefix(b"MIME-Magic\0\n").map(|_| 12)?;
+  let mut magics = Vec::new();
+  let mut cleared = Vec::new();
+
+  while pos < data.len() {
+    // Section header: [priority:mime/type]
+    let end = pos + data[pos..].iter().position(|&byte| byte == b'\n')?;
+    let header = std::str::from_utf8(&data[pos..end]).ok()?;
+    let (priority, mime_type) = header
+      .strip_prefix('[')?
+      .strip_suffix(']')?
+      .split_once(':')?;
+    let mut magic = Magic {
+      priority: priority.parse().ok()?,
+      mime_type: mime_type.to_string(),
+      rules: Vec::new(),
+    };
+    pos = end + 1;
+
+    while pos < data.len() && data[pos] != b'[' {
+      if data[pos..].starts_with(b"__NOMAGIC__\n") {
+        cleared.push(magic.mime_type.clone());
+        pos += 12;
+        continue;
+      }
+      magic.rules.push(parse_magic_rule(data, &mut pos)?);
+    }
+    magics.push(magic);
+  }
+  Some((magics, cleared))
+}
+
+/// Parse a `globs2` file, or a `globs` file with `old_format`, returning
+/// the types listed with `__NOGLOBS__` too
+fn parse_globs(contents: &str, old_format: bool) -> (Vec<Glob>, Vec<String>) {
+  let mut globs = Vec::new();
+  let mut cleared = Vec::new();
+
+  for line in contents.lines().filter(|line| !line.starts_with('#')) {
+    let fields: Vec<&str> = line.split(':').collect();
+    let (weight, fields) = match (old_format, fields.as_slice()) {
+      (true, [_, _]) => (DEFAULT_GLOB_WEIGHT, fields.as_slice()),
+      (false, [weight, rest @ ..]) if rest.len() >= 2 => match weight.parse() {
+        Ok(weight) => (weight, rest),
+        Err(_) => continue,
+      },
+      _ => continue,
+    };
+    let (mime_type, pattern) = (fields[0], fields[1]);
+    if pattern == "__NOGLOBS__" {
+      cleared.push(mime_type.to_string());
+      continue;
+    }
+    let case_sensitive = fields
+      .get(2)
+      .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+    globs.push(Glob::new(weight, mime_type, pattern, case_sensitive));
+  }
+  (globs, cleared)
+}
+
+/// Check whether contents look like text rather than binary data
+fn looks_like_text(data: &[u8]) -> bool {
+  let data = &data[..data.len().min(256)];
+  // A multi-byte character may be cut off at the end
+  let valid = match std::str::from_utf8(data) {
+    Ok(_) => true,
+    Err(e) => e.error_len().is_none(),
+  };
+  valid
+    && !data
+      .iter()
+      .any(|&byte| byte < 0x20 && !b"\t\n\r\x0c\x1b".contains(&byte))
+}
+
+/// The shared-mime-info database, merged from all data directories
+#[derive(Debug, Default)]
+pub struct MimeDatabase {
+  globs: Vec<Glob>,
+  /// Magic rules by descending priority
+  magic: Vec<Magic>,
+  /// Bytes of a file needed to check all magic rules
+  magic_extent: usize,
+  /// Canonical type of each alias
+  aliases: HashMap<String, String>,
+  /// Parent types of each type
+  parents: HashMap<String, Vec<String>>,
+}
+
+impl MimeDatabase {
+  /// Load the database from the `mime` directories, most important first
+  fn load(mime_dirs: &[&Path]) -> Self {
+    let mut db = Self::default();
+
+    // Less important directories first, so `__NOGLOBS__` and `__NOMAGIC__`
+    // can clear what they define
+    for dir in mime_dirs.iter().rev() {
+      let globs = match fs::read_to_string(dir.join("globs2")) {
+        Ok(contents) => Some(parse_globs(&contents, false)),
+        Err(_) => fs::read_to_string(dir.join("globs"))
+          .ok()
+          .map(|contents| parse_globs(&contents, true)),
+      };
+      if let Some((globs, cleared)) = globs {
+        db.globs.retain(|glob| !cleared.contains(&glob.mime_type));
+        db.globs.extend(globs);
+      }
+
+      let magic = fs::read(dir.join("magic")).ok();
+      match magic.as_deref().map(parse_magic) {
+        Some(Some((magic, cleared))) => {
+          db.magic.retain(|magic| !cleared.contains(&magic.mime_type));
+          db.magic.extend(magic);
+        }
+        Some(None) => eprintln!("[MIME] Invalid magic file in {}", dir.display()),
+        None => {}
+      }
+
+      let pairs = |name: &str| {
+        fs::read_to_string(dir.join(name))
+          .unwrap_or_default()
+          .lines()
+          .filter_map(|line| line.split_once(' '))
+          .map(|(a, b)| (a.to_string(), b.to_string()))
+          .collect::<Vec<_>>()
+      };
+      db.aliases.extend(pairs("aliases"));
+      for (mime_type, parent) in pairs("subclasses") {
+        let parents = db.parents.entry(mime_type).or_default();
+        if !parents.contains(&parent) {
+          parents.push(parent);
+        }
+      }
+    }
+
+    db.magic.sort_by_key(|magic| Reverse(magic.priority));
+    db.magic_extent = db
+      .magic
+      .iter()
+      .flat_map(|magic| &magic.rules)
+      .map(MagicRule::extent)
+      .max()
+      .unwrap_or_default()
+      .min(MAX_MAGIC_EXTENT);
+    db
+  }
+
+  /// Get the canonical name of a type, which may be an alias
+  pub fn canonical<'a>(&'a self, mime_type: &'a str) -> &'a str {
+    self
+      .aliases
+      .get(mime_type)
+      .map(String::as_str)
+      .unwrap_or(mime_type)
+  }
+
+  /// A canonical type followed by its aliases
+  pub fn names<'a>(&'a self, mime_type: &'a str) -> Vec<&'a str> {
+    let aliases = self
+      .aliases
+      .iter()
+      .filter(|(_, canonical)| *canonical == mime_type)
+      .map(|(alias, _)| alias.as_str());
+    iter::once(mime_type).chain(aliases).collect()
+  }
+
+  /// Direct parents of a type, including the implicit ones
+  fn parents_of(&self, mime_type: &str) -> Vec<&str> {
+    let mut parents: Vec<&str> = self
+      .parents
+      .get(mime_type)
+      .map(|parents| {
+        parents
+          .iter()
+          .map(|parent| self.canonical(parent))
+          .collect()
+      })
+      .unwrap_or_default();
+    if parents.is_empty() {
+      // All text is plain text, and all files a stream of bytes
+      if mime_type.starts_with("text/") && mime_type != "text/plain" {
+        parents.push("text/plain");
+      } else if !mime_type.starts_with("inode/") && mime_type != FALLBACK_MIME_TYPE {
+        parents.push(FALLBACK_MIME_TYPE);
+      }
+    }
+    parents
+  }
+
+  /// A type followed by all its ancestors, nearest first
+  pub fn with_ancestors(&self, mime_type: &str) -> Vec<String> {
+    let mut types = vec![self.canonical(mime_type).to_string()];
+    let mut queue = VecDeque::from([types[0].clone()]);
+    let mut seen: HashSet<String> = types.iter().cloned().collect();
+    while let Some(mime_type) = queue.pop_front() {
+      for parent in self.parents_of(&mime_type) {
+        if seen.insert(parent.to_string()) {
+          types.push(parent.to_string());
+          queue.push_back(parent.to_string());
+        }
+      }
+    }
+    types
+  }
+
+  fn is_subclass(&self, mime_type: &str, parent: &str) -> bool {
+    self
+      .with_ancestors(mime_type)
+      .iter()
+      .any(|ancestor| ancestor == self.canonical(parent))
+  }
+
+  /// Types whose globs match a file name best: the highest weight, then the
+  /// longest pattern, then case-sensitive ones
+  fn types_for_name(&self, name: &str) -> Vec<&str> {
+    let lowercase_name = name.to_lowercase();
+    let mut best: Option<(u32, usize, bool)> = None;
+    let mut types = Vec::new();
+    for glob in &self.globs {
+      if !glob.matches(name, &lowercase_name) {
+        continue;
+      }
+      let rank = Some((glob.weight, glob.pattern.len(), glob.case_sensitive));
+      if rank > best {
+        best = rank;
+        types.clear();
+      }
+      if rank == best && !types.contains(&glob.mime_type.as_str()) {
+        types.push(glob.mime_type.as_str());
+      }
+    }
+    types
+  }
+
+  /// Type of the contents by the magic rule with the highest priority
+  fn type_for_data(&self, data: &[u8]) -> Option<&str> {
+    self
+      .magic
+      .iter()
+      .find(|magic| rules_match(&magic.rules, data))
+      .map(|magic| magic.mime_type.as_str())
+  }
+
+  /// Detect the type of a file from its name and contents
+  ///
+  /// Contents are only read when no glob or several conflicting ones match
+  /// the name; of those, a type the contents' type is or is a parent of wins.
+  pub fn type_for_file(&self, path: &Path) -> String {
+    if let Ok(metadata) = fs::metadata(path) {
+      let file_type = metadata.file_type();
+      let inode_type = if file_type.is_dir() {
+        Some("inode/directory")
+      } else if file_type.is_char_device() {
+        Some("inode/chardevice")
+      } else if file_type.is_block_device() {
+        Some("inode/blockdevice")
+      } else if file_type.is_fifo() {
+        Some("inode/fifo")
+      } else if file_type.is_socket() {
+        Some("inode/socket")
+      } else {
+        None
+      };
+      if let Some(inode_type) = inode_type {
+        return inode_type.to_string();
+      }
+    }
+
+    let name = path
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+      .unwrap_or_default();
+    let glob_types = self.types_for_name(&name);
+    if let [mime_type] = glob_types.as_slice() {
+      return mime_type.to_string();
+    }
+
+    let mut data = Vec::new();
+    let read = File::open(path).and_then(|file| {
+      file
+        .take(self.magic_extent.max(256) as u64)
+        .read_to_end(&mut data)
+    });
+    if read.is_err() {
+      return glob_types
+        .first()
+        .unwrap_or(&FALLBACK_MIME_TYPE)
+        .to_string();
+    }
+
+    let mime_type = match self.type_for_data(&data) {
+      Some(magic_type) => glob_types
+        .iter()
+        .find(|glob_type| self.is_subclass(glob_type, magic_type))
+        .copied()
+        .unwrap_or(magic_type),
+      None => match glob_types.first() {
+        Some(glob_type) => glob_type,
+        None if looks_like_text(&data) => "text/plain",
+        None => FALLBACK_MIME_TYPE,
+      },
+    };
+    self.canonical(mime_type).to_string()
+  }
+}
+
+/// Latest modification time of the database files
+fn database_timestamp(mime_dirs: &[&Path]) -> Option<SystemTime> {
+  mime_dirs
+    .iter()
+    .flat_map(|dir| DATABASE_FILES.iter().map(move |name| dir.join(name)))
+    .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    .max()
+}
+
+/// Get the MIME database, loading it again when it was updated
+pub fn mime_database() -> Arc<MimeDatabase> {
+  let data_dirs = data_dirs();
+  let mime_dirs: Vec<_> = data_dirs.iter().map(|dir| dir.join("mime")).collect();
+  let mime_dirs: Vec<&Path> = mime_dirs.iter().map(|dir| dir.as_path()).collect();
+  let timestamp = database_timestamp(&mime_dirs);
+
+  let mut database = MIME_DATABASE.lock().unwrap();
+  match database.as_ref() {
+    Some((loaded, db)) if *loaded == timestamp => db.clone(),
+    _ => {
+      let db = Arc::new(MimeDatabase::load(&mime_dirs));
+      *database = Some((timestamp, db.clone()));
+      db
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  /// Build a magic file from sections of raw rule lines
+  fn magic_file(sections: &[(&str, &[&[u8]])]) -> Vec<u8> {
+    let mut data = b"MIME-Magic\0\n".to_vec();
+    for (header, rules) in sections {
+      data.extend(format!("[{}]\n", header).as_bytes());
+      for rule in *rules {
+        data.extend(*rule);
+      }
+    }
+    data
+  }
+
+  #[test]
+  fn test_parse_magic() {
+    let magic = magic_file(&[
+      ("80:image/png", &[b">0=\x00\x04\x89PNG\n"]),
+      (
+        "50:application/x-test",
+        &[
+          b">0=\x00\x02AB\n",
+          b"1>4=\x00\x01C+4\n",
+          b">8=\x00\x02\x0f\x0f&\x0f\x0f\n",
+        ],
+      ),
+      ("40:text/x-cleared", &[b"__NOMAGIC__\n"]),
+    ]);
+    let (magics, cleared) = parse_magic(&magic).unwrap();
+    assert_eq!(magics.len(), 3);
+    assert_eq!(cleared, vec!["text/x-cleared"]);
+
+    let rules = &magics[1].rules;
+    assert!(rules_match(&magics[0].rules, b"\x89PNG\r\n"));
+    // The nested rule must match within its range too
+    assert!(rules_match(rules, b"AB.....C"));
+    assert!(!rules_match(rules, b"AB......C"));
+    assert!(rules_match(rules, b"xx......\x1f\xff"));
+    assert!(!rules_match(rules, b"xx......\x10\xff"));
+    assert!(parse_magic(b"not magic").is_none());
+  }
+
+  #[test]
+  fn test_type_for_file() {
+    let root = TempDir::new("mime");
+    let (system, user) = (root.join("system"), root.join("user"));
+    fs::create_dir_all(&system).unwrap();
+    fs::create_dir_all(&user).unwrap();
+    fs::write(
+      system.join("globs2"),
+      "# comment\n\
+       50:text/x-csrc:*.c\n\
+       50:text/x-c++src:*.C:cs\n\
+       50:application/msword:*.doc\n\
+       50:text/plain:*.doc\n\
+       10:text/x-readme:README*\n\
+       50:text/x-old:*.old\n",
+    )
+    .unwrap();
+    fs::write(
+      system.join("magic"),
+      magic_file(&[(
+        "50:application/x-ole-storage",
+        &[b">0=\x00\x04\xd0\xcf\x11\xe0\n"],
+      )]),
+    )
+    .unwrap();
+    fs::write(
+      system.join("subclasses"),
+      "application/msword application/x-ole-storage\n",
+    )
+    .unwrap();
+    fs::write(system.join("aliases"), "text/x-c text/x-csrc\n").unwrap();
+    fs::write(user.join("globs2"), "50:text/x-old:__NOGLOBS__\n").unwrap();
+
+    let db = MimeDatabase::load(&[&user, &system]);
+    let write = |name: &str, contents: &[u8]| {
+      let path = root.join(name);
+      fs::write(&path, contents).unwrap();
+      path
+    };
+
+    assert_eq!(
+      db.type_for_file(&write("main.c", b"\xd0\xcf\x11\xe0")),
+      "text/x-csrc"
+    );
+    assert_eq!(db.type_for_file(&write("MAIN.c", b"")), "text/x-csrc");
+    assert_eq!(db.type_for_file(&write("main.C", b"")), "text/x-c++src");
+    assert_eq!(db.type_for_file(&write("README.md", b"")), "text/x-readme");
+    // Conflicting globs are decided by the contents
+    assert_eq!(
+      db.type_for_file(&write("letter.doc", b"\xd0\xcf\x11\xe0")),
+      "application/msword"
+    );
+    assert_eq!(
+      db.type_for_file(&write("unknown", b"\xd0\xcf\x11\xe0")),
+      "application/x-ole-storage"
+    );
+    assert_eq!(
+      db.type_for_file(&write("notes.old", b"plain")),
+      "text/plain"
+    );
+    assert_eq!(
+      db.type_for_file(&write("data", b"\x00\x01")),
+      FALLBACK_MIME_TYPE
+    );
+    assert_eq!(db.type_for_file(&root), "inode/directory");
+
+    assert_eq!(
+      db.with_ancestors("text/x-c"),
+      vec!["text/x-csrc", "text/plain", FALLBACK_MIME_TYPE]
+    );
+    assert_eq!(
+      db.with_ancestors("application/msword"),
+      vec![
+        "application/msword",
+        "application/x-ole-storage",
+        FALLBACK_MIME_TYPE
+      ]
+    );
+  }
+}
@@ -1,14 +1,24 @@
 mod applications;
 mod clipboard;
 mod control_server;
+mod desktop_entry;
 pub mod display_server;
 mod hyprland;
+mod icon_protocol;
+mod icon_theme;
+mod launcher;
+mod mime_apps;
+mod mime_database;
 mod notification;
 mod shell_executor;
+mod url_handler;
 pub mod x11_window;
 
 pub use applications::*;
 pub use clipboard::*;
 pub use control_server::*;
+pub use icon_protocol::*;
+pub use mime_apps::*;
 pub use notification::*;
 pub use shell_executor::*;
+pub use url_handler::*;
@@ -1,18 +1,208 @@
 //! Notification Module
 //!
-//! Provides system notification functionality for extensions.
+//! Shows notifications through the `org.freedesktop.Notifications` D-Bus
+//! service, with icons, urgency, timeouts, action buttons and replacing by ID.
+//! Clicked actions are emitted as `rua://notification-action` for the
+//! extension that raised the notification. `notify-send` is only used when the
+//! session bus is not available.
 
-/// Show a system notification using notify-send on Linux
-#[tauri::command]
-pub fn show_notification(title: String, body: Option<String>) -> Result<(), String> {
-  use std::process::Command;
+use std::{
+  collections::HashMap,
+  process::Command,
+  sync::{Mutex, Once},
+};
 
-  let mut cmd = Command::new("notify-send");
-  cmd.arg(&title);
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::OnceCell;
+use zbus::zvariant::Value;
+
+/// Name shown by notification daemons
+const APP_NAME: &str = "Rua";
+
+/// Event emitted when an action button of a notification is clicked
+pub const NOTIFICATION_ACTION_EVENT: &str = "rua://notification-action";
+
+#[zbus::proxy(
+  interface = "org.freedesktop.Notifications",
+  default_service = "org.freedesktop.Notifications",
+  default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+  #[allow(clippy::too_many_arguments)]
+  fn notify(
+    &self,
+    app_name: &str,
+    replaces_id: u32,
+    app_icon: &str,
+    summary: &str,
+    body: &str,
+    actions: &[&str],
+    hints: HashMap<&str, Value<'_>>,
+    expire_timeout: i32,
+  ) -> zbus::Result<u32>;
+
+  fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+  #[zbus(signal)]
+  fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Session bus proxy, connected on first use
+static PROXY: OnceCell<NotificationsProxy<'static>> = OnceCell::const_new();
+static FORWARD_ACTIONS: Once = Once::new();
+
+lazy_static::lazy_static! {
+    /// Extensions that raised the open notifications with actions, by notification ID
+    static ref OWNERS: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+  Low,
+  #[default]
+  Normal,
+  Critical,
+}
+
+/// A button on a notification
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationAction {
+  /// Key reported back when clicked, `default` for clicking the notification itself
+  pub id: String,
+  pub label: String,
+}
 
-  if let Some(body_text) = body {
-    cmd.arg(&body_text);
+/// Optional notification settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationOptions {
+  /// Icon name from the icon theme or an absolute path
+  pub icon: Option<String>,
+  pub urgency: Option<Urgency>,
+  /// Milliseconds until the notification expires, 0 to never expire
+  pub timeout: Option<i32>,
+  /// ID of a notification to replace
+  pub replace_id: Option<u32>,
+  #[serde(default)]
+  pub actions: Vec<NotificationAction>,
+}
+
+/// Payload of `rua://notification-action`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationActionEvent {
+  pub notification_id: u32,
+  pub action_id: String,
+  pub extension_id: String,
+}
+
+/// Send a notification, returning its ID
+async fn send_notification(
+  proxy: &NotificationsProxy<'_>,
+  title: &str,
+  body: &str,
+  options: &NotificationOptions,
+) -> zbus::Result<u32> {
+  let actions: Vec<&str> = options
+    .actions
+    .iter()
+    .flat_map(|action| [action.id.as_str(), action.label.as_str()])
+    .collect();
+
+  let urgency = match options.urgency.unwrap_or_default() {
+    Urgency::Low => 0u8,
+    Urgency::Normal => 1,
+    Urgency::Critical => 2,
+  };
+  let hints = HashMap::from([("urgency", Value::from(urgency))]);
+
+  proxy
+    .notify(
+      APP_NAME,
+      options.replace_id.unwrap_or(0),
+      options.icon.as_deref().unwrap_or_default(),
+      title,
+      body,
+      &actions,
+      hints,
+      options.timeout.unwrap_or(-1),
+    )
+    .await
+}
+
+/// Report clicked actions of notifications raised by extensions, until the
+/// connection closes
+async fn forward_actions(
+  proxy: NotificationsProxy<'static>,
+  on_action: impl Fn(NotificationActionEvent),
+) -> zbus::Result<()> {
+  let mut invoked = proxy.receive_action_invoked().await?;
+  let mut closed = proxy.receive_notification_closed().await?;
+
+  loop {
+    tokio::select! {
+      Some(signal) = invoked.next() => {
+        let args = signal.args()?;
+        let extension_id = OWNERS.lock().unwrap().get(&args.id).cloned();
+        // Other apps' notifications are reported too
+        if let Some(extension_id) = extension_id {
+          on_action(NotificationActionEvent {
+            notification_id: args.id,
+            action_id: args.action_key,
+            extension_id,
+          });
+        }
+      }
+      Some(signal) = closed.next() => {
+        OWNERS.lock().unwrap().remove(&signal.args()?.id);
+      }
+      else => return Ok(()),
+    }
   }
+}
+
+/// Connect to the notification service on the session bus
+async fn notifications_proxy() -> Result<&'static NotificationsProxy<'static>, String> {
+  PROXY
+    .get_or_try_init(|| async {
+      // Connect on the app runtime, which outlives the caller's
+      tauri::async_runtime::spawn(async {
+        let connection = zbus::Connection::session().await?;
+        NotificationsProxy::new(&connection).await
+      })
+      .await
+      .map_err(|e| e.to_string())?
+      .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Failed to connect to notification service: {}", e))
+}
+
+/// Show a notification using notify-send, which doesn't support actions
+fn notify_send(title: &str, body: &str, options: &NotificationOptions) -> Result<(), String> {
+  let mut cmd = Command::new("notify-send");
+  cmd.args(["--app-name", APP_NAME]);
+  if let Some(icon) = &options.icon {
+    cmd.args(["--icon", icon]);
+  }
+  if let Some(urgency) = options.urgency {
+    let urgency = match urgency {
+      Urgency::Low => "low",
+      Urgency::Normal => "normal",
+      Urgency::Critical => "critical",
+    };
+    cmd.args(["--urgency", urgency]);
+  }
+  if let Some(timeout) = options.timeout {
+    cmd.arg(format!("--expire-time={}", timeout));
+  }
+  cmd.arg(title).arg(body);
 
   let output = cmd.output().map_err(|e| {
     format!(
@@ -28,3 +218,238 @@ pub fn show_notification(title: String, body: Option<String>) -> Result<(), Stri
     Err(format!("notify-send failed: {}", stderr))
   }
 }
+
+/// Show a plain notification from the backend without waiting for it
+pub fn notify(title: &str, body: &str) {
+  let (title, body) = (title.to_string(), body.to_string());
+  tauri::async_runtime::spawn(async move {
+    let options = NotificationOptions::default();
+    let result = match notifications_proxy().await {
+      Ok(proxy) => send_notification(proxy, &title, &body, &options)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to show notification: {}", e)),
+      Err(_) => notify_send(&title, &body, &options),
+    };
+    if let Err(e) = result {
+      eprintln!("[Notification] {}", e);
+    }
+  });
+}
+
+/// Show a system notification, returning its ID for replacing or closing it
+///
+/// The ID is 0 when the notification service is unavailable and notify-send
+/// is used instead.
+#[tauri::command]
+pub async fn show_notification(
+  app: AppHandle,
+  title: String,
+  body: Option<String>,
+  options: Option<NotificationOptions>,
+  extension_id: Option<String>,
+) -> Result<u32, String> {
+  let options = options.unwrap_or_default();
+  let body = body.unwrap_or_default();
+
+  let proxy = match notifications_proxy().await {
+    Ok(proxy) => proxy,
+    Err(e) => {
+      eprintln!("[Notification] {}, falling back to notify-send", e);
+      notify_send(&title, &body, &options)?;
+      return Ok(0);
+    }
+  };
+
+  FORWARD_ACTIONS.call_once(|| {
+    let proxy = proxy.clone();
+    tauri::async_runtime::spawn(async move {
+      let result = forward_actions(proxy, |event| {
+        if let Err(e) = app.emit(NOTIFICATION_ACTION_EVENT, event) {
+          eprintln!("Failed to emit {}: {}", NOTIFICATION_ACTION_EVENT, e);
+        }
+      })
+      .await;
+      if let Err(e) = result {
+        eprintln!("[Notification] Stopped listening for actions: {}", e);
+      }
+    });
+  });
+
+  let id = send_notification(proxy, &title, &body, &options)
+    .await
+    .map_err(|e| format!("Failed to show notification: {}", e))?;
+
+  let mut owners = OWNERS.lock().unwrap();
+  match extension_id {
+    Some(extension_id) if !options.actions.is_empty() => owners.insert(id, extension_id),
+    _ => owners.remove(&id),
+  };
+  Ok(id)
+}
+
+/// Close a notification shown by `show_notification`
+#[tauri::command]
+pub async fn close_notification(id: u32) -> Result<(), String> {
+  notifications_proxy()
+    .await?
+    .close_notification(id)
+    .await
+    .map_err(|e| format!("Failed to close notification: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{sync::Arc, time::Duration};
+
+  use tokio::{net::UnixStream, sync::mpsc};
+  use zbus::{connection, object_server::SignalEmitter, Guid};
+
+  use super::*;
+
+  const PATH: &str = "/org/freedesktop/Notifications";
+
+  /// Replaced ID, icon, summary, actions, urgency and timeout of a notification
+  type Shown = (u32, String, String, Vec<String>, u8, i32);
+
+  /// A notification daemon recording what it was asked to show
+  #[derive(Default)]
+  struct MockDaemon {
+    shown: Arc<Mutex<Vec<Shown>>>,
+  }
+
+  #[zbus::interface(name = "org.freedesktop.Notifications")]
+  impl MockDaemon {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+      &self,
+      _app_name: &str,
+      replaces_id: u32,
+      app_icon: &str,
+      summary: &str,
+      _body: &str,
+      actions: Vec<String>,
+      hints: HashMap<String, zbus::zvariant::OwnedValue>,
+      expire_timeout: i32,
+    ) -> u32 {
+      let urgency = hints
+        .get("urgency")
+        .and_then(|value| u8::try_from(value).ok())
+        .unwrap_or_default();
+      let mut shown = self.shown.lock().unwrap();
+      shown.push((
+        replaces_id,
+        app_icon.to_string(),
+        summary.to_string(),
+        actions,
+        urgency,
+        expire_timeout,
+      ));
+      if replaces_id > 0 {
+        replaces_id
+      } else {
+        shown.len() as u32
+      }
+    }
+
+    #[zbus(signal)]
+    async fn action_invoked(
+      emitter: &SignalEmitter<'_>,
+      id: u32,
+      action_key: &str,
+    ) -> zbus::Result<()>;
+  }
+
+  #[tokio::test]
+  async fn test_notification_actions() {
+    let (client_socket, server_socket) = UnixStream::pair().unwrap();
+    let daemon = MockDaemon::default();
+    let shown = daemon.shown.clone();
+    let (server, client) = tokio::try_join!(
+      connection::Builder::unix_stream(server_socket)
+        .server(Guid::generate())
+        .unwrap()
+        .p2p()
+        .serve_at(PATH, daemon)
+        .unwrap()
+        .build(),
+      connection::Builder::unix_stream(client_socket)
+        .p2p()
+        .build(),
+    )
+    .unwrap();
+    let proxy = NotificationsProxy::new(&client).await.unwrap();
+
+    let options = NotificationOptions {
+      icon: Some("dialog-information".to_string()),
+      urgency: Some(Urgency::Critical),
+      timeout: Some(5000),
+      actions: vec![NotificationAction {
+        id: "open".to_string(),
+        label: "Open".to_string(),
+      }],
+      ..Default::default()
+    };
+    let id = send_notification(&proxy, "Title", "Body", &options)
+      .await
+      .unwrap();
+    let replace = NotificationOptions {
+      replace_id: Some(id),
+      ..Default::default()
+    };
+    assert_eq!(
+      send_notification(&proxy, "Updated", "", &replace)
+        .await
+        .unwrap(),
+      id
+    );
+    {
+      let shown = shown.lock().unwrap();
+      assert_eq!(
+        shown[0],
+        (
+          0,
+          "dialog-information".to_string(),
+          "Title".to_string(),
+          vec!["open".to_string(), "Open".to_string()],
+          2,
+          5000
+        )
+      );
+      assert_eq!(
+        shown[1],
+        (id, String::new(), "Updated".to_string(), vec![], 1, -1)
+      );
+    }
+
+    // Clicks are only reported for notifications raised by extensions
+    OWNERS.lock().unwrap().insert(id, "test.ext".to_string());
+    let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+    tokio::spawn(forward_actions(proxy, move |event| {
+      let _ = action_tx.send(event);
+    }));
+
+    // Signals sent before the listener subscribed are lost, so repeat them
+    let emitter = SignalEmitter::new(&server, PATH).unwrap();
+    let event = loop {
+      MockDaemon::action_invoked(&emitter, 999, "open")
+        .await
+        .unwrap();
+      MockDaemon::action_invoked(&emitter, id, "open")
+        .await
+        .unwrap();
+      let received = tokio::time::timeout(Duration::from_millis(50), action_rx.recv()).await;
+      if let Ok(Some(event)) = received {
+        break event;
+      }
+    };
+    assert_eq!(
+      event,
+      NotificationActionEvent {
+        notification_id: id,
+        action_id: "open".to_string(),
+        extension_id: "test.ext".to_string(),
+      }
+    );
+  }
+}
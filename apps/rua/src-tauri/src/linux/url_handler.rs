@@ -0,0 +1,122 @@
+//! URL Handler Module
+//!
+//! Registers rua with the desktop as the handler of `rua://` links, so that
+//! browsers and `xdg-open` launch rua (or forward the link to the running
+//! instance) with the URL as argument.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+use tauri::{AppHandle, Manager};
+
+/// Desktop entry written to `$XDG_DATA_HOME/applications`
+const DESKTOP_FILE_NAME: &str = "rua-url-handler.desktop";
+
+/// MIME type of `rua://` links
+const SCHEME_MIME_TYPE: &str = "x-scheme-handler/rua";
+
+/// Quote an Exec argument as required by the Desktop Entry spec
+///
+/// Backslashes are escaped once more for the string value of the key.
+fn quote_exec_arg(arg: &str) -> String {
+  if !arg.contains(|c: char| c.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(c)) {
+    return arg.to_string();
+  }
+
+  let mut quoted = String::from("\"");
+  for c in arg.chars() {
+    if matches!(c, '"' | '`' | '$' | '\\') {
+      quoted.push('\\');
+    }
+    quoted.push(c);
+  }
+  quoted.push('"');
+  quoted.replace('\\', "\\\\")
+}
+
+/// Make rua the default handler unless the user picked another one
+fn set_default_handler() -> Result<(), String> {
+  let current = Command::new("xdg-mime")
+    .args(["query", "default", SCHEME_MIME_TYPE])
+    .output()
+    .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+  let current = String::from_utf8_lossy(&current.stdout).trim().to_string();
+  if !current.is_empty() {
+    return Ok(());
+  }
+
+  let status = Command::new("xdg-mime")
+    .args(["default", DESKTOP_FILE_NAME, SCHEME_MIME_TYPE])
+    .status()
+    .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+  if !status.success() {
+    return Err(format!("xdg-mime default failed: {}", status));
+  }
+  Ok(())
+}
+
+/// Install the `x-scheme-handler/rua` desktop entry pointing at this executable
+pub fn register_url_handler(app: &AppHandle) -> Result<(), String> {
+  // An AppImage runs from a temporary mount, point at the image itself instead
+  let exe = match std::env::var_os("APPIMAGE") {
+    Some(appimage) => PathBuf::from(appimage),
+    None => std::env::current_exe().map_err(|e| format!("Failed to get executable: {}", e))?,
+  };
+  let applications_dir = app
+    .path()
+    .data_dir()
+    .map_err(|e| format!("Failed to get data dir: {}", e))?
+    .join("applications");
+  let desktop_file = applications_dir.join(DESKTOP_FILE_NAME);
+
+  let content = format!(
+    "[Desktop Entry]\n\
+     Type=Application\n\
+     Name=Rua\n\
+     Comment=Open rua:// links\n\
+     Exec={} %u\n\
+     Terminal=false\n\
+     NoDisplay=true\n\
+     MimeType={};\n",
+    quote_exec_arg(&exe.to_string_lossy()),
+    SCHEME_MIME_TYPE
+  );
+
+  if fs::read_to_string(&desktop_file).ok().as_deref() != Some(content.as_str()) {
+    fs::create_dir_all(&applications_dir)
+      .map_err(|e| format!("Failed to create applications dir: {}", e))?;
+    fs::write(&desktop_file, &content)
+      .map_err(|e| format!("Failed to write {}: {}", desktop_file.display(), e))?;
+    update_desktop_database(&applications_dir);
+  }
+
+  set_default_handler()
+}
+
+/// Refresh the MIME cache so the new entry is picked up right away
+fn update_desktop_database(applications_dir: &Path) {
+  if let Err(e) = Command::new("update-desktop-database")
+    .arg(applications_dir)
+    .status()
+  {
+    eprintln!("Failed to run update-desktop-database: {}", e);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quote_exec_arg() {
+    assert_eq!(quote_exec_arg("/usr/bin/rua"), "/usr/bin/rua");
+    assert_eq!(
+      quote_exec_arg("/home/me/My Apps/rua"),
+      "\"/home/me/My Apps/rua\""
+    );
+    assert_eq!(quote_exec_arg("/opt/$rua"), "\"/opt/\\\\$rua\"");
+  }
+}
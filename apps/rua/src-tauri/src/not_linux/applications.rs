@@ -1,22 +1,43 @@
+use tauri::AppHandle;
+
 use crate::types::Application;
 
+/// Keep the application index up to date (not supported on non-Linux platforms)
+pub fn watch_applications(_app: &AppHandle) {}
+
 #[tauri::command]
 #[cfg(not(target_os = "linux"))]
-pub fn get_applications() -> Vec<Application> {
+pub async fn get_applications() -> Vec<Application> {
   // Return empty on non-Linux platforms
   Vec::new()
 }
 
 #[tauri::command]
 #[cfg(not(target_os = "linux"))]
-pub fn refresh_applications_cache() -> Result<String, String> {
+pub async fn refresh_applications_cache(_app: AppHandle) -> Result<String, String> {
   // No-op on non-Linux platforms
   Ok("Cache refresh not supported on this platform".to_string())
 }
 
 #[tauri::command]
 #[cfg(not(target_os = "linux"))]
-pub fn launch_application(_exec: String, _terminal: bool) -> Result<String, String> {
+pub fn launch_application(
+  _app: AppHandle,
+  _exec: String,
+  _terminal: bool,
+  _working_dir: Option<String>,
+) -> Result<String, String> {
+  // Not supported on non-Linux platforms
+  Err("Application launch not supported on this platform".to_string())
+}
+
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn launch_application_with(
+  _app: AppHandle,
+  _app_id: String,
+  _uris: Vec<String>,
+) -> Result<String, String> {
   // Not supported on non-Linux platforms
   Err("Application launch not supported on this platform".to_string())
 }
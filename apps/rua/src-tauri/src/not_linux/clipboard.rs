@@ -13,3 +13,69 @@ pub fn write_clipboard(_text: String) -> Result<(), String> {
   // No-op on non-Linux platforms
   Ok(())
 }
+
+/// List clipboard formats (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn get_clipboard_formats() -> Result<Vec<String>, String> {
+  Ok(Vec::new())
+}
+
+/// Read a clipboard format (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn read_clipboard_format(_mime_type: String) -> Result<Option<Vec<u8>>, String> {
+  Ok(None)
+}
+
+/// Write a clipboard format (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn write_clipboard_format(_mime_type: String, _data: Vec<u8>) -> Result<(), String> {
+  Ok(())
+}
+
+/// Read a clipboard image (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn read_clipboard_image() -> Result<Option<Vec<u8>>, String> {
+  Ok(None)
+}
+
+/// Write a clipboard image (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn write_clipboard_image(_data: Vec<u8>) -> Result<(), String> {
+  Ok(())
+}
+
+/// Read copied files (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn read_clipboard_files() -> Result<Vec<String>, String> {
+  Ok(Vec::new())
+}
+
+/// Copy files (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn write_clipboard_files(_paths: Vec<String>) -> Result<(), String> {
+  Ok(())
+}
+
+/// Get the selected text (not supported on non-Linux platforms)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn get_selected_text() -> Option<String> {
+  None
+}
+
+/// Watch the clipboard (not supported on non-Linux platforms)
+#[cfg(not(target_os = "linux"))]
+pub fn watch_clipboard(_on_change: impl Fn() + Send + 'static) {}
+
+/// Why clipboard changes can't be watched (not supported on non-Linux platforms)
+#[cfg(not(target_os = "linux"))]
+pub fn clipboard_watch_error() -> Option<String> {
+  Some("Clipboard history is only supported on Linux".to_string())
+}
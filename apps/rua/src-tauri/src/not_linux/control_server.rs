@@ -2,9 +2,9 @@ use anyhow::bail;
 use axum::http::StatusCode;
 use tauri::{Emitter, WebviewWindow};
 
-use crate::control_server::Response;
+use crate::{control_server::Response, types::WindowShownPayload};
 
-pub fn show_window(window: WebviewWindow) -> anyhow::Result<String> {
+pub fn show_window(window: WebviewWindow, payload: WindowShownPayload) -> anyhow::Result<String> {
   if let Err(e) = window.center() {
     eprintln!("Failed to center window: {}", e);
   }
@@ -15,7 +15,7 @@ pub fn show_window(window: WebviewWindow) -> anyhow::Result<String> {
     eprintln!("Failed to focus window: {}", e);
   }
   // Emit window-shown event
-  let _ = window.emit("rua://window-shown", ());
+  let _ = window.emit("rua://window-shown", payload);
   Ok("".to_string())
 }
 
@@ -0,0 +1,20 @@
+use tauri::{
+  http::{Request, Response},
+  UriSchemeContext, UriSchemeResponder, Wry,
+};
+
+#[cfg(not(target_os = "linux"))]
+pub fn handle_icon_protocol(
+  _ctx: UriSchemeContext<'_, Wry>,
+  _request: Request<Vec<u8>>,
+  responder: UriSchemeResponder,
+) {
+  // Icon themes are not supported on non-Linux platforms
+  responder.respond(
+    Response::builder()
+      .status(404)
+      .header("Content-Type", "text/plain")
+      .body(b"Icons not supported on this platform".to_vec())
+      .unwrap(),
+  );
+}
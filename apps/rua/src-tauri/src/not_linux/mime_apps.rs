@@ -0,0 +1,33 @@
+//! MIME Applications Module
+//!
+//! Finds the applications that can open a file.
+
+use serde::Serialize;
+
+/// The type of a file and the applications that can open it, see the Linux
+/// implementation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandlers {
+  pub mime_type: String,
+  pub applications: Vec<String>,
+}
+
+/// Get the default application for a file (not supported on non-Linux platforms)
+pub fn default_application_for_file(_path: &str) -> Option<String> {
+  None
+}
+
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn get_file_handlers(_path: String) -> Result<FileHandlers, String> {
+  // Not supported on non-Linux platforms
+  Err("Open With is not supported on this platform".to_string())
+}
+
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub fn set_default_application(_mime_type: String, _app_id: String) -> Result<String, String> {
+  // Not supported on non-Linux platforms
+  Err("Default applications are not supported on this platform".to_string())
+}
@@ -1,9 +1,17 @@
 mod applications;
 mod clipboard;
 mod control_server;
+mod icon_protocol;
+mod mime_apps;
 mod notification;
 mod shell_executor;
+mod url_handler;
 
 pub use applications::*;
 pub use clipboard::*;
+pub use control_server::*;
+pub use icon_protocol::*;
+pub use mime_apps::*;
+pub use notification::*;
 pub use shell_executor::*;
+pub use url_handler::*;
@@ -2,9 +2,33 @@
 //!
 //! Provides system notification functionality for extensions.
 
+use serde::Deserialize;
+use tauri::AppHandle;
+
+/// Optional notification settings, see the Linux implementation
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationOptions {}
+
+/// Show a plain notification from the backend (not supported on non-Linux platforms yet)
+pub fn notify(_title: &str, _body: &str) {}
+
 /// Show a system notification (not supported on non-Linux platforms yet)
 #[tauri::command]
-pub fn show_notification(_title: String, _body: Option<String>) -> Result<(), String> {
+#[cfg(not(target_os = "linux"))]
+pub async fn show_notification(
+  _app: AppHandle,
+  _title: String,
+  _body: Option<String>,
+  _options: Option<NotificationOptions>,
+  _extension_id: Option<String>,
+) -> Result<u32, String> {
   // TODO: Implement for other platforms
+  Ok(0)
+}
+
+/// Close a notification (not supported on non-Linux platforms yet)
+#[tauri::command]
+#[cfg(not(target_os = "linux"))]
+pub async fn close_notification(_id: u32) -> Result<(), String> {
   Ok(())
 }
@@ -0,0 +1,11 @@
+//! URL Handler Module
+//!
+//! Registers rua as the handler of `rua://` links.
+
+use tauri::AppHandle;
+
+/// Register the `rua://` URL handler (not supported on non-Linux platforms yet)
+pub fn register_url_handler(_app: &AppHandle) -> Result<(), String> {
+  // TODO: Implement for other platforms
+  Ok(())
+}
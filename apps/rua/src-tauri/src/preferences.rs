@@ -10,7 +10,7 @@ use serde_json::Value;
 use tauri::{AppHandle, Manager};
 
 /// Get the preferences file path
-fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn get_preferences_path(app: &AppHandle) -> Result<PathBuf, String> {
   let app_config_dir = app
     .path()
     .app_config_dir()
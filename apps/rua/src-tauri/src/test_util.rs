@@ -0,0 +1,44 @@
+//! Test Utilities
+//!
+//! Fixtures shared by the unit tests.
+
+use std::{
+  fs,
+  ops::Deref,
+  path::{Path, PathBuf},
+};
+
+/// A fresh directory under the system temp dir, removed with its contents when
+/// dropped, so failing tests clean up as well
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  /// Create an empty directory named after the test fixture and the process
+  pub fn new(name: &str) -> Self {
+    let path = std::env::temp_dir().join(format!("rua-test-{}-{}", name, std::process::id()));
+    // Left behind by an aborted run with a reused PID
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    Self(path)
+  }
+}
+
+impl Deref for TempDir {
+  type Target = Path;
+
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl AsRef<Path> for TempDir {
+  fn as_ref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
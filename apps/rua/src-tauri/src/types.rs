@@ -1,13 +1,35 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Application {
+  /// Desktop file ID, e.g. `org.gnome.Nautilus.desktop`
+  pub id: String,
   pub name: String,
   pub exec: String,
+  /// Icon name or absolute path, to load over `icon://`
   pub icon: Option<String>,
   pub description: Option<String>,
   pub path: String,
   pub terminal: bool,
+  /// Generic name, e.g. "Web Browser"
+  pub generic_name: Option<String>,
+  pub keywords: Vec<String>,
+  pub categories: Vec<String>,
+  /// Directory to run the application in
+  pub working_dir: Option<String>,
+  /// WM class of the application's windows
+  pub startup_wm_class: Option<String>,
+  /// Additional ways to start the application, e.g. with a private window
+  pub actions: Vec<ApplicationAction>,
+}
+
+/// A `[Desktop Action]` of an application
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplicationAction {
+  pub id: String,
+  pub name: String,
+  pub exec: String,
+  pub icon: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -17,3 +39,16 @@ pub struct ShellResult {
   pub stderr: String,
   pub exit_code: Option<i32>,
 }
+
+/// Payload of the `rua://window-shown` event
+///
+/// `query` pre-fills the search box and `view` opens an action (e.g. `ext.id.action`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowShownPayload {
+  pub query: Option<String>,
+  pub view: Option<String>,
+  /// Text selected in other apps when the window was shown, filled in by
+  /// `show_window` and never taken from requests
+  #[serde(skip_deserializing)]
+  pub selection: Option<String>,
+}
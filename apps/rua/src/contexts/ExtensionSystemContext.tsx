@@ -12,8 +12,8 @@ import {
   type ReactNode,
 } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { toast } from "@fzdwx/ruaui";
-import { useFileWatcher, type FileChangeEvent } from "@/hooks/useFileWatcher";
 import {
   executeBackgroundScript,
   setBackgroundCallbacks,
@@ -22,10 +22,14 @@ import {
   notifySearchChange as notifySearchChangeBackground,
   cleanupExtension as cleanupBackgroundExtension,
   isBackgroundScriptLoaded,
+  getBackgroundScriptState,
+  runBackgroundAction,
+  notifyNotificationAction as notifyNotificationActionBackground,
 } from "@/extension/background-executor.ts";
 import {
   notifyViewExtensionsActivate,
   notifyViewExtensionsDeactivate,
+  notifyViewExtensionNotificationAction,
 } from "@/extension/view-extension-manager.ts";
 
 // Import types from rua-api package
@@ -36,6 +40,7 @@ import {
   ExtensionManifest,
   ExtensionPermission,
   ParsedPermission,
+  NotificationActionData,
 } from "rua-api";
 
 /**
@@ -53,6 +58,23 @@ export interface ExtensionInfo {
   error?: string;
 }
 
+/**
+ * Payload of the `rua://run-action` event emitted by the backend
+ */
+interface RunActionEvent {
+  requestId: string;
+  extensionId: string;
+  actionName: string;
+  args: Record<string, string>;
+}
+
+/**
+ * Payload of the `rua://notification-action` event emitted by the backend
+ */
+interface NotificationActionEvent extends NotificationActionData {
+  extensionId: string;
+}
+
 /**
  * Extension system context value
  */
@@ -207,6 +229,15 @@ async function loadDevExtension(devPath: string): Promise<ExtensionInfo | null>
   }
 }
 
+/**
+ * Report an error of the dev extension so `ruactl dev` can print it
+ */
+function reportDevExtensionError(extensionId: string | null, source: string, message: string) {
+  invoke("report_dev_extension_error", { extensionId, source, message }).catch((error) => {
+    console.error("Failed to report dev extension error:", error);
+  });
+}
+
 /**
  * Extension system extension component
  */
@@ -216,13 +247,12 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
   const [plugins, setPlugins] = useState<ExtensionInfo[]>([]);
   const [pluginActions, setPluginActions] = useState<ManifestDerivedAction[]>([]);
   const [extensionsPath, setExtensionsPath] = useState<string | null>(null);
-  const [devExtensionPath, setDevExtensionPathState] = useState<string | null>(() => {
-    // Restore from localStorage
-    if (typeof window !== "undefined") {
-      return localStorage.getItem("rua:devExtensionPath");
-    }
-    return null;
-  });
+  // Dev extension path, persisted by the backend so it survives restarts
+  const [devExtensionPath, setDevExtensionPathState] = useState<string | null>(null);
+  const [devPathRestored, setDevPathRestored] = useState(false);
+
+  // ID of the currently loaded dev extension
+  const devExtensionIdRef = useRef<string | null>(null);
 
   // Dev mode hot reload state
   const [devRefreshKey, setDevRefreshKey] = useState(0);
@@ -230,20 +260,6 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
   // Dynamic actions registered by extensions at runtime
   const [dynamicActions, setDynamicActions] = useState<Map<string, DynamicAction[]>>(new Map());
 
-  // Track if we should start watching after initial load
-  const shouldStartWatchingRef = useRef(false);
-
-  // File change handler - increment refresh key to trigger hot reload
-  const handleFileChange = useCallback((event: FileChangeEvent) => {
-    console.log("[ExtensionSystemContext] File changed:", event.path);
-    setDevRefreshKey((prev) => prev + 1);
-  }, []);
-
-  // File watcher hook
-  const { isWatching, startWatching, stopWatching } = useFileWatcher({
-    onFileChange: handleFileChange,
-  });
-
   // Register dynamic actions callback (called from init scripts)
   const registerDynamicActionsRef = useRef<
     ((extensionId: string, actions: DynamicAction[]) => void) | undefined
@@ -276,10 +292,12 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
 
       // Load dev extension if path is set
       let allExtensions = [...extensions];
+      devExtensionIdRef.current = null;
       if (devExtensionPath) {
         try {
           const devExt = await loadDevExtension(devExtensionPath);
           if (devExt) {
+            devExtensionIdRef.current = devExt.manifest.id;
             // Remove any existing extension with same ID
             allExtensions = allExtensions.filter((e) => e.manifest.id !== devExt.manifest.id);
             // Add dev extension with a marker
@@ -290,9 +308,8 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
           const errorMessage = error instanceof Error ? error.message : String(error);
           toast.show(`Failed to load dev extension: ${errorMessage}`, "failure");
           console.error("Failed to load dev extension:", error);
-          // Clear the invalid dev path
-          localStorage.removeItem("rua:devExtensionPath");
-          setDevExtensionPathState(null);
+          // Keep the path registered so the extension loads again once it's fixed
+          reportDevExtensionError(null, "manifest", errorMessage);
         }
       }
 
@@ -331,7 +348,7 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
               ext.manifest.permissions || []
             );
 
-            await executeBackgroundScript(
+            const loaded = await executeBackgroundScript(
               ext.manifest.id,
               ext.path,
               backgroundAction.script,
@@ -340,6 +357,14 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
               permissions,
               parsedPermissions
             );
+            if (!loaded && ext.manifest.id === devExtensionIdRef.current) {
+              const state = getBackgroundScriptState(ext.manifest.id);
+              reportDevExtensionError(
+                ext.manifest.id,
+                "background",
+                state?.error ?? "Failed to load background script"
+              );
+            }
           } catch (error) {
             console.error(
               `[ExtensionSystemContext] Failed to execute background script for ${ext.manifest.id}:`,
@@ -367,18 +392,10 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
     }
   }, [devExtensionPath]);
 
-  // Set dev extension path and reload extensions
+  // Set dev extension path; the backend validates and persists it, watches
+  // the directory and notifies all windows via rua://dev-extension-changed
   const setDevExtensionPath = useCallback(
     async (path: string | null) => {
-      // Stop watching the old path
-      if (isWatching) {
-        try {
-          await stopWatching();
-        } catch (error) {
-          console.error("Failed to stop file watcher:", error);
-        }
-      }
-
       // If stopping dev mode, clean up the dev extension's background script
       // and uninstall the dev extension from extensions directory if it exists
       if (!path && devExtensionPath) {
@@ -416,54 +433,88 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
         }
       }
 
-      setDevExtensionPathState(path);
-      if (path) {
-        localStorage.setItem("rua:devExtensionPath", path);
-        // Mark that we should start watching after extensions are loaded
-        shouldStartWatchingRef.current = true;
-      } else {
-        localStorage.removeItem("rua:devExtensionPath");
-        shouldStartWatchingRef.current = false;
+      try {
+        await invoke("set_dev_extension", { path });
+      } catch (error) {
+        const errorMessage = error instanceof Error ? error.message : String(error);
+        toast.show(`Failed to load dev extension: ${errorMessage}`, "failure");
+        console.error("Failed to set dev extension:", error);
       }
       // Note: loadExtensions will be called automatically via useEffect
-      // because it depends on devExtensionPath
+      // once the change event updates devExtensionPath
     },
-    [isWatching, stopWatching, devExtensionPath, plugins, extensionsPath]
+    [devExtensionPath, plugins, extensionsPath]
   );
 
-  // Initialize extension system
+  // Restore the dev extension path from the backend, migrating the path
+  // previously kept in localStorage
   useEffect(() => {
-    loadExtensions();
-  }, [loadExtensions]);
+    const restoreDevExtensionPath = async () => {
+      let path: string | null = null;
+      try {
+        path = await invoke<string | null>("get_dev_extension");
+        const legacyPath = localStorage.getItem("rua:devExtensionPath");
+        if (legacyPath) {
+          localStorage.removeItem("rua:devExtensionPath");
+          if (!path) {
+            await invoke("set_dev_extension", { path: legacyPath });
+            path = legacyPath;
+          }
+        }
+      } catch (error) {
+        console.error("Failed to restore dev extension path:", error);
+      }
+      setDevExtensionPathState(path);
+      setDevPathRestored(true);
+    };
+
+    restoreDevExtensionPath();
+  }, []);
 
-  // Start/stop file watcher based on dev extension path
+  // Follow dev extension registration (from this UI or `ruactl dev`) and hot
+  // reload on changes reported by the backend file watcher
   useEffect(() => {
-    // Only start watching after initial load is complete
-    if (!initialized) return;
+    const unlistenChanged = listen<{ path: string | null }>(
+      "rua://dev-extension-changed",
+      (event) => {
+        const { path } = event.payload;
+        if (path === devExtensionPath) {
+          // Re-registered, restart it like a file change
+          setDevRefreshKey((prev) => prev + 1);
+          return;
+        }
 
-    const setupWatcher = async () => {
-      if (devExtensionPath && shouldStartWatchingRef.current) {
-        try {
-          await startWatching(devExtensionPath);
-          console.log("[ExtensionSystemContext] Started watching:", devExtensionPath);
-          shouldStartWatchingRef.current = false;
-        } catch (error) {
-          console.error("Failed to start file watcher:", error);
+        const previousId = devExtensionIdRef.current;
+        if (previousId) {
+          cleanupBackgroundExtension(previousId);
+          setDynamicActions((prev) => {
+            const newMap = new Map(prev);
+            newMap.delete(previousId);
+            return newMap;
+          });
         }
+        setDevExtensionPathState(path);
       }
-    };
+    );
+    const unlistenReload = listen<{ extensionId: string; paths: string[] }>(
+      "rua://dev-extension-reload",
+      (event) => {
+        console.log("[ExtensionSystemContext] Dev files changed:", event.payload.paths);
+        setDevRefreshKey((prev) => prev + 1);
+      }
+    );
 
-    setupWatcher();
-  }, [initialized, devExtensionPath, startWatching]);
+    return () => {
+      unlistenChanged.then((fn) => fn());
+      unlistenReload.then((fn) => fn());
+    };
+  }, [devExtensionPath]);
 
-  // Start watching on initial load if dev path is set
+  // Initialize extension system
   useEffect(() => {
-    if (initialized && devExtensionPath && !isWatching) {
-      startWatching(devExtensionPath).catch((error) => {
-        console.error("Failed to start file watcher on init:", error);
-      });
-    }
-  }, [initialized, devExtensionPath, isWatching, startWatching]);
+    if (!devPathRestored) return;
+    loadExtensions();
+  }, [devPathRestored, loadExtensions]);
 
   // Reload dev extension's background script when files change
   useEffect(() => {
@@ -501,7 +552,7 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
           devExt.manifest.permissions || []
         );
 
-        await executeBackgroundScript(
+        const loaded = await executeBackgroundScript(
           devExt.manifest.id,
           devExt.path,
           backgroundAction.script,
@@ -510,9 +561,20 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
           permissions,
           parsedPermissions
         );
-        console.log("[ExtensionSystemContext] Dev background script reloaded successfully");
+        if (loaded) {
+          console.log("[ExtensionSystemContext] Dev background script reloaded successfully");
+        } else {
+          const state = getBackgroundScriptState(devExt.manifest.id);
+          reportDevExtensionError(
+            devExt.manifest.id,
+            "background",
+            state?.error ?? "Failed to load background script"
+          );
+        }
       } catch (error) {
         console.error("[ExtensionSystemContext] Failed to reload dev background script:", error);
+        const errorMessage = error instanceof Error ? error.message : String(error);
+        reportDevExtensionError(devExt.manifest.id, "background", errorMessage);
       }
     };
 
@@ -662,6 +724,77 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
     };
   }, [registerDynamicActions, unregisterDynamicActions]);
 
+  // Run actions requested by the backend (e.g. `ruactl run`) and report the result
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<RunActionEvent>("rua://run-action", async (event) => {
+      const { requestId, extensionId, actionName, args } = event.payload;
+      try {
+        const result = await runBackgroundAction(extensionId, actionName, args);
+        await invoke("resolve_action_run", { requestId, result, error: null });
+      } catch (error) {
+        const errorMessage = error instanceof Error ? error.message : String(error);
+        await invoke("resolve_action_run", { requestId, result: null, error: errorMessage });
+      }
+    }).then((unlistenFn) => {
+      unlisten = unlistenFn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, []);
+
+  // Deliver clicked notification actions to the extension that showed the notification
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<NotificationActionEvent>("rua://notification-action", (event) => {
+      const { extensionId, ...data } = event.payload;
+      notifyNotificationActionBackground(extensionId, data);
+      notifyViewExtensionNotificationAction(extensionId, data);
+    }).then((unlistenFn) => {
+      unlisten = unlistenFn;
+    });
+
+    return () => {
+      unlisten?.();
+    };
+  }, []);
+
+  // Reload when extensions change outside the UI (e.g. `ruactl ext`)
+  useEffect(() => {
+    const events = [
+      "rua://extension-installed",
+      "rua://extension-uninstalled",
+      "rua://extension-enabled",
+      "rua://extension-disabled",
+    ];
+    const unlisteners: Array<() => void> = [];
+
+    for (const eventName of events) {
+      listen<{ id: string }>(eventName, async (event) => {
+        if (eventName === "rua://extension-uninstalled" || eventName === "rua://extension-disabled") {
+          const extensionId = event.payload.id;
+          setDynamicActions((prev) => {
+            const newMap = new Map(prev);
+            newMap.delete(extensionId);
+            return newMap;
+          });
+          cleanupBackgroundExtension(extensionId);
+        }
+        await loadExtensions();
+      }).then((unlistenFn) => {
+        unlisteners.push(unlistenFn);
+      });
+    }
+
+    return () => {
+      unlisteners.forEach((unlistenFn) => unlistenFn());
+    };
+  }, [loadExtensions]);
+
   // Notify functions that call both background and view extensions
   const notifyActivate = useCallback(async () => {
     await Promise.all([notifyActivateBackground(), notifyViewExtensionsActivate()]);
@@ -683,7 +816,7 @@ export function ExtensionSystemProvider({ children }: ExtensionSystemProviderPro
     extensionsPath,
     devExtensionPath,
     setDevExtensionPath,
-    devWatcherActive: isWatching,
+    devWatcherActive: devExtensionPath !== null,
     devRefreshKey,
     dynamicActions,
     registerDynamicActions,
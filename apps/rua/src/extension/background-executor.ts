@@ -14,6 +14,8 @@ import {
   ShellResult,
   ActionTriggeredData,
   SearchChangeData,
+  NotificationOptions,
+  NotificationActionData,
   DirEntry,
   FileStat,
   BackgroundScriptCallbacks,
@@ -85,12 +87,50 @@ export function createMainContextRuaAPI(
         checkPermission("clipboard");
         await apiCore.clipboardWriteText(text);
       },
+      async getFormats(): Promise<string[]> {
+        checkPermission("clipboard");
+        return await apiCore.clipboardGetFormats();
+      },
+      async read(mimeType: string): Promise<Uint8Array | null> {
+        checkPermission("clipboard");
+        const data = await apiCore.clipboardRead(mimeType);
+        return data ? new Uint8Array(data) : null;
+      },
+      async write(mimeType: string, data: Uint8Array): Promise<void> {
+        checkPermission("clipboard");
+        await apiCore.clipboardWrite(mimeType, data);
+      },
+      async readImage(): Promise<Uint8Array | null> {
+        checkPermission("clipboard");
+        const data = await apiCore.clipboardReadImage();
+        return data ? new Uint8Array(data) : null;
+      },
+      async writeImage(png: Uint8Array): Promise<void> {
+        checkPermission("clipboard");
+        await apiCore.clipboardWriteImage(png);
+      },
+      async readFiles(): Promise<string[]> {
+        checkPermission("clipboard");
+        return await apiCore.clipboardReadFiles();
+      },
+      async writeFiles(paths: string[]): Promise<void> {
+        checkPermission("clipboard");
+        await apiCore.clipboardWriteFiles(paths);
+      },
+      async readSelection(): Promise<string | null> {
+        checkPermission("clipboard");
+        return await apiCore.clipboardReadSelection();
+      },
     },
 
     notification: {
-      async show(options: { title: string; body?: string }): Promise<void> {
+      async show(options: NotificationOptions): Promise<number> {
         checkPermission("notification");
-        await apiCore.notificationShow(options);
+        return await apiCore.notificationShow(options, extensionInfo.id);
+      },
+      async close(id: number): Promise<void> {
+        checkPermission("notification");
+        await apiCore.notificationClose(id);
       },
     },
 
@@ -197,8 +237,15 @@ export function createMainContextRuaAPI(
     },
 
     on(
-      event: "activate" | "deactivate" | "action-triggered" | "search-change",
-      callback: (() => void) | ((data: ActionTriggeredData | SearchChangeData) => void)
+      event:
+        | "activate"
+        | "deactivate"
+        | "action-triggered"
+        | "search-change"
+        | "notification-action",
+      callback:
+        | (() => void)
+        | ((data: ActionTriggeredData | SearchChangeData | NotificationActionData) => void)
     ): void {
       if (event === "activate") {
         state.activateCallbacks.add(callback as () => void);
@@ -208,12 +255,21 @@ export function createMainContextRuaAPI(
         state.actionTriggeredCallbacks.add(callback as (data: ActionTriggeredData) => void);
       } else if (event === "search-change") {
         state.searchChangeCallbacks.add(callback as (data: SearchChangeData) => void);
+      } else if (event === "notification-action") {
+        state.notificationActionCallbacks.add(callback as (data: NotificationActionData) => void);
       }
     },
 
     off(
-      event: "activate" | "deactivate" | "action-triggered" | "search-change",
-      callback: (() => void) | ((data: ActionTriggeredData | SearchChangeData) => void)
+      event:
+        | "activate"
+        | "deactivate"
+        | "action-triggered"
+        | "search-change"
+        | "notification-action",
+      callback:
+        | (() => void)
+        | ((data: ActionTriggeredData | SearchChangeData | NotificationActionData) => void)
     ): void {
       if (event === "activate") {
         state.activateCallbacks.delete(callback as () => void);
@@ -223,6 +279,10 @@ export function createMainContextRuaAPI(
         state.actionTriggeredCallbacks.delete(callback as (data: ActionTriggeredData) => void);
       } else if (event === "search-change") {
         state.searchChangeCallbacks.delete(callback as (data: SearchChangeData) => void);
+      } else if (event === "notification-action") {
+        state.notificationActionCallbacks.delete(
+          callback as (data: NotificationActionData) => void
+        );
       }
     },
   };
@@ -274,6 +334,7 @@ export async function executeBackgroundScript(
     deactivateCallbacks: new Set(),
     actionTriggeredCallbacks: new Set(),
     searchChangeCallbacks: new Set(),
+    notificationActionCallbacks: new Set(),
     registeredActions: [],
   };
 
@@ -433,6 +494,72 @@ export async function notifyActionTriggered(
   await Promise.allSettled(promises);
 }
 
+/**
+ * Notify an extension that an action button of its notification was clicked
+ * @param extensionId - The extension that showed the notification
+ * @param data - The notification and action IDs
+ */
+export async function notifyNotificationAction(
+  extensionId: string,
+  data: NotificationActionData
+): Promise<void> {
+  const state = backgroundScripts.get(extensionId);
+  if (!state?.loaded) return;
+
+  console.log("[BackgroundExecutor] Notifying notification-action:", extensionId, data.actionId);
+
+  const promises: Promise<void>[] = [];
+
+  for (const callback of state.notificationActionCallbacks) {
+    promises.push(
+      (async () => {
+        try {
+          callback(data);
+        } catch (error) {
+          console.warn(
+            "[BackgroundExecutor] Failed to notify notification-action:",
+            extensionId,
+            error
+          );
+        }
+      })()
+    );
+  }
+
+  await Promise.allSettled(promises);
+}
+
+/**
+ * Run an extension action on behalf of the backend (e.g. `ruactl run`)
+ * Unlike notifyActionTriggered, callback errors propagate and the first
+ * non-undefined value returned by a callback becomes the action result.
+ * @param extensionId - The extension ID
+ * @param actionId - The action ID (without extension prefix)
+ * @param context - Arguments passed to the action
+ */
+export async function runBackgroundAction(
+  extensionId: string,
+  actionId: string,
+  context?: unknown
+): Promise<unknown> {
+  const state = backgroundScripts.get(extensionId);
+  if (!state?.loaded) {
+    throw new Error(`Background script not loaded for extension: ${extensionId}`);
+  }
+  if (state.actionTriggeredCallbacks.size === 0) {
+    throw new Error(`Extension ${extensionId} does not handle action-triggered events`);
+  }
+
+  console.log("[BackgroundExecutor] Running action:", extensionId, actionId);
+
+  const data: ActionTriggeredData = { actionId, context };
+  const results: unknown[] = await Promise.all(
+    Array.from(state.actionTriggeredCallbacks, async (callback) => callback(data))
+  );
+
+  return results.find((result) => result !== undefined) ?? null;
+}
+
 /**
  * Notify all extensions that the search input value has changed
  * @param query - The current search query
@@ -485,6 +612,7 @@ export function cleanupExtension(extensionId: string): void {
   state.deactivateCallbacks.clear();
   state.actionTriggeredCallbacks.clear();
   state.searchChangeCallbacks.clear();
+  state.notificationActionCallbacks.clear();
 
   // Remove from registry
   backgroundScripts.delete(extensionId);
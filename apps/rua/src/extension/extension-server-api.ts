@@ -13,6 +13,7 @@ import {
   ShellResult,
   FileStat,
   DirEntry,
+  NotificationOptions,
 } from "rua-api";
 import {
   apiCore,
@@ -83,10 +84,55 @@ export function createRuaAPI(
       await apiCore.clipboardWriteText(text);
     },
 
+    async clipboardGetFormats(): Promise<string[]> {
+      checkPermission("clipboard");
+      return await apiCore.clipboardGetFormats();
+    },
+
+    async clipboardRead(mimeType: string): Promise<Uint8Array | null> {
+      checkPermission("clipboard");
+      return await apiCore.clipboardRead(mimeType);
+    },
+
+    async clipboardWrite(mimeType: string, data: Uint8Array): Promise<void> {
+      checkPermission("clipboard");
+      await apiCore.clipboardWrite(mimeType, data);
+    },
+
+    async clipboardReadImage(): Promise<Uint8Array | null> {
+      checkPermission("clipboard");
+      return await apiCore.clipboardReadImage();
+    },
+
+    async clipboardWriteImage(data: Uint8Array): Promise<void> {
+      checkPermission("clipboard");
+      await apiCore.clipboardWriteImage(data);
+    },
+
+    async clipboardReadFiles(): Promise<string[]> {
+      checkPermission("clipboard");
+      return await apiCore.clipboardReadFiles();
+    },
+
+    async clipboardWriteFiles(paths: string[]): Promise<void> {
+      checkPermission("clipboard");
+      await apiCore.clipboardWriteFiles(paths);
+    },
+
+    async clipboardReadSelection(): Promise<string | null> {
+      checkPermission("clipboard");
+      return await apiCore.clipboardReadSelection();
+    },
+
     // Notification API
-    async notificationShow(options: { title: string; body?: string }): Promise<void> {
+    async notificationShow(options: NotificationOptions): Promise<number> {
+      checkPermission("notification");
+      return await apiCore.notificationShow(options, extensionInfo.id);
+    },
+
+    async notificationClose(id: number): Promise<void> {
       checkPermission("notification");
-      await apiCore.notificationShow(options);
+      await apiCore.notificationClose(id);
     },
 
     // Storage API
@@ -6,7 +6,14 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
-import { DirEntry, ExtensionHostInfo, FileStat, ParsedPermission, ShellResult } from "rua-api";
+import {
+  DirEntry,
+  ExtensionHostInfo,
+  FileStat,
+  NotificationOptions,
+  ParsedPermission,
+  ShellResult,
+} from "rua-api";
 
 /**
  * Core API implementations - direct Tauri invoke calls
@@ -21,9 +28,51 @@ export const apiCore = {
     await invoke("write_clipboard", { text });
   },
 
+  async clipboardGetFormats(): Promise<string[]> {
+    return await invoke<string[]>("get_clipboard_formats");
+  },
+
+  async clipboardRead(mimeType: string): Promise<Uint8Array | null> {
+    return await invoke<Uint8Array | null>("read_clipboard_format", { mimeType });
+  },
+
+  async clipboardWrite(mimeType: string, data: Uint8Array): Promise<void> {
+    await invoke("write_clipboard_format", { mimeType, data });
+  },
+
+  async clipboardReadImage(): Promise<Uint8Array | null> {
+    return await invoke<Uint8Array | null>("read_clipboard_image");
+  },
+
+  async clipboardWriteImage(data: Uint8Array): Promise<void> {
+    await invoke("write_clipboard_image", { data });
+  },
+
+  async clipboardReadFiles(): Promise<string[]> {
+    return await invoke<string[]>("read_clipboard_files");
+  },
+
+  async clipboardWriteFiles(paths: string[]): Promise<void> {
+    await invoke("write_clipboard_files", { paths });
+  },
+
+  async clipboardReadSelection(): Promise<string | null> {
+    return await invoke<string | null>("get_selected_text");
+  },
+
   // Notification
-  async notificationShow(options: { title: string; body?: string }): Promise<void> {
-    await invoke("show_notification", options);
+  async notificationShow(options: NotificationOptions, extensionId: string): Promise<number> {
+    const { title, body, ...rest } = options;
+    return await invoke<number>("show_notification", {
+      title,
+      body,
+      options: rest,
+      extensionId,
+    });
+  },
+
+  async notificationClose(id: number): Promise<void> {
+    await invoke("close_notification", { id });
   },
 
   // Storage
@@ -2,12 +2,13 @@
  * View Extension Manager
  *
  * Manages the currently active view mode extension and provides notification functionality.
- * This allows the ExtensionSystemContext to notify the active view extension of activate/deactivate events.
+ * This allows the ExtensionSystemContext to notify the active view extension of activate/deactivate
+ * and notification-action events.
  *
  * Note: Only one view extension can be active at a time.
  */
 
-import type { RuaClientCallbacks } from "rua-api";
+import type { NotificationActionData, RuaClientCallbacks } from "rua-api";
 
 // Currently active view extension and its RPC API
 let activeViewExtension: { extensionId: string; clientAPI: RuaClientCallbacks } | null = null;
@@ -76,3 +77,24 @@ export async function notifyViewExtensionsDeactivate(): Promise<void> {
     console.warn("[ViewExtensionManager] Failed to notify deactivate:", extensionId, error);
   }
 }
+
+/**
+ * Notify the active view extension that an action button of its notification was clicked
+ * Ignored when the extension that showed the notification isn't the active one
+ */
+export async function notifyViewExtensionNotificationAction(
+  extensionId: string,
+  data: NotificationActionData
+): Promise<void> {
+  if (activeViewExtension?.extensionId !== extensionId) return;
+
+  try {
+    activeViewExtension.clientAPI.onNotificationAction?.(data);
+  } catch (error) {
+    console.warn(
+      "[ViewExtensionManager] Failed to notify notification-action:",
+      extensionId,
+      error
+    );
+  }
+}
@@ -6,6 +6,7 @@ import { useExtensionActionsForPalette } from "@/hooks/useExtensionActions";
 import { useFileSearch } from "@/hooks/useFileSearch";
 import { useTheme } from "@/hooks/useTheme";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { invoke } from "@tauri-apps/api/core";
 import { useActionUsage } from "@/hooks/useActionUsage";
 import { DefaultView } from "./DefaultView";
 import { AnimatePresence } from "motion/react";
@@ -16,6 +17,14 @@ import { ViewContext } from "./viewContext";
 import { useExtensionSystem } from "@/contexts/ExtensionSystemContext";
 import { Background, Container } from "@fzdwx/ruaui";
 
+/** Payload of the `rua://window-shown` event */
+interface WindowShownPayload {
+  query?: string | null;
+  view?: string | null;
+  /** Text selected in other apps when the window was shown */
+  selection?: string | null;
+}
+
 export default function Home() {
   const [search, setSearch] = useState("");
   const [actionLoading, setActionLoading] = useState(false);
@@ -27,7 +36,8 @@ export default function Home() {
   const lastActiveMainActionRef = useRef<ActionImpl | null>(null); // Store last active main action for passing data to edit action
   const { theme, toggleTheme } = useTheme();
   const { incrementUsage } = useActionUsage();
-  const { notifyActivate, notifyDeactivate, notifySearchChange } = useExtensionSystem();
+  const { initialized, notifyActivate, notifyDeactivate, notifySearchChange } =
+    useExtensionSystem();
 
   // Initialize action store
   const { useRegisterActions, setRootActionId, setActiveIndex, state } = useActionStore();
@@ -74,8 +84,17 @@ export default function Home() {
     let unlistenHide: (() => void) | undefined;
 
     // Listen for window-shown event (custom event from control_server)
+    // The payload may carry a query and a target view (e.g. from `ruactl show`)
     getCurrentWebviewWindow()
-      .listen("rua://window-shown", () => {
+      .listen<WindowShownPayload | null>("rua://window-shown", (event) => {
+        const { query, view } = event.payload ?? {};
+        if (view) {
+          setRootActionId(view);
+          setSearch(query ?? "");
+        } else if (query != null) {
+          setRootActionId(null);
+          setSearch(query);
+        }
         document.body.focus();
         notifyActivate();
       })
@@ -96,7 +115,15 @@ export default function Home() {
       unlistenShow?.();
       unlistenHide?.();
     };
-  }, [notifyActivate, notifyDeactivate]);
+  }, [notifyActivate, notifyDeactivate, setRootActionId]);
+
+  // Handle rua:// links received while the window was loading (e.g. on first launch)
+  useEffect(() => {
+    if (!initialized) return;
+    invoke("deep_links_ready").catch((error) => {
+      console.error("Failed to handle pending deep links:", error);
+    });
+  }, [initialized]);
 
   // Notify extensions when search input changes
   useEffect(() => {
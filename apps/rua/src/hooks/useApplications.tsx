@@ -1,15 +1,67 @@
 import { useCallback, useEffect, useMemo, useState } from "react";
 import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Action } from "@fzdwx/ruaui";
 import { useActionUsage } from "@/hooks/useActionUsage";
 
+/**
+ * A `[Desktop Action]` of an application, e.g. opening a private window
+ */
+export interface ApplicationAction {
+  id: string;
+  name: string;
+  exec: string;
+  icon?: string;
+}
+
 export interface Application {
+  /** Desktop file ID, e.g. "org.gnome.Nautilus.desktop" */
+  id: string;
   name: string;
   exec: string;
+  /** Icon name from the icon theme or absolute path */
   icon?: string;
   description?: string;
   path: string;
   terminal: boolean;
+  generic_name?: string;
+  keywords: string[];
+  categories: string[];
+  working_dir?: string;
+  startup_wm_class?: string;
+  actions: ApplicationAction[];
+}
+
+/**
+ * Payload of the `rua://applications-changed` event emitted by the backend
+ */
+interface ApplicationsChangedEvent {
+  added: Application[];
+  updated: Application[];
+  /** Desktop file IDs of the removed applications */
+  removed: string[];
+}
+
+/**
+ * Apply the changes of an applications changed event, keeping the list sorted by name
+ */
+function applyChanges(apps: Application[], changes: ApplicationsChangedEvent): Application[] {
+  const replaced = new Set([...changes.removed, ...changes.updated.map((app) => app.id)]);
+  const kept = apps.filter((app) => !replaced.has(app.id));
+  return [...kept, ...changes.added, ...changes.updated].sort((a, b) =>
+    a.name.toLowerCase().localeCompare(b.name.toLowerCase())
+  );
+}
+
+/** Pixel size icons are loaded at, larger than shown for HiDPI screens */
+const ICON_SIZE = 48;
+
+/**
+ * Get the icon:// URL of an icon name or path
+ */
+export function toIconSrc(icon?: string): string | null {
+  if (!icon) return null;
+  return `${convertFileSrc(icon, "icon")}?size=${ICON_SIZE}`;
 }
 
 /**
@@ -26,14 +78,20 @@ async function loadApplications(): Promise<Application[]> {
 }
 
 /**
- * Launch an application and hide the window
+ * Launch an application, or one of its actions, and hide the window
  */
-async function launchApplication(app: Application): Promise<void> {
+async function launchApplication(app: Application, action?: ApplicationAction): Promise<void> {
   try {
-    await invoke("launch_application", {
-      exec: app.exec,
-      terminal: app.terminal,
-    });
+    if (action) {
+      await invoke("launch_application", {
+        exec: action.exec,
+        terminal: app.terminal,
+        workingDir: app.working_dir,
+      });
+    } else {
+      // Launched by ID so %c, %i and %k get the entry's values
+      await invoke("launch_application_with", { appId: app.id, uris: [] });
+    }
     // Hide window after launching
     const { getCurrentWindow } = await import("@tauri-apps/api/window");
     getCurrentWindow().hide();
@@ -65,13 +123,21 @@ export function useApplications() {
       }
     };
     load();
+
+    // Installed, removed and changed applications are pushed by the backend
+    const unlisten = listen<ApplicationsChangedEvent>("rua://applications-changed", (event) => {
+      setApplications((apps) => applyChanges(apps, event.payload));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   // Launch application callback
   const handleLaunchApplication = useCallback(
-    async (app: Application, actionId: string) => {
+    async (app: Application, actionId: string, action?: ApplicationAction) => {
       incrementUsage(actionId);
-      await launchApplication(app);
+      await launchApplication(app, action);
     },
     [incrementUsage]
   );
@@ -80,21 +146,17 @@ export function useApplications() {
   const actions: Action[] = useMemo(
     () =>
       applications.map((app) => {
-        // Convert file:// URL to asset protocol URL
-        let iconSrc = null;
-        if (app.icon && app.icon.startsWith("file://")) {
-          const filePath = app.icon.replace("file://", "");
-          iconSrc = convertFileSrc(filePath);
-        }
-
+        const iconSrc = toIconSrc(app.icon);
         const actionId = app.path;
         const usageCount = getUsageCount(actionId);
 
         return {
           id: actionId,
           name: app.name,
-          subtitle: app.description,
-          keywords: app.description,
+          subtitle: app.description ?? app.generic_name,
+          keywords: [app.generic_name, app.description, ...app.keywords].filter(
+            (keyword): keyword is string => !!keyword
+          ),
           icon: iconSrc ? (
             <img src={iconSrc} alt={app.name} className="size-6 object-contain" />
           ) : (
@@ -107,6 +169,30 @@ export function useApplications() {
           perform: async () => {
             await handleLaunchApplication(app, actionId);
           },
+          // Desktop actions, e.g. "New Private Window"
+          footerAction:
+            app.actions.length > 0
+              ? (changeVisible) =>
+                  app.actions.map((action) => {
+                    const actionIconSrc = toIconSrc(action.icon) ?? iconSrc;
+                    return {
+                      id: `${actionId}#${action.id}`,
+                      name: action.name,
+                      subtitle: app.name,
+                      icon: actionIconSrc ? (
+                        <img
+                          src={actionIconSrc}
+                          alt={action.name}
+                          className="size-5 object-contain"
+                        />
+                      ) : undefined,
+                      perform: async () => {
+                        changeVisible();
+                        await handleLaunchApplication(app, actionId, action);
+                      },
+                    };
+                  })
+              : undefined,
         };
       }),
     [applications, handleLaunchApplication, getUsageCount]
@@ -1,10 +1,11 @@
 import {useState, useEffect, useCallback, useRef} from "react";
 import {invoke} from "@tauri-apps/api/core";
 import {getCurrentWebviewWindow} from "@tauri-apps/api/webviewWindow";
-import {Action} from "@fzdwx/ruaui";
+import {Action, toast} from "@fzdwx/ruaui";
 import {Icon} from "@iconify/react";
 import {useDebounce} from "ahooks";
 import {DEFAULT_FILE_SEARCH_CONFIG, type FileSearchConfig} from "rua-api";
+import {toIconSrc} from "@/hooks/useApplications";
 
 export interface FileSearchResult {
   path: string;
@@ -12,12 +13,99 @@ export interface FileSearchResult {
   isDirectory: boolean;
 }
 
+/**
+ * An application that can open a file
+ */
+interface OpenWithApplication {
+  /** Desktop file ID */
+  id: string;
+  name: string;
+  icon?: string;
+  isDefault: boolean;
+}
+
+/**
+ * The type of a file and the applications that can open it, default first
+ */
+interface FileHandlers {
+  mimeType: string;
+  applications: OpenWithApplication[];
+}
+
 interface UseFileSearchOptions {
   query: string;
   currentResultsCount: number;
   onFileOpen?: () => void;
 }
 
+/**
+ * Get the applications that can open each file, by path
+ */
+async function loadFileHandlers(files: FileSearchResult[]): Promise<Map<string, FileHandlers>> {
+  const handlers = new Map<string, FileHandlers>();
+  await Promise.all(
+    files.map(async (file) => {
+      try {
+        handlers.set(file.path, await invoke<FileHandlers>("get_file_handlers", {path: file.path}));
+      } catch (error) {
+        console.error("Failed to get applications for file:", error);
+      }
+    })
+  );
+  return handlers;
+}
+
+/**
+ * "Open With" actions for a file, followed by actions making another application the default
+ */
+function openWithActions(
+  file: FileSearchResult,
+  handlers: FileHandlers,
+  changeVisible: () => void,
+  onFileOpen?: () => void
+): Action[] {
+  const appIcon = (app: OpenWithApplication) => {
+    const iconSrc = toIconSrc(app.icon);
+    return iconSrc ? (
+      <img src={iconSrc} alt={app.name} className="size-5 object-contain"/>
+    ) : undefined;
+  };
+
+  const openWith: Action[] = handlers.applications.map((app) => ({
+    id: `file-${file.path}#open-with-${app.id}`,
+    name: `Open with ${app.name}`,
+    subtitle: app.isDefault ? "Default" : undefined,
+    icon: appIcon(app),
+    perform: async () => {
+      changeVisible();
+      try {
+        await invoke("launch_application_with", {appId: app.id, uris: [file.path]});
+        onFileOpen?.();
+      } catch (error) {
+        toast.failure(`Failed to open file: ${error}`);
+      }
+    },
+  }));
+
+  const makeDefault: Action[] = handlers.applications
+    .filter((app) => !app.isDefault)
+    .map((app) => ({
+      id: `file-${file.path}#default-${app.id}`,
+      name: `Always open ${handlers.mimeType} with ${app.name}`,
+      icon: appIcon(app),
+      perform: async () => {
+        changeVisible();
+        try {
+          await invoke("set_default_application", {mimeType: handlers.mimeType, appId: app.id});
+          toast.success(`${app.name} now opens ${handlers.mimeType}`);
+        } catch (error) {
+          toast.failure(`Failed to change the default application: ${error}`);
+        }
+      },
+    }));
+
+  return [...openWith, ...makeDefault];
+}
 
 /**
  * Custom hook for searching files on filesystem
@@ -98,13 +186,14 @@ export function useFileSearch({query, currentResultsCount, onFileOpen}: UseFileS
         const effectiveMaxResults = config.maxResults;
         const effectiveSearchPaths =
           config.customPaths && config.customPaths.length > 0 ? config.customPaths : undefined;
-        const effectiveOpenMethod = config.openMethod ?? "xdg-open";
+        const effectiveOpenMethod = config.openMethod ?? "system";
 
         const searchResults = await invoke<FileSearchResult[]>("search_files", {
           query: searchQuery,
           maxResults: effectiveMaxResults,
           searchPaths: effectiveSearchPaths,
         });
+        const fileHandlers = await loadFileHandlers(searchResults);
 
         const actions: Action[] = searchResults.map((file) => ({
           id: `file-${file.path}`,
@@ -127,6 +216,11 @@ export function useFileSearch({query, currentResultsCount, onFileOpen}: UseFileS
               console.error("Failed to open file:", error);
             }
           },
+          // Other applications that can open the file
+          footerAction: fileHandlers.get(file.path)?.applications.length
+            ? (changeVisible) =>
+              openWithActions(file, fileHandlers.get(file.path)!, changeVisible, onFileOpen)
+            : undefined,
         }));
 
         setFileActions(actions);
@@ -112,7 +112,7 @@ export default function Settings() {
             title: "Open Method",
             description: "Method to open files when selected",
             type: "dropdown",
-            default: "xdg-open",
+            default: "system",
             options: [
               { label: "XDG Open", value: "xdg-open" },
               { label: "System Default", value: "system" },
@@ -128,11 +128,69 @@ export default function Settings() {
           },
         ],
       },
+      {
+        id: "clipboard",
+        name: "Clipboard",
+        type: "system",
+        preferences: [
+          {
+            name: "historyEnabled",
+            title: "Record Clipboard History",
+            description: "Keep copied text and images so they can be searched and copied again",
+            type: "toggle",
+            default: true,
+          },
+          {
+            name: "historyLimit",
+            title: "History Size",
+            description: "Number of entries to keep. Pinned entries are never removed",
+            type: "number",
+            default: 200,
+            min: 10,
+            max: 5000,
+            step: 10,
+          },
+        ],
+      },
       {
         id: "advanced",
         name: "Advanced",
         type: "system",
-        preferences: [],
+        preferences: [
+          {
+            name: "controlServerHost",
+            title: "Control Server Address",
+            description: "Address the control server listens on for ruactl. Takes effect after restarting Rua",
+            type: "textfield",
+            default: "127.0.0.1",
+            placeholder: "127.0.0.1",
+          },
+          {
+            name: "controlServerAllowRemote",
+            title: "Allow Remote Control",
+            description: "Let the control server listen on addresses other than localhost. Requests are sent unencrypted. Takes effect after restarting Rua",
+            type: "toggle",
+            default: false,
+          },
+          {
+            name: "controlServerPort",
+            title: "Control Server Port",
+            description: "Port the control server listens on. Use 0 to pick a free port. Takes effect after restarting Rua",
+            type: "number",
+            default: 7777,
+            min: 0,
+            max: 65535,
+            step: 1,
+          },
+          {
+            name: "rpcAllowedMethods",
+            title: "RPC Allowed Methods",
+            description: "Backend commands callable through the control server's JSON-RPC bridge, separated by commas (e.g. fs_*, get_applications). Use * to allow all",
+            type: "textfield",
+            default: "",
+            placeholder: "fs_*, get_applications",
+          },
+        ],
       },
       {
         id: "about",
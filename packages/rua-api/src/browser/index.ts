@@ -32,7 +32,7 @@ export type { RuaAPI, RuaClientAPI, ExtensionMeta, DynamicAction } from "./rua-a
 export { BaseDirectory } from "./rua-api";
 export type { FsOptions } from "../types";
 
-import { ExtensionMeta, FsOptions } from "../types";
+import { ExtensionMeta, FsOptions, NotificationOptions } from "../types";
 
 export interface CommonRuaAPI {
   extension: ExtensionMeta;
@@ -40,10 +40,29 @@ export interface CommonRuaAPI {
   clipboard: {
     readText(): Promise<string>;
     writeText(text: string): Promise<void>;
+    /** List the MIME types the clipboard content is available in */
+    getFormats(): Promise<string[]>;
+    /** Read the content in a MIME type, null if it isn't available */
+    read(mimeType: string): Promise<Uint8Array | null>;
+    /** Replace the clipboard with content in a MIME type */
+    write(mimeType: string, data: Uint8Array): Promise<void>;
+    /** Read an image as PNG, null if there is none */
+    readImage(): Promise<Uint8Array | null>;
+    /** Copy a PNG image */
+    writeImage(png: Uint8Array): Promise<void>;
+    /** Read the absolute paths of files copied in a file manager */
+    readFiles(): Promise<string[]>;
+    /** Copy files so they can be pasted into a file manager */
+    writeFiles(paths: string[]): Promise<void>;
+    /** Read the text that was selected in other apps when Rua was shown, null if none */
+    readSelection(): Promise<string | null>;
   };
 
   notification: {
-    show(options: { title: string; body?: string }): Promise<void>;
+    /** Show a notification, returning its ID (0 if the notification service is unavailable) */
+    show(options: NotificationOptions): Promise<number>;
+    /** Close a notification shown by this extension */
+    close(id: number): Promise<void>;
   };
 
   storage: {
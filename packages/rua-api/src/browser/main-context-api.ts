@@ -30,15 +30,15 @@ export interface MainContextRuaAPI extends CommonRuaAPI {
     unregister(actionIds: string[]): Promise<void>;
   };
 
-  /** Register an event handler for lifecycle events. Background scripts support: activate, deactivate, action-triggered, search-change */
+  /** Register an event handler for lifecycle events. Background scripts support: activate, deactivate, action-triggered, search-change, notification-action */
   on(
-    event: "activate" | "deactivate" | "action-triggered" | "search-change",
+    event: "activate" | "deactivate" | "action-triggered" | "search-change" | "notification-action",
     handler: EventHandler
   ): void;
 
   /** Unregister an event handler */
   off(
-    event: "activate" | "deactivate" | "action-triggered" | "search-change",
+    event: "activate" | "deactivate" | "action-triggered" | "search-change" | "notification-action",
     handler: EventHandler
   ): void;
 }
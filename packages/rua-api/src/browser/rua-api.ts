@@ -6,7 +6,7 @@
  */
 
 import { IframeChildIO, RPCChannel } from "kkrpc/browser";
-import type { RuaClientAPI, EventHandler, FsOptions } from "../types";
+import type { RuaClientAPI, EventHandler, FsOptions, NotificationActionData } from "../types";
 import { RuaServerAPI } from "../types/rua";
 
 // Re-export types for convenience
@@ -108,6 +108,16 @@ export async function initializeRuaAPI(): Promise<RuaClientAPI> {
             }
           });
         },
+        onNotificationAction: async (data: NotificationActionData) => {
+          const handlers = eventHandlers.get("notification-action") || [];
+          handlers.forEach((handler) => {
+            try {
+              handler(data);
+            } catch (e) {
+              console.error("[Rua API] Event handler error:", e);
+            }
+          });
+        },
       },
     });
 
@@ -130,10 +140,25 @@ export async function initializeRuaAPI(): Promise<RuaClientAPI> {
       clipboard: {
         readText: () => hostAPI.clipboardReadText(),
         writeText: (text) => hostAPI.clipboardWriteText(text),
+        getFormats: () => hostAPI.clipboardGetFormats(),
+        read: async (mimeType) => {
+          const data = await hostAPI.clipboardRead(mimeType);
+          return data ? new Uint8Array(data) : null;
+        },
+        write: (mimeType, data) => hostAPI.clipboardWrite(mimeType, data),
+        readImage: async () => {
+          const data = await hostAPI.clipboardReadImage();
+          return data ? new Uint8Array(data) : null;
+        },
+        writeImage: (png) => hostAPI.clipboardWriteImage(png),
+        readFiles: () => hostAPI.clipboardReadFiles(),
+        writeFiles: (paths) => hostAPI.clipboardWriteFiles(paths),
+        readSelection: () => hostAPI.clipboardReadSelection(),
       },
 
       notification: {
         show: (options) => hostAPI.notificationShow(options),
+        close: (id) => hostAPI.notificationClose(id),
       },
 
       storage: {
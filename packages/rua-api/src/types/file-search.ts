@@ -12,7 +12,7 @@ export interface FileSearchConfig {
   maxResults: number;
   /** Minimum number of app/extension results before showing file search */
   threshold: number;
-  /** Method to open files: "xdg-open" or "system" */
+  /** Method to open files: "system" (default application of the type) or "xdg-open" */
   openMethod: "xdg-open" | "system";
   /** Custom search paths (array of directory paths) */
   customPaths: string[];
@@ -25,6 +25,6 @@ export const DEFAULT_FILE_SEARCH_CONFIG: FileSearchConfig = {
   enabled: true,
   maxResults: 20,
   threshold: 5,
-  openMethod: "xdg-open",
+  openMethod: "system",
   customPaths: [],
 };
@@ -42,6 +42,9 @@ export type {
   FsOptions,
   ActionTriggeredData,
   SearchChangeData,
+  NotificationAction,
+  NotificationOptions,
+  NotificationActionData,
   BackgroundScriptState,
   BackgroundScriptCallbacks,
 } from "./rua";
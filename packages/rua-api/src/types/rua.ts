@@ -100,6 +100,33 @@ export interface SearchChangeData {
   query: string;
 }
 
+/** A button on a notification */
+export interface NotificationAction {
+  /** Reported back in notification-action events, "default" for clicking the notification itself */
+  id: string;
+  label: string;
+}
+
+/** Notification content and settings */
+export interface NotificationOptions {
+  title: string;
+  body?: string;
+  /** Icon name from the icon theme or an absolute path */
+  icon?: string;
+  urgency?: "low" | "normal" | "critical";
+  /** Milliseconds until the notification expires, 0 to never expire */
+  timeout?: number;
+  /** ID of a notification to replace, as returned by show() */
+  replaceId?: number;
+  actions?: NotificationAction[];
+}
+
+/** Notification action event data */
+export interface NotificationActionData {
+  notificationId: number;
+  actionId: string;
+}
+
 /** Shell command execution result */
 export interface ShellResult {
   /** Whether the command succeeded */
@@ -197,15 +224,15 @@ export interface RuaClientAPI extends CommonRuaAPI {
     remove(key: string): Promise<void>;
   };
 
-  /** Register event handler. View mode extensions support: activate, deactivate, action-triggered, theme-change */
+  /** Register event handler. View mode extensions support: activate, deactivate, action-triggered, theme-change, notification-action */
   on(
-    event: "activate" | "deactivate" | "action-triggered" | "theme-change",
+    event: "activate" | "deactivate" | "action-triggered" | "theme-change" | "notification-action",
     handler: EventHandler
   ): void;
 
   /** Unregister event handler */
   off(
-    event: "activate" | "deactivate" | "action-triggered" | "theme-change",
+    event: "activate" | "deactivate" | "action-triggered" | "theme-change" | "notification-action",
     handler: EventHandler
   ): void;
 }
@@ -223,8 +250,26 @@ export interface RuaServerAPI {
 
   clipboardWriteText(text: string): Promise<void>;
 
+  clipboardGetFormats(): Promise<string[]>;
+
+  clipboardRead(mimeType: string): Promise<Uint8Array | null>;
+
+  clipboardWrite(mimeType: string, data: Uint8Array): Promise<void>;
+
+  clipboardReadImage(): Promise<Uint8Array | null>;
+
+  clipboardWriteImage(data: Uint8Array): Promise<void>;
+
+  clipboardReadFiles(): Promise<string[]>;
+
+  clipboardWriteFiles(paths: string[]): Promise<void>;
+
+  clipboardReadSelection(): Promise<string | null>;
+
   // Notification API
-  notificationShow(options: { title: string; body?: string }): Promise<void>;
+  notificationShow(options: NotificationOptions): Promise<number>;
+
+  notificationClose(id: number): Promise<void>;
 
   // Storage API
   storageGet(key: string): Promise<string | null>;
@@ -308,6 +353,8 @@ export interface RuaClientCallbacks {
   onDeactivate?: () => void;
   /** Called when the application theme changes */
   onThemeChange?: (theme: "light" | "dark") => void;
+  /** Called when an action button of a notification shown by the extension is clicked */
+  onNotificationAction?: (data: NotificationActionData) => void;
 }
 
 /** Parsed permission with allow rules */
@@ -345,6 +392,7 @@ export interface BackgroundScriptState {
   deactivateCallbacks: Set<() => void>;
   actionTriggeredCallbacks: Set<(data: ActionTriggeredData) => void>;
   searchChangeCallbacks: Set<(data: SearchChangeData) => void>;
+  notificationActionCallbacks: Set<(data: NotificationActionData) => void>;
   registeredActions: string[];
 }
 